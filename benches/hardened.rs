@@ -0,0 +1,48 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Measures the overhead of the hardened combiner over the plain sum
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use hash_that_set::{hash_by_summing_hashes, hash_by_summing_hashes_hardened};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::Hasher;
+
+fn combiners(c: &mut Criterion) {
+    let mut group = c.benchmark_group("combiner");
+    for size in [16_u64, 1024, 65536] {
+        let set: HashSet<u64> = (0..size).collect();
+        group.bench_with_input(BenchmarkId::new("plain", size), &set, |b, set| {
+            b.iter(|| {
+                let mut hasher = DefaultHasher::new();
+                hash_by_summing_hashes::<HashSet<u64>, _>(black_box(set), &mut hasher);
+                hasher.finish()
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("hardened", size), &set, |b, set| {
+            b.iter(|| {
+                let mut hasher = DefaultHasher::new();
+                hash_by_summing_hashes_hardened::<HashSet<u64>, _>(black_box(set), &mut hasher);
+                hasher.finish()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, combiners);
+criterion_main!(benches);
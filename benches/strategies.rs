@@ -0,0 +1,385 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Compares per-element hashing strategies and combiners, at several collection sizes
+//!
+//! Run with `cargo bench --bench strategies --features hashbrown,xxh3,dashmap` to include the
+//! strategies behind feature flags; `--all-features` would also enable the nightly-only
+//! features, and so needs a nightly toolchain. The largest size is 10 million elements, which
+//! needs several gigabytes of memory for the string sets; set `HASH_THAT_SET_BENCH_MAX` to a
+//! smaller number of elements to leave out larger sizes. A new strategy is added by a
+//! `bench_with_input` call in each of the collection groups.
+
+use criterion::measurement::WallTime;
+use criterion::{
+    black_box, criterion_group, criterion_main, BenchmarkGroup, BenchmarkId, Criterion, Throughput,
+};
+use hash_that_set::{
+    hash_by_summing_hashes_hardened, hash_by_summing_hashes_with, hash_bytes_by_summing,
+    hash_primitive_slice_by_summing, BuildHasherFromFriend, Domain, DomainTag, HashCombiner,
+    OneShotStrategy, PartialHash, PrecomputedHashes, Prehashed, SortedCombiner, SumCombiner,
+    UseDefaultHasher, UseIdentityMix, UsePrehashed, UseProvidedHasher, UseStableHasher,
+    UseTaggedHasher, XorCombiner,
+};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+struct Bench;
+
+impl Domain for Bench {
+    const TAG: DomainTag = DomainTag::new("bench");
+}
+
+fn sizes() -> Vec<usize> {
+    let max = std::env::var("HASH_THAT_SET_BENCH_MAX")
+        .ok()
+        .and_then(|max| max.parse().ok())
+        .unwrap_or(usize::MAX);
+    [1_000, 100_000, 10_000_000]
+        .into_iter()
+        .filter(|size| *size <= max)
+        .collect()
+}
+
+fn configure(group: &mut BenchmarkGroup<'_, WallTime>, size: usize) {
+    group.throughput(Throughput::Elements(size as u64));
+    group.sample_size(if size >= 1_000_000 { 10 } else { 100 });
+}
+
+fn digest<C, BH>(collection: &C) -> u64
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash,
+    BH: BuildHasherFromFriend<C>,
+{
+    let mut hasher = DefaultHasher::new();
+    hash_by_summing_hashes_with::<C, _, BH>(collection, &mut hasher);
+    hasher.finish()
+}
+
+fn one_shot<C, S>(collection: &C) -> u64
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: AsRef<[u8]>,
+    S: OneShotStrategy,
+{
+    let mut hasher = DefaultHasher::new();
+    hash_bytes_by_summing::<C, S, _>(collection, &mut hasher);
+    hasher.finish()
+}
+
+fn hardened<C>(collection: &C) -> u64
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash,
+{
+    let mut hasher = DefaultHasher::new();
+    hash_by_summing_hashes_hardened::<C, _>(collection, &mut hasher);
+    hasher.finish()
+}
+
+fn u64_sets(c: &mut Criterion) {
+    type Set = HashSet<u64>;
+    let mut group = c.benchmark_group("u64_set");
+    for size in sizes() {
+        configure(&mut group, size);
+        let set: Set = (0..size as u64).collect();
+        group.bench_with_input(BenchmarkId::new("default", size), &set, |b, set| {
+            b.iter(|| digest::<Set, UseDefaultHasher>(black_box(set)))
+        });
+        group.bench_with_input(BenchmarkId::new("provided", size), &set, |b, set| {
+            b.iter(|| digest::<Set, UseProvidedHasher<Set>>(black_box(set)))
+        });
+        group.bench_with_input(BenchmarkId::new("stable", size), &set, |b, set| {
+            b.iter(|| digest::<Set, UseStableHasher>(black_box(set)))
+        });
+        group.bench_with_input(BenchmarkId::new("tagged", size), &set, |b, set| {
+            b.iter(|| digest::<Set, UseTaggedHasher<Bench>>(black_box(set)))
+        });
+        group.bench_with_input(BenchmarkId::new("hardened", size), &set, |b, set| {
+            b.iter(|| hardened::<Set>(black_box(set)))
+        });
+        group.bench_with_input(BenchmarkId::new("identity_mix", size), &set, |b, set| {
+            b.iter(|| digest::<Set, UseIdentityMix>(black_box(set)))
+        });
+        #[cfg(feature = "hashbrown")]
+        {
+            type Brown = hashbrown::HashSet<u64>;
+            let set: Brown = set.iter().copied().collect();
+            group.bench_with_input(BenchmarkId::new("hashbrown", size), &set, |b, set| {
+                b.iter(|| digest::<Brown, UseProvidedHasher<Brown>>(black_box(set)))
+            });
+        }
+    }
+    group.finish();
+}
+
+fn string_sets(c: &mut Criterion) {
+    type Set = HashSet<String>;
+    for (name, length) in [("short_string_set", 8), ("long_string_set", 128)] {
+        let mut group = c.benchmark_group(name);
+        for size in sizes() {
+            configure(&mut group, size);
+            let set: Set = (0..size)
+                .map(|index| format!("{:0>width$}", index, width = length))
+                .collect();
+            group.bench_with_input(BenchmarkId::new("default", size), &set, |b, set| {
+                b.iter(|| digest::<Set, UseDefaultHasher>(black_box(set)))
+            });
+            group.bench_with_input(
+                BenchmarkId::new("default_one_shot", size),
+                &set,
+                |b, set| b.iter(|| one_shot::<Set, UseDefaultHasher>(black_box(set))),
+            );
+            #[cfg(feature = "xxh3")]
+            {
+                group.bench_with_input(BenchmarkId::new("xxh3", size), &set, |b, set| {
+                    b.iter(|| digest::<Set, hash_that_set::UseXxh3>(black_box(set)))
+                });
+                group.bench_with_input(BenchmarkId::new("xxh3_one_shot", size), &set, |b, set| {
+                    b.iter(|| one_shot::<Set, hash_that_set::UseXxh3>(black_box(set)))
+                });
+            }
+            group.bench_with_input(BenchmarkId::new("provided", size), &set, |b, set| {
+                b.iter(|| digest::<Set, UseProvidedHasher<Set>>(black_box(set)))
+            });
+            group.bench_with_input(BenchmarkId::new("stable", size), &set, |b, set| {
+                b.iter(|| digest::<Set, UseStableHasher>(black_box(set)))
+            });
+            group.bench_with_input(BenchmarkId::new("hardened", size), &set, |b, set| {
+                b.iter(|| hardened::<Set>(black_box(set)))
+            });
+            #[cfg(feature = "hashbrown")]
+            {
+                type Brown = hashbrown::HashSet<String>;
+                let set: Brown = set.into_iter().collect();
+                group.bench_with_input(BenchmarkId::new("hashbrown", size), &set, |b, set| {
+                    b.iter(|| digest::<Brown, UseProvidedHasher<Brown>>(black_box(set)))
+                });
+            }
+        }
+        group.finish();
+    }
+}
+
+fn u64_maps(c: &mut Criterion) {
+    type Map = HashMap<u64, u64>;
+    let mut group = c.benchmark_group("u64_map");
+    for size in sizes() {
+        configure(&mut group, size);
+        let map: Map = (0..size as u64).map(|key| (key, !key)).collect();
+        group.bench_with_input(BenchmarkId::new("default", size), &map, |b, map| {
+            b.iter(|| digest::<Map, UseDefaultHasher>(black_box(map)))
+        });
+        group.bench_with_input(BenchmarkId::new("provided", size), &map, |b, map| {
+            b.iter(|| digest::<Map, UseProvidedHasher<Map>>(black_box(map)))
+        });
+        group.bench_with_input(BenchmarkId::new("stable", size), &map, |b, map| {
+            b.iter(|| digest::<Map, UseStableHasher>(black_box(map)))
+        });
+        group.bench_with_input(BenchmarkId::new("hardened", size), &map, |b, map| {
+            b.iter(|| hardened::<Map>(black_box(map)))
+        });
+        #[cfg(feature = "dashmap")]
+        {
+            let map: dashmap::DashMap<u64, u64> = map.into_iter().collect();
+            group.bench_with_input(BenchmarkId::new("dashmap", size), &map, |b, map| {
+                b.iter(|| {
+                    let mut hasher = DefaultHasher::new();
+                    hash_that_set::hash_dashmap(black_box(map), &mut hasher);
+                    hasher.finish()
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+fn combiners(c: &mut Criterion) {
+    let mut group = c.benchmark_group("combiner");
+    for size in sizes() {
+        configure(&mut group, size);
+        let precomputed: PrecomputedHashes<u64> = (0..size as u64).collect();
+        group.bench_with_input(BenchmarkId::new("sum", size), &precomputed, |b, hashes| {
+            b.iter(|| black_box(hashes).recombine_with::<SumCombiner>())
+        });
+        group.bench_with_input(BenchmarkId::new("xor", size), &precomputed, |b, hashes| {
+            b.iter(|| black_box(hashes).recombine_with::<XorCombiner>())
+        });
+        group.bench_with_input(
+            BenchmarkId::new("sorted", size),
+            &precomputed,
+            |b, hashes| b.iter(|| black_box(hashes).recombine_with::<SortedCombiner>()),
+        );
+    }
+    group.finish();
+}
+
+/// Combining many small sets, where the sorted combiner does not allocate
+fn small_sets(c: &mut Criterion) {
+    // Collects into a Vec per set, as the sorted combiner did before its inline buffer
+    fn sorted_collected(hashes: &[u64]) -> u64 {
+        let mut hashes = hashes.to_vec();
+        hashes.sort_unstable();
+        let mut hasher = DefaultHasher::new();
+        hashes.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    let mut group = c.benchmark_group("sorted_combiner_ten_element_sets");
+    for size in sizes() {
+        let sets = size / 10;
+        configure(&mut group, sets);
+        let hashes: Vec<u64> = (0..size as u64)
+            .map(|n| n.wrapping_mul(0x9e37_79b9_7f4a_7c15))
+            .collect();
+        group.bench_with_input(BenchmarkId::new("inline", sets), &hashes, |b, hashes| {
+            b.iter(|| {
+                black_box(hashes)
+                    .chunks(10)
+                    .map(|set| SortedCombiner::combine(set.iter().copied()))
+                    .fold(0, u64::wrapping_add)
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("vec", sets), &hashes, |b, hashes| {
+            b.iter(|| {
+                black_box(hashes)
+                    .chunks(10)
+                    .map(sorted_collected)
+                    .fold(0, u64::wrapping_add)
+            })
+        });
+    }
+    group.finish();
+}
+
+/// The chunked accumulation over slices of integers, against the per-element loop
+fn primitive_slices(c: &mut Criterion) {
+    let mut group = c.benchmark_group("primitive_slice");
+    for size in sizes() {
+        configure(&mut group, size);
+        let values: Vec<u64> = (0..size as u64).collect();
+        group.bench_with_input(BenchmarkId::new("u64_loop", size), &values, |b, values| {
+            b.iter(|| digest::<Vec<u64>, UseIdentityMix>(black_box(values)))
+        });
+        group.bench_with_input(
+            BenchmarkId::new("u64_chunked", size),
+            &values,
+            |b, values| {
+                b.iter(|| {
+                    let mut hasher = DefaultHasher::new();
+                    hash_primitive_slice_by_summing(black_box(values.as_slice()), &mut hasher);
+                    hasher.finish()
+                })
+            },
+        );
+        let values: Vec<u32> = (0..size as u32).collect();
+        group.bench_with_input(BenchmarkId::new("u32_loop", size), &values, |b, values| {
+            b.iter(|| digest::<Vec<u32>, UseIdentityMix>(black_box(values)))
+        });
+        group.bench_with_input(
+            BenchmarkId::new("u32_chunked", size),
+            &values,
+            |b, values| {
+                b.iter(|| {
+                    let mut hasher = DefaultHasher::new();
+                    hash_primitive_slice_by_summing(black_box(values.as_slice()), &mut hasher);
+                    hasher.finish()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+/// Re-digesting a set of 4 KiB strings, against the same set with prehashed elements
+fn prehashed(c: &mut Criterion) {
+    let mut group = c.benchmark_group("prehashed_4k_string_set");
+    for size in [1_000, 10_000] {
+        configure(&mut group, size);
+        let set: HashSet<String> = (0..size).map(|index| format!("{:0>4096}", index)).collect();
+        group.bench_with_input(BenchmarkId::new("raw", size), &set, |b, set| {
+            b.iter(|| digest::<HashSet<String>, UseDefaultHasher>(black_box(set)))
+        });
+        let set: HashSet<Prehashed<String>> = set.into_iter().map(Prehashed::new).collect();
+        group.bench_with_input(BenchmarkId::new("prehashed", size), &set, |b, set| {
+            b.iter(|| digest::<HashSet<Prehashed<String>>, UsePrehashed>(black_box(set)))
+        });
+    }
+    group.finish();
+}
+
+fn hash_one(value: u64) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Replacing one element: adjusting the raw sum by the old and new element hashes and
+/// finalizing it, against rehashing the whole collection
+fn incremental_update(c: &mut Criterion) {
+    type Set = HashSet<u64>;
+    let mut group = c.benchmark_group("update_one");
+    for size in sizes() {
+        group.sample_size(if size >= 1_000_000 { 10 } else { 100 });
+        let mut set: Set = (0..size as u64).collect();
+        let sum_before = PartialHash::of::<Set>(&set).sum;
+        set.remove(&0);
+        set.insert(size as u64);
+        let expected = digest::<Set, UseDefaultHasher>(&set);
+        let finalize = |sum: u64| {
+            let mut hasher = DefaultHasher::new();
+            hasher.write_u64(sum);
+            hasher.finish()
+        };
+        let updated = sum_before
+            .wrapping_sub(hash_one(0))
+            .wrapping_add(hash_one(size as u64));
+        assert_eq!(expected, finalize(updated));
+        group.bench_with_input(
+            BenchmarkId::new("incremental", size),
+            &sum_before,
+            |b, sum| {
+                b.iter(|| {
+                    finalize(
+                        black_box(*sum)
+                            .wrapping_sub(hash_one(black_box(0)))
+                            .wrapping_add(hash_one(black_box(size as u64))),
+                    )
+                })
+            },
+        );
+        group.bench_with_input(BenchmarkId::new("recompute", size), &set, |b, set| {
+            b.iter(|| digest::<Set, UseDefaultHasher>(black_box(set)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    u64_sets,
+    string_sets,
+    u64_maps,
+    combiners,
+    small_sets,
+    primitive_slices,
+    prehashed,
+    incremental_update
+);
+criterion_main!(benches);
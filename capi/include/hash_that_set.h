@@ -0,0 +1,50 @@
+#ifndef HASH_THAT_SET_H
+#define HASH_THAT_SET_H
+
+/* Generated by cbindgen from src/lib.rs. Do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// A digest which is built incrementally, from element hashes
+typedef struct HtsDigest HtsDigest;
+
+// Creates an empty digest. It must be released with `hts_digest_finish`.
+struct HtsDigest *hts_digest_new(void);
+
+// Adds the hash of an element to the digest. Does nothing if `digest` is null.
+//
+// # Safety
+//
+// `digest` must be null, or returned by `hts_digest_new` and not yet finished.
+void hts_digest_add_element_hash(struct HtsDigest *digest, uint64_t hash);
+
+// Removes the hash of an element which was added to the digest. Does nothing if `digest` is
+// null.
+//
+// # Safety
+//
+// `digest` must be null, or returned by `hts_digest_new` and not yet finished.
+void hts_digest_remove_element_hash(struct HtsDigest *digest, uint64_t hash);
+
+// Releases the digest, returning its value. Returns 0, the digest of no elements, if `digest`
+// is null.
+//
+// # Safety
+//
+// `digest` must be null, or returned by `hts_digest_new` and not yet finished. It must not be
+// used afterwards.
+uint64_t hts_digest_finish(struct HtsDigest *digest);
+
+// Computes the digest of `len` element hashes at `hashes` in one call. `hashes` may be null if
+// `len` is 0.
+//
+// # Safety
+//
+// Unless `len` is 0, `hashes` must point to `len` consecutive, initialized `uint64_t` values.
+uint64_t hts_digest_combine(const uint64_t *hashes, size_t len);
+
+#endif  /* HASH_THAT_SET_H */
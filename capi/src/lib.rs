@@ -0,0 +1,93 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! C ABI for computing digests from other languages
+//!
+//! Every function matches [`hash_that_set::combine_precomputed_hashes`]: a digest is the
+//! wrapping sum of its element hashes, so services in other languages produce byte-compatible
+//! digests by computing element hashes the same way. The header `include/hash_that_set.h` is
+//! generated from this file by cbindgen.
+//!
+//! This is a separate crate because the library forbids unsafe code, which a C ABI taking raw
+//! pointers requires.
+
+use hash_that_set::combine_precomputed_hashes;
+use std::num::Wrapping;
+
+/// A digest which is built incrementally, from element hashes
+pub struct HtsDigest {
+    sum: Wrapping<u64>,
+}
+
+/// Creates an empty digest. It must be released with `hts_digest_finish`.
+#[no_mangle]
+pub extern "C" fn hts_digest_new() -> *mut HtsDigest {
+    Box::into_raw(Box::new(HtsDigest { sum: Wrapping(0) }))
+}
+
+/// Adds the hash of an element to the digest. Does nothing if `digest` is null.
+///
+/// # Safety
+///
+/// `digest` must be null, or returned by `hts_digest_new` and not yet finished.
+#[no_mangle]
+pub unsafe extern "C" fn hts_digest_add_element_hash(digest: *mut HtsDigest, hash: u64) {
+    if let Some(digest) = digest.as_mut() {
+        digest.sum += hash;
+    }
+}
+
+/// Removes the hash of an element which was added to the digest. Does nothing if `digest` is
+/// null.
+///
+/// # Safety
+///
+/// `digest` must be null, or returned by `hts_digest_new` and not yet finished.
+#[no_mangle]
+pub unsafe extern "C" fn hts_digest_remove_element_hash(digest: *mut HtsDigest, hash: u64) {
+    if let Some(digest) = digest.as_mut() {
+        digest.sum -= hash;
+    }
+}
+
+/// Releases the digest, returning its value. Returns 0, the digest of no elements, if `digest`
+/// is null.
+///
+/// # Safety
+///
+/// `digest` must be null, or returned by `hts_digest_new` and not yet finished. It must not be
+/// used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn hts_digest_finish(digest: *mut HtsDigest) -> u64 {
+    if digest.is_null() {
+        return 0;
+    }
+    Box::from_raw(digest).sum.0
+}
+
+/// Computes the digest of `len` element hashes at `hashes` in one call. `hashes` may be null if
+/// `len` is 0.
+///
+/// # Safety
+///
+/// Unless `len` is 0, `hashes` must point to `len` consecutive, initialized `uint64_t` values.
+#[no_mangle]
+pub unsafe extern "C" fn hts_digest_combine(hashes: *const u64, len: usize) -> u64 {
+    if len == 0 || hashes.is_null() {
+        return 0;
+    }
+    combine_precomputed_hashes(std::slice::from_raw_parts(hashes, len))
+}
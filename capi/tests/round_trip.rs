@@ -0,0 +1,82 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Calls the exported functions through the C ABI, as a C program would
+
+use hash_that_set::combine_precomputed_hashes;
+use std::ptr;
+
+// Links the library, whose symbols are otherwise unreferenced
+use hash_that_set_capi as _;
+
+#[repr(C)]
+struct HtsDigest {
+    _private: [u8; 0],
+}
+
+extern "C" {
+    fn hts_digest_new() -> *mut HtsDigest;
+    fn hts_digest_add_element_hash(digest: *mut HtsDigest, hash: u64);
+    fn hts_digest_remove_element_hash(digest: *mut HtsDigest, hash: u64);
+    fn hts_digest_finish(digest: *mut HtsDigest) -> u64;
+    fn hts_digest_combine(hashes: *const u64, len: usize) -> u64;
+}
+
+const HASHES: [u64; 4] = [3, u64::MAX, 0x9e37_79b9_7f4a_7c15, 42];
+
+#[test]
+fn incremental_digest_matches_combine_precomputed_hashes() {
+    let digest = unsafe {
+        let digest = hts_digest_new();
+        for hash in HASHES.iter().rev() {
+            hts_digest_add_element_hash(digest, *hash);
+        }
+        hts_digest_finish(digest)
+    };
+    assert_eq!(combine_precomputed_hashes(&HASHES), digest);
+}
+
+#[test]
+fn removing_an_element_hash_undoes_adding_it() {
+    let digest = unsafe {
+        let digest = hts_digest_new();
+        for hash in HASHES {
+            hts_digest_add_element_hash(digest, hash);
+        }
+        hts_digest_add_element_hash(digest, 7);
+        hts_digest_remove_element_hash(digest, 7);
+        hts_digest_finish(digest)
+    };
+    assert_eq!(combine_precomputed_hashes(&HASHES), digest);
+}
+
+#[test]
+fn one_shot_combine_matches_combine_precomputed_hashes() {
+    let digest = unsafe { hts_digest_combine(HASHES.as_ptr(), HASHES.len()) };
+    assert_eq!(combine_precomputed_hashes(&HASHES), digest);
+}
+
+#[test]
+fn empty_and_null_inputs_give_the_empty_digest() {
+    unsafe {
+        assert_eq!(0, hts_digest_combine(ptr::null(), 0));
+        assert_eq!(0, hts_digest_finish(hts_digest_new()));
+        assert_eq!(0, hts_digest_finish(ptr::null_mut()));
+        hts_digest_add_element_hash(ptr::null_mut(), 1);
+        hts_digest_remove_element_hash(ptr::null_mut(), 1);
+    }
+    assert_eq!(0, combine_precomputed_hashes(&[]));
+}
@@ -0,0 +1,74 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Deduplicates incoming batches of groups against a cache of previously seen groups
+//!
+//! Run with `cargo run --example dedup_cache`. Each group is a set of item IDs, and the cache
+//! is a `HashSet` of wrapped sets. Incoming groups are looked up by borrowing them as keys, so
+//! only the groups which are new are moved into the cache, and none are cloned.
+
+use hash_that_set::SumHashes;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::BuildHasherDefault;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+struct ItemId(u32);
+
+/// Groups share a deterministic hasher, so that equal groups hash equally. A `RandomState`
+/// would be seeded differently for each group.
+type Group = HashSet<ItemId, BuildHasherDefault<DefaultHasher>>;
+
+#[derive(Default)]
+struct DedupCache {
+    seen: HashSet<SumHashes<Group>>,
+}
+
+impl DedupCache {
+    /// Adds the groups of a batch which have not been seen, returning how many were new
+    fn ingest(&mut self, batch: Vec<Group>) -> usize {
+        let mut new = 0;
+        for group in batch {
+            if !SumHashes::<Group>::contains_equivalent(&self.seen, &group) {
+                self.seen.insert(SumHashes::new(group));
+                new += 1;
+            }
+        }
+        new
+    }
+}
+
+fn group(ids: &[u32]) -> Group {
+    ids.iter().copied().map(ItemId).collect()
+}
+
+fn main() {
+    let batches = vec![
+        vec![group(&[1, 2, 3]), group(&[4, 5]), group(&[6])],
+        // The same groups as the first batch, with items in other orders
+        vec![group(&[3, 2, 1]), group(&[5, 4])],
+        vec![group(&[1, 2]), group(&[6]), group(&[4, 5, 6]), group(&[])],
+    ];
+
+    let mut cache = DedupCache::default();
+    for (index, batch) in batches.into_iter().enumerate() {
+        let total = batch.len();
+        let new = cache.ingest(batch);
+        println!("batch {index}: {new} new, {} duplicate groups", total - new);
+    }
+    println!("{} distinct groups seen", cache.seen.len());
+    assert!(cache.seen.contains(SumHashes::as_key(&group(&[2, 1, 3]))));
+}
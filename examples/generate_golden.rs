@@ -0,0 +1,115 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Regenerates the golden-vector corpus in `tests/golden/`
+//!
+//! Run with `cargo run --example generate_golden --features xxh3`. The corpus guards against
+//! accidental changes to the digests: regenerate it only to add cases or digests, and review
+//! that no existing digest changed.
+
+#[path = "../tests/support/golden.rs"]
+mod golden;
+
+use golden::{digests, Case, Corpus};
+use serde::Serialize;
+use std::hash::Hash;
+use std::path::Path;
+
+fn corpus<T: Hash + Clone>(element_type: &str, cases: Vec<(&str, Vec<T>)>) -> Corpus<T> {
+    Corpus {
+        element_type: element_type.to_owned(),
+        cases: cases
+            .into_iter()
+            .map(|(name, elements)| Case {
+                name: name.to_owned(),
+                digests: digests(&elements),
+                elements,
+            })
+            .collect(),
+    }
+}
+
+fn write<T: Serialize>(file: &str, corpus: &Corpus<T>) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(file);
+    let mut json = serde_json::to_string_pretty(corpus).expect("the corpus serializes");
+    json.push('\n');
+    std::fs::write(&path, json).expect("the corpus is writable");
+    println!("wrote {}", path.display());
+}
+
+fn main() {
+    write(
+        "ints.json",
+        &corpus::<u64>(
+            "u64",
+            vec![
+                ("empty", vec![]),
+                ("single", vec![0]),
+                ("small", vec![1, 2, 3]),
+                ("reordered", vec![3, 1, 2]),
+                ("duplicates", vec![7, 7, 7]),
+                ("extremes", vec![u64::MAX, 1 << 63, 0]),
+            ],
+        ),
+    );
+    write(
+        "strings.json",
+        &corpus::<String>(
+            "string",
+            vec![
+                ("empty", vec![]),
+                ("empty_string", vec![String::new()]),
+                (
+                    "words",
+                    ["alpha", "beta", "gamma"].map(String::from).to_vec(),
+                ),
+                (
+                    "reordered",
+                    ["gamma", "alpha", "beta"].map(String::from).to_vec(),
+                ),
+                (
+                    "unicode",
+                    ["größe", "日本", "🦀"].map(String::from).to_vec(),
+                ),
+                ("concatenation", ["ab", "c"].map(String::from).to_vec()),
+                ("split", ["a", "bc"].map(String::from).to_vec()),
+            ],
+        ),
+    );
+    write(
+        "pairs.json",
+        &corpus::<(u32, String)>(
+            "(u32, string)",
+            vec![
+                ("empty", vec![]),
+                (
+                    "entries",
+                    vec![(1, "one".to_owned()), (2, "two".to_owned())],
+                ),
+                (
+                    "swapped_values",
+                    vec![(1, "two".to_owned()), (2, "one".to_owned())],
+                ),
+                (
+                    "repeated_key",
+                    vec![(5, "a".to_owned()), (5, "b".to_owned())],
+                ),
+            ],
+        ),
+    );
+}
@@ -0,0 +1,52 @@
+#![no_main]
+
+//! The sum is a homomorphism from multisets under union to integers under wrapping addition:
+//! digests of shards merge into the digest of the whole, and removing a shard subtracts its
+//! digest
+
+use arbitrary::Arbitrary;
+use hash_that_set::{merge_partial_hashes, PartialHash};
+use hash_that_set_fuzz::{reference_digest, Element};
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Arbitrary, Debug)]
+struct Sharded {
+    shards: Vec<Vec<Element>>,
+    /// The shard to leave out, modulo the number of shards
+    left_out: u8,
+}
+
+fuzz_target!(|sharded: Sharded| {
+    let whole: Vec<Element> = sharded.shards.iter().flatten().cloned().collect();
+    let partials: Vec<PartialHash> = sharded
+        .shards
+        .iter()
+        .map(|shard| PartialHash::of::<Vec<Element>>(shard))
+        .collect();
+    let merged = merge_partial_hashes(&partials);
+    assert_eq!(reference_digest(&whole), merged);
+    assert_eq!(
+        whole.len(),
+        partials.iter().map(|partial| partial.count).sum::<usize>()
+    );
+
+    if !partials.is_empty() {
+        let left_out = usize::from(sharded.left_out) % partials.len();
+        let mut rest = partials.clone();
+        let removed = rest.remove(left_out);
+        assert_eq!(
+            merged.wrapping_sub(removed.sum),
+            merge_partial_hashes(&rest)
+        );
+
+        // Merging in two steps, with the first step's result as a partial hash of its own
+        let (front, back) = partials.split_at(left_out);
+        let front = PartialHash {
+            sum: merge_partial_hashes(front),
+            count: front.iter().map(|partial| partial.count).sum(),
+        };
+        let mut two_step = vec![front];
+        two_step.extend_from_slice(back);
+        assert_eq!(merged, merge_partial_hashes(&two_step));
+    }
+});
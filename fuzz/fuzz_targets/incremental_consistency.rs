@@ -0,0 +1,36 @@
+#![no_main]
+
+//! A digest which is updated incrementally, by adding the hash of each inserted element and
+//! subtracting that of each removed one, must always equal the digest recomputed from scratch
+
+use hash_that_set::{delta_hash, hash_by_summing_hashes, PrecomputedHashes, SumCombiner};
+use hash_that_set_fuzz::{hash_one, replay, Element, Op};
+use libfuzzer_sys::fuzz_target;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hasher;
+
+fuzz_target!(|ops: Vec<Op>| {
+    let mut cached = 0_u64;
+    let mut baseline = (0_u64, 0_usize);
+    let elements = replay(&ops, |elements, element, inserted| {
+        cached = if inserted {
+            cached.wrapping_add(hash_one(element))
+        } else {
+            cached.wrapping_sub(hash_one(element))
+        };
+        let elements = elements.to_vec();
+        let mut recomputed = DefaultHasher::new();
+        hash_by_summing_hashes::<Vec<Element>, _>(&elements, &mut recomputed);
+        let mut expected = DefaultHasher::new();
+        expected.write_u64(cached);
+        assert_eq!(expected.finish(), recomputed.finish());
+
+        let report = delta_hash::<Vec<Element>>(&elements, baseline.0, baseline.1);
+        assert_eq!(cached, report.hash());
+        assert_eq!(if inserted { 1 } else { -1 }, report.count_delta());
+        baseline = (report.hash(), report.count());
+    });
+
+    let precomputed: PrecomputedHashes<Element> = elements.into_iter().collect();
+    assert_eq!(cached, precomputed.recombine_with::<SumCombiner>());
+});
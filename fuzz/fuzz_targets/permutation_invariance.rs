@@ -0,0 +1,46 @@
+#![no_main]
+
+//! Two collections with the same multiset content, built in different orders, must have equal
+//! digests under every order-independent function
+
+use hash_that_set::{
+    hash_by_summing_hashes, versioned_digest, Algorithm, SumHashesAnyCollection, Unordered,
+};
+use hash_that_set_fuzz::{reference_digest, Element, Permutation};
+use libfuzzer_sys::fuzz_target;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+fn hash_outer<T: Hash>(value: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+fuzz_target!(|permutation: Permutation| {
+    let (first, second) = permutation.orders();
+
+    let mut hashers = (DefaultHasher::new(), DefaultHasher::new());
+    hash_by_summing_hashes::<Vec<Element>, _>(&first, &mut hashers.0);
+    hash_by_summing_hashes::<Vec<Element>, _>(&second, &mut hashers.1);
+    assert_eq!(hashers.0.finish(), hashers.1.finish());
+
+    let wrapped = (
+        SumHashesAnyCollection::<_>::new(first.clone()),
+        SumHashesAnyCollection::<_>::new(second.clone()),
+    );
+    assert_eq!(
+        wrapped.0.raw_unordered_hash(),
+        wrapped.1.raw_unordered_hash()
+    );
+    assert_eq!(reference_digest(&first), wrapped.0.raw_unordered_hash());
+
+    assert_eq!(
+        versioned_digest::<Vec<Element>>(&first, Algorithm::V1Sum64),
+        versioned_digest::<Vec<Element>>(&second, Algorithm::V1Sum64)
+    );
+
+    let unordered = (Unordered::new(first), Unordered::new(second));
+    assert_eq!(unordered.0, unordered.1);
+    assert_eq!(hash_outer(&unordered.0), hash_outer(&unordered.1));
+});
@@ -0,0 +1,101 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Shared harness for the fuzz targets: decoding of operations, and reference digests
+//!
+//! Run a target with `cargo fuzz run <target>` from the repository root, e.g.
+//! `cargo fuzz run permutation_invariance`. This requires a nightly toolchain and
+//! `cargo install cargo-fuzz`.
+
+use arbitrary::Arbitrary;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// An element of the fuzzed collections. Two kinds, so that elements write differing shapes.
+#[derive(Arbitrary, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Element {
+    Number(u16),
+    Text(String),
+}
+
+/// An operation on a multiset of elements
+#[derive(Arbitrary, Clone, Debug)]
+pub enum Op {
+    Insert(Element),
+    /// Removes one occurrence of the element at this index, modulo the length, if any
+    Remove(u8),
+}
+
+/// Builds the same multiset of elements twice, in different orders
+#[derive(Arbitrary, Debug)]
+pub struct Permutation {
+    pub elements: Vec<Element>,
+    /// Drives the reordering of the second copy
+    pub swaps: Vec<(u8, u8)>,
+}
+
+impl Permutation {
+    /// The elements, and a reordering of them
+    pub fn orders(&self) -> (Vec<Element>, Vec<Element>) {
+        let mut reordered = self.elements.clone();
+        let len = reordered.len();
+        if len > 0 {
+            for (a, b) in &self.swaps {
+                reordered.swap(usize::from(*a) % len, usize::from(*b) % len);
+            }
+            reordered.reverse();
+        }
+        (self.elements.clone(), reordered)
+    }
+}
+
+/// Hashes one element with a new [`DefaultHasher`], as the library does per element
+pub fn hash_one<T: Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Recomputes the digest from scratch, independently of the library
+pub fn reference_digest(elements: &[Element]) -> u64 {
+    elements
+        .iter()
+        .fold(0_u64, |sum, element| sum.wrapping_add(hash_one(element)))
+}
+
+/// Replays operations against a list of elements, calling `on_change` after each one with the
+/// elements, the element and whether it was inserted (`true`) or removed
+pub fn replay<F>(ops: &[Op], mut on_change: F) -> Vec<Element>
+where
+    F: FnMut(&[Element], &Element, bool),
+{
+    let mut elements = Vec::new();
+    for op in ops {
+        match op {
+            Op::Insert(element) => {
+                elements.push(element.clone());
+                on_change(&elements, element, true);
+            }
+            Op::Remove(index) => {
+                if !elements.is_empty() {
+                    let removed = elements.swap_remove(usize::from(*index) % elements.len());
+                    on_change(&elements, &removed, false);
+                }
+            }
+        }
+    }
+    elements
+}
@@ -0,0 +1,478 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Functions which transform each element before hashing it
+
+use crate::{hash_of, sum_of_hashes, BuildHasherFromFriend, UseDefaultHasher};
+use std::borrow::Borrow;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::{Display, Write};
+use std::hash::{Hash, Hasher};
+use std::num::Wrapping;
+use std::ops::Deref;
+
+///
+/// Implements hashing by summing the hashes of each element's [`Display`] representation. Each
+/// formatted string is hashed like a `str`, so a collection of numbers hashes the same as a
+/// collection of the same numbers written out as strings. This is useful for comparing with
+/// systems which hash stringified values.
+///
+/// Every element is formatted into a string buffer, which is allocated once and reused. The
+/// cost of formatting is usually greater than that of hashing.
+///
+pub fn hash_by_display<C, H>(collection: &C, state: &mut H)
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Display,
+    H: Hasher,
+{
+    hash_by_display_with::<C, H, UseDefaultHasher>(collection, state)
+}
+
+///
+/// Implements hashing like [`hash_by_display`], with a means of specifying which kind of hasher
+/// is created per element via the `BH` parameter.
+///
+pub fn hash_by_display_with<C, H, BH>(collection: &C, state: &mut H)
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Display,
+    BH: BuildHasherFromFriend<C>,
+    H: Hasher,
+{
+    let mut buffer = String::new();
+    let mut sum = Wrapping::default();
+    for value in collection {
+        buffer.clear();
+        // Writing to a String only fails if the Display impl itself errors. Rather than
+        // panicking, hash whatever it wrote
+        let _ = write!(buffer, "{}", value);
+        sum += hash_of(buffer.as_str(), BH::build_hasher_from(collection));
+    }
+    state.write_u64(sum.0);
+}
+
+///
+/// Computes the digest of a collection after borrowing each element as `Q`, using hashers
+/// from `BH`. Since `Borrow` requires that borrowed and owned forms hash identically,
+/// collections of owned and borrowed elements have equal digests under the same `BH`; for
+/// example, a `HashSet<String>` and a `HashSet<&str>` with the same strings.
+///
+/// In fact, the other functions of this crate uphold that guarantee too, if their element
+/// hashing is fixed by the caller. This function makes the intent explicit. Note that
+/// [`crate::SumHashes`] does *not* fix element hashing: it uses each collection's own hasher,
+/// which differs between collections with different hashers. Use
+/// [`crate::SumHashes::digest_with`] to compare such collections.
+///
+pub fn digest_borrowed<C, Q, BH>(collection: &C) -> u64
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Deref,
+    for<'c> <<&'c C as IntoIterator>::Item as Deref>::Target: Borrow<Q>,
+    Q: Hash + ?Sized,
+    BH: BuildHasherFromFriend<C>,
+{
+    let mut sum = Wrapping::default();
+    for value in collection {
+        sum += hash_of::<Q, _>((*value).borrow(), BH::build_hasher_from(collection));
+    }
+    sum.0
+}
+
+///
+/// Computes the digest of a map after borrowing each key as `Q`, using hashers from `BH`. Maps
+/// with owned and borrowed keys, such as `HashMap<String, V>` and `HashMap<&str, V>`, have equal
+/// digests. See [`digest_borrowed`] for details.
+///
+pub fn digest_borrowed_map<C, K, V, Q, BH>(map: &C) -> u64
+where
+    for<'c> &'c C: IntoIterator<Item = (&'c K, &'c V)>,
+    K: Borrow<Q>,
+    V: Hash,
+    Q: Hash + ?Sized,
+    BH: BuildHasherFromFriend<C>,
+{
+    let borrowed = map.into_iter().map(|(key, value)| (key.borrow(), value));
+    sum_of_hashes(borrowed, || BH::build_hasher_from(map))
+}
+
+///
+/// Hashes the keys of a [`BTreeMap`] as an unordered set, ignoring the values. The result is
+/// identical to hashing a `HashSet` of the same keys with [`crate::hash_by_summing_hashes`],
+/// so that ordered and unordered key collections can be compared. A new [`DefaultHasher`] is
+/// created for each key.
+///
+pub fn hash_btreemap_keys_as_set<K, V, H>(map: &BTreeMap<K, V>, state: &mut H)
+where
+    K: Hash,
+    H: Hasher,
+{
+    state.write_u64(sum_of_hashes(map.keys(), DefaultHasher::new));
+}
+
+///
+/// Hashes the edge set of a graph by summing the hashes of each edge, so that the order of
+/// edges does not matter. For undirected graphs, each edge is normalized to
+/// `(min, max)` beforehand, so that the orientation of edges does not matter either.
+///
+/// A new [`DefaultHasher`](std::collections::hash_map::DefaultHasher) is created for each edge.
+/// Duplicate edges contribute once per occurrence.
+///
+pub fn hash_edge_set<N, H>(edges: &[(N, N)], directed: bool, state: &mut H)
+where
+    N: Hash + Ord,
+    H: Hasher,
+{
+    let normalized = edges.iter().map(|(from, to)| {
+        if directed || from <= to {
+            (from, to)
+        } else {
+            (to, from)
+        }
+    });
+    state.write_u64(sum_of_hashes(normalized, DefaultHasher::new));
+}
+
+///
+/// Hashes the set of distinct equivalence classes of a collection's elements. Each element is
+/// mapped to a class key by `class`; elements with equal keys contribute once, as a single
+/// class. The result is identical to hashing a `HashSet` of the keys with
+/// [`crate::hash_by_summing_hashes`].
+///
+/// This is useful for hashing the "shape" of data while ignoring distinctions within a class.
+///
+pub fn hash_by_equivalence<C, H, F, K>(collection: &C, state: &mut H, class: F)
+where
+    for<'c> &'c C: IntoIterator,
+    F: for<'c> Fn(&<&'c C as IntoIterator>::Item) -> K,
+    K: Hash + Eq,
+    H: Hasher,
+{
+    let classes: HashSet<K> = collection.into_iter().map(|value| class(&value)).collect();
+    state.write_u64(sum_of_hashes(classes, DefaultHasher::new));
+}
+
+///
+/// Hashes a collection under a custom equality relation, by mapping each element to its
+/// canonical representative with `canonical`, then summing the hashes of the representatives.
+/// Elements which are equal under the custom relation should map to equal representatives, so
+/// that they contribute identically. A new [`DefaultHasher`] is created for each element.
+///
+/// Unlike [`hash_by_equivalence`], every element contributes, including elements with equal
+/// representatives.
+///
+pub fn hash_modulo_eq<C, H, F, K>(collection: &C, state: &mut H, canonical: F)
+where
+    for<'c> &'c C: IntoIterator,
+    F: for<'c> Fn(&<&'c C as IntoIterator>::Item) -> K,
+    K: Hash,
+    H: Hasher,
+{
+    let representatives = collection.into_iter().map(|value| canonical(&value));
+    state.write_u64(sum_of_hashes(representatives, DefaultHasher::new));
+}
+
+///
+/// Implements hashing by summing the hashes of each element, widened to a `u64`. A new
+/// [`DefaultHasher`] is created for each element. Hence, collections of integers of different
+/// widths, such as a `HashSet<u32>` and a `HashSet<u64>`, hash equally when they hold the same
+/// values. This keeps hashes comparable when migrating an element type to a wider one.
+///
+pub fn hash_as_u64<C, T, H>(collection: &C, state: &mut H)
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Deref<Target = T>,
+    T: Into<u64> + Copy,
+    H: Hasher,
+{
+    let widened = collection
+        .into_iter()
+        .map(|value| -> u64 { (*value).into() });
+    state.write_u64(sum_of_hashes(widened, DefaultHasher::new));
+}
+
+///
+/// Hashes a columnar, or struct-of-arrays, collection of `len` rows. The hash of the row at
+/// each index is computed by `elem_hash` from the columns, and the row hashes are summed, so
+/// that the order of rows does not matter.
+///
+/// To hash equally with the equivalent array of structs under
+/// [`crate::hash_by_summing_hashes`], `elem_hash` should hash the row's fields with a new
+/// [`DefaultHasher`], as the row's own `Hash` implementation would.
+///
+pub fn hash_soa<H, F>(len: usize, elem_hash: F, state: &mut H)
+where
+    H: Hasher,
+    F: Fn(usize) -> u64,
+{
+    let sum: Wrapping<u64> = (0..len).map(|index| Wrapping(elem_hash(index))).sum();
+    state.write_u64(sum.0);
+}
+
+///
+/// Hashes an empirical distribution, given as a collection of pairs of an element and its
+/// count. `key` and `count` extract the element and the count of each pair, and each
+/// `(element, count)` tuple is hashed with a new [`DefaultHasher`], then summed. Two
+/// distributions with the same counts for the same elements hash equally, in any order, and
+/// differ if any count differs.
+///
+/// Pairs with a count of zero are skipped, so an element with no occurrences hashes the same
+/// as an absent one. Each element should appear in at most one pair: the pairs `(a, 1)` and
+/// `(a, 2)` do not hash as `(a, 3)`.
+///
+pub fn hash_distribution<C, H, K, CNT, E>(pairs: &C, state: &mut H, key: K, count: CNT)
+where
+    for<'c> &'c C: IntoIterator,
+    H: Hasher,
+    K: for<'c> Fn(&<&'c C as IntoIterator>::Item) -> E,
+    CNT: for<'c> Fn(&<&'c C as IntoIterator>::Item) -> u64,
+    E: Hash,
+{
+    let entries = pairs.into_iter().filter_map(|pair| {
+        let count = count(&pair);
+        if count == 0 {
+            None
+        } else {
+            Some((key(&pair), count))
+        }
+    });
+    state.write_u64(sum_of_hashes(entries, DefaultHasher::new));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash_by_summing_hashes;
+
+    #[test]
+    fn owned_and_borrowed_digests_agree() {
+        use crate::SumHashesAnyCollection;
+        use std::collections::HashMap;
+
+        let words = ["owned", "or", "borrowed"];
+        let borrowed: HashSet<&str> = words.iter().copied().collect();
+        let owned: HashSet<String> = words.iter().rev().map(|w| w.to_string()).collect();
+        let digest = digest_borrowed::<HashSet<&str>, str, UseDefaultHasher>(&borrowed);
+        assert_eq!(
+            digest,
+            digest_borrowed::<HashSet<String>, str, UseDefaultHasher>(&owned)
+        );
+        // The standard digest agrees, too
+        assert_eq!(
+            digest,
+            SumHashesAnyCollection::<_, UseDefaultHasher>::new(owned).raw_unordered_hash()
+        );
+
+        let borrowed: HashMap<&str, usize> = words.iter().map(|w| (*w, w.len())).collect();
+        let owned: HashMap<String, usize> =
+            words.iter().map(|w| (w.to_string(), w.len())).collect();
+        let digest =
+            digest_borrowed_map::<HashMap<&str, usize>, _, _, str, UseDefaultHasher>(&borrowed);
+        assert_eq!(
+            digest,
+            digest_borrowed_map::<HashMap<String, usize>, _, _, str, UseDefaultHasher>(&owned)
+        );
+        assert_eq!(
+            digest,
+            SumHashesAnyCollection::<_, UseDefaultHasher>::new(owned).raw_unordered_hash()
+        );
+    }
+
+    #[test]
+    fn btreemap_keys_hash_like_a_set() {
+        fn hash_keys(map: &BTreeMap<&str, u8>) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            hash_btreemap_keys_as_set(map, &mut hasher);
+            hasher.finish()
+        }
+        let map: BTreeMap<&str, u8> = [("b", 1), ("a", 2), ("c", 3)].into_iter().collect();
+        let set: HashSet<&str> = ["c", "a", "b"].into_iter().collect();
+        let mut hasher = DefaultHasher::new();
+        hash_by_summing_hashes::<HashSet<&str>, _>(&set, &mut hasher);
+        assert_eq!(hasher.finish(), hash_keys(&map));
+
+        // Values are ignored, but keys are not
+        let revalued: BTreeMap<&str, u8> = map.keys().map(|key| (*key, 0)).collect();
+        assert_eq!(hash_keys(&map), hash_keys(&revalued));
+        let mut fewer = map.clone();
+        fewer.remove("c");
+        assert_ne!(hash_keys(&map), hash_keys(&fewer));
+    }
+
+    #[test]
+    fn columns_hash_like_rows() {
+        let rows = vec![
+            (3_u32, "three".to_string()),
+            (1, "one".to_string()),
+            (2, "two".to_string()),
+        ];
+        let ids = [1_u32, 2, 3];
+        let names = ["one", "two", "three"].map(String::from);
+        let row_hash = |index: usize| {
+            let mut hasher = DefaultHasher::new();
+            (ids[index], &names[index]).hash(&mut hasher);
+            hasher.finish()
+        };
+
+        let mut from_rows = DefaultHasher::new();
+        hash_by_summing_hashes::<Vec<(u32, String)>, _>(&rows, &mut from_rows);
+        let mut from_columns = DefaultHasher::new();
+        hash_soa(ids.len(), row_hash, &mut from_columns);
+        assert_eq!(from_rows.finish(), from_columns.finish());
+
+        let mut fewer = DefaultHasher::new();
+        hash_soa(2, row_hash, &mut fewer);
+        assert_ne!(from_rows.finish(), fewer.finish());
+    }
+
+    #[test]
+    fn edge_sets_ignore_order_and_orientation() {
+        fn hash(edges: &[(char, char)], directed: bool) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            hash_edge_set(edges, directed, &mut hasher);
+            hasher.finish()
+        }
+        let edges = [('a', 'b'), ('b', 'c'), ('c', 'a')];
+        let reordered = [('b', 'c'), ('c', 'a'), ('a', 'b')];
+        let reoriented = [('b', 'a'), ('c', 'b'), ('a', 'c')];
+
+        for directed in [false, true] {
+            assert_eq!(hash(&edges, directed), hash(&reordered, directed));
+        }
+        assert_eq!(hash(&edges, false), hash(&reoriented, false));
+        assert_ne!(hash(&edges, true), hash(&reoriented, true));
+        assert_ne!(hash(&edges, false), hash(&edges[..2], false));
+    }
+
+    #[test]
+    fn numbers_align_with_strings() {
+        let numbers: HashSet<i32> = [1, -20, 300].into_iter().collect();
+        let strings = vec!["300", "1", "-20"];
+        let floats = vec![-20.0, 300.0, 1.0];
+
+        let mut by_display = DefaultHasher::new();
+        hash_by_display::<HashSet<i32>, _>(&numbers, &mut by_display);
+        let mut by_str = DefaultHasher::new();
+        hash_by_summing_hashes::<Vec<&str>, _>(&strings, &mut by_str);
+        assert_eq!(by_str.finish(), by_display.finish());
+
+        let mut strings_by_display = DefaultHasher::new();
+        hash_by_display::<Vec<&str>, _>(&strings, &mut strings_by_display);
+        assert_eq!(by_str.finish(), strings_by_display.finish());
+
+        // Floats display as "300", not "300.0"
+        let mut floats_by_display = DefaultHasher::new();
+        hash_by_display::<Vec<f64>, _>(&floats, &mut floats_by_display);
+        assert_eq!(by_str.finish(), floats_by_display.finish());
+
+        let mut different = DefaultHasher::new();
+        hash_by_display::<Vec<f64>, _>(&vec![-20.5, 300.0, 1.0], &mut different);
+        assert_ne!(by_str.finish(), different.finish());
+    }
+
+    #[test]
+    fn classes_collapse_to_one_contribution() {
+        let words = vec!["apple", "avocado", "banana", "blueberry", "cherry"];
+        let initials: HashSet<char> = ['a', 'b', 'c'].into_iter().collect();
+        let by_initial = |words: &Vec<&str>| {
+            let mut hasher = DefaultHasher::new();
+            hash_by_equivalence(words, &mut hasher, |word: &&&str| word.chars().next());
+            hasher.finish()
+        };
+
+        let mut expected = DefaultHasher::new();
+        hash_by_summing_hashes::<HashSet<Option<char>>, _>(
+            &initials.iter().copied().map(Some).collect(),
+            &mut expected,
+        );
+        assert_eq!(expected.finish(), by_initial(&words));
+        assert_eq!(
+            by_initial(&words),
+            by_initial(&vec!["cider", "bread", "acorn"])
+        );
+        assert_ne!(by_initial(&words), by_initial(&vec!["apple", "banana"]));
+        assert_ne!(by_initial(&words), by_initial(&vec!["apple", "banana", ""]));
+    }
+
+    #[test]
+    fn custom_equal_elements_hash_identically() {
+        // Paths which are equal ignoring case and trailing separators
+        let canonical = |path: &&&str| path.trim_end_matches('/').to_ascii_lowercase();
+        let hash = |paths: &Vec<&str>| {
+            let mut hasher = DefaultHasher::new();
+            hash_modulo_eq(paths, &mut hasher, canonical);
+            hasher.finish()
+        };
+
+        let paths = vec!["/usr/Bin/", "/etc", "/home/"];
+        assert_eq!(hash(&paths), hash(&vec!["/ETC/", "/home", "/usr/bin"]));
+        assert_ne!(hash(&paths), hash(&vec!["/etc", "/home", "/usr/lib"]));
+
+        // Representatives which are equal still contribute once each
+        assert_ne!(hash(&vec!["/etc", "/ETC"]), hash(&vec!["/etc"]));
+        assert_eq!(hash(&vec!["/etc", "/ETC"]), hash(&vec!["/Etc/", "/etc/"]));
+    }
+
+    #[test]
+    fn widths_hash_equally() {
+        let narrow: HashSet<u32> = [0, 7, u32::MAX].into_iter().collect();
+        let wide: HashSet<u64> = [u64::from(u32::MAX), 7, 0].into_iter().collect();
+        let bytes: Vec<u8> = vec![7, 0];
+
+        let hash_wide = || {
+            let mut hasher = DefaultHasher::new();
+            hash_as_u64::<HashSet<u64>, _, _>(&wide, &mut hasher);
+            hasher.finish()
+        };
+        let mut hasher = DefaultHasher::new();
+        hash_as_u64::<HashSet<u32>, _, _>(&narrow, &mut hasher);
+        assert_eq!(hash_wide(), hasher.finish());
+
+        // Identical to hashing the u64 values directly
+        let mut direct = DefaultHasher::new();
+        hash_by_summing_hashes::<HashSet<u64>, _>(&wide, &mut direct);
+        assert_eq!(hash_wide(), direct.finish());
+
+        let mut from_bytes = DefaultHasher::new();
+        hash_as_u64::<Vec<u8>, _, _>(&bytes, &mut from_bytes);
+        assert_ne!(hash_wide(), from_bytes.finish());
+    }
+
+    #[test]
+    fn distributions_by_counts() {
+        fn digest(pairs: Vec<(&str, u32)>) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            hash_distribution::<Vec<(&str, u32)>, _, _, _, _>(
+                &pairs,
+                &mut hasher,
+                |(word, _)| *word,
+                |(_, count)| u64::from(*count),
+            );
+            hasher.finish()
+        }
+
+        let histogram = digest(vec![("the", 12), ("cat", 3), ("sat", 1)]);
+        assert_eq!(histogram, digest(vec![("sat", 1), ("the", 12), ("cat", 3)]));
+        assert_eq!(
+            histogram,
+            digest(vec![("cat", 3), ("mat", 0), ("sat", 1), ("the", 12)])
+        );
+        assert_ne!(histogram, digest(vec![("the", 12), ("cat", 4), ("sat", 1)]));
+        assert_ne!(histogram, digest(vec![("the", 3), ("cat", 12), ("sat", 1)]));
+        assert_ne!(histogram, digest(vec![("the", 12), ("cat", 3)]));
+    }
+}
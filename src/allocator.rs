@@ -0,0 +1,60 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Variants of the allocating modes which allocate from a given allocator
+//!
+//! Requires the `nightly-allocator-api` feature, and a nightly toolchain.
+
+use crate::combine::hash_sorted;
+use crate::{hashes_of, BuildHasherFromFriend};
+use std::alloc::Allocator;
+use std::hash::Hash;
+
+///
+/// Combines element hashes as [`SortedCombiner`](crate::SortedCombiner) does, collecting them
+/// into a buffer allocated from `alloc`. The digest is the same.
+///
+/// Unlike the combiner, this allocates even for small collections, since the buffer's allocator
+/// is chosen by the caller. An arena or pool allocator avoids pressure on the global allocator
+/// when many collections are combined.
+///
+pub fn combine_sorted_in<I, A>(hashes: I, alloc: A) -> u64
+where
+    I: IntoIterator<Item = u64>,
+    A: Allocator,
+{
+    let mut buffer = Vec::new_in(alloc);
+    buffer.extend(hashes);
+    hash_sorted(&mut buffer)
+}
+
+///
+/// Computes the [`reconciliation_digest`](crate::reconciliation_digest) of a collection into a
+/// vector allocated from `alloc`. The digest is the same.
+///
+pub fn reconciliation_digest_in<C, BH, A>(collection: &C, alloc: A) -> Vec<u64, A>
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash,
+    BH: BuildHasherFromFriend<C>,
+    A: Allocator,
+{
+    let mut hashes = Vec::new_in(alloc);
+    hashes.extend(hashes_of(collection, || BH::build_hasher_from(collection)));
+    hashes.sort_unstable();
+    hashes.dedup();
+    hashes
+}
@@ -0,0 +1,169 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Support for [`crate::assert_unordered_hash_eq`] and [`crate::assert_unordered_hash_ne`]
+
+use crate::{summed_hash, BuildHasherFromFriend};
+use std::hash::Hash;
+
+///
+/// Asserts that two collections have equal order-independent digests. Elements are hashed by a
+/// new hasher per element from the strategy given as the optional third argument, which is
+/// [`UseDefaultHasher`](crate::UseDefaultHasher) by default. The collections may be of
+/// different types.
+///
+/// On failure, the panic message includes both digests and the lengths of the collections.
+///
+/// ```rust
+/// # use std::collections::HashSet;
+/// use hash_that_set::{assert_unordered_hash_eq, UseStableHasher};
+///
+/// let set: HashSet<u8> = (0..10).collect();
+/// let vec: Vec<u8> = (0..10).rev().collect();
+/// assert_unordered_hash_eq!(set, vec);
+/// assert_unordered_hash_eq!(set, vec, UseStableHasher);
+/// ```
+///
+#[macro_export]
+macro_rules! assert_unordered_hash_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::assert_unordered_hash_eq!($left, $right, $crate::UseDefaultHasher)
+    };
+    ($left:expr, $right:expr, $strategy:ty $(,)?) => {
+        $crate::assert::check_digests(
+            $crate::assert::Collection(&$left).digest_and_len::<$strategy>(),
+            $crate::assert::Collection(&$right).digest_and_len::<$strategy>(),
+            true,
+        )
+    };
+}
+
+///
+/// Asserts that two collections have different order-independent digests, as
+/// [`assert_unordered_hash_eq`](crate::assert_unordered_hash_eq) computes them.
+///
+/// ```rust
+/// use hash_that_set::assert_unordered_hash_ne;
+///
+/// assert_unordered_hash_ne!(vec![1, 2, 3], vec![1, 2, 4]);
+/// ```
+///
+#[macro_export]
+macro_rules! assert_unordered_hash_ne {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::assert_unordered_hash_ne!($left, $right, $crate::UseDefaultHasher)
+    };
+    ($left:expr, $right:expr, $strategy:ty $(,)?) => {
+        $crate::assert::check_digests(
+            $crate::assert::Collection(&$left).digest_and_len::<$strategy>(),
+            $crate::assert::Collection(&$right).digest_and_len::<$strategy>(),
+            false,
+        )
+    };
+}
+
+/// Holds a collection, so that its type is known before the bounds of the methods are checked
+pub struct Collection<'c, C>(pub &'c C);
+
+impl<C> Collection<'_, C>
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash,
+{
+    pub fn digest_and_len<BH: BuildHasherFromFriend<C>>(&self) -> (u64, usize) {
+        (summed_hash::<C, BH>(self.0), self.0.into_iter().count())
+    }
+}
+
+#[track_caller]
+pub fn check_digests(left: (u64, usize), right: (u64, usize), expect_equal: bool) {
+    if (left.0 == right.0) != expect_equal {
+        let relation = if expect_equal { "==" } else { "!=" };
+        panic!(
+            "assertion `left {relation} right` failed for unordered hashes\n  \
+             left: digest {:#018x} of {} elements\n \
+             right: digest {:#018x} of {} elements",
+            left.0, left.1, right.0, right.1
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Collection;
+    use crate::{UseDefaultHasher, UseStableHasher};
+    use std::collections::{HashMap, HashSet};
+    use std::panic::{catch_unwind, UnwindSafe};
+
+    fn panic_message(assertion: impl FnOnce() + UnwindSafe) -> String {
+        let payload = catch_unwind(assertion).expect_err("assertion should fail");
+        payload
+            .downcast::<String>()
+            .map(|message| *message)
+            .unwrap()
+    }
+
+    #[test]
+    fn passing_assertions() {
+        let set: HashSet<&str> = ["a", "b", "c"].into_iter().collect();
+        let other_set: HashSet<&str> = ["c", "b", "a"].into_iter().collect();
+        assert_unordered_hash_eq!(set, other_set);
+        assert_unordered_hash_eq!(set, vec!["b", "c", "a"], UseStableHasher);
+
+        let map: HashMap<u8, u8> = [(1, 2), (3, 4)].into_iter().collect();
+        let other_map: HashMap<u8, u8> = [(3, 4), (1, 2)].into_iter().collect();
+        assert_unordered_hash_eq!(map, other_map);
+        assert_unordered_hash_ne!(map, [(1, 2), (3, 5)]);
+
+        // Vectors are hashed as multisets
+        let multiset = vec![1, 1, 2];
+        assert_unordered_hash_eq!(multiset, vec![1, 2, 1], UseDefaultHasher);
+        assert_unordered_hash_ne!(multiset, vec![1, 2, 2]);
+    }
+
+    #[test]
+    fn failure_message_of_eq() {
+        let message = panic_message(|| assert_unordered_hash_eq!(vec![1, 2], vec![1, 2, 3]));
+        let left = Collection(&vec![1, 2])
+            .digest_and_len::<UseDefaultHasher>()
+            .0;
+        let right = Collection(&vec![1, 2, 3])
+            .digest_and_len::<UseDefaultHasher>()
+            .0;
+        assert_eq!(
+            format!(
+                "assertion `left == right` failed for unordered hashes\n  \
+                 left: digest {left:#018x} of 2 elements\n \
+                 right: digest {right:#018x} of 3 elements"
+            ),
+            message
+        );
+    }
+
+    #[test]
+    fn failure_message_of_ne() {
+        let set: HashSet<u8> = (0..4).collect();
+        let message = panic_message(|| assert_unordered_hash_ne!(set, vec![3_u8, 2, 1, 0]));
+        assert!(
+            message.starts_with("assertion `left != right` failed for unordered hashes\n"),
+            "{message}"
+        );
+        assert!(
+            message.contains(" of 4 elements\n right: digest "),
+            "{message}"
+        );
+    }
+}
@@ -0,0 +1,82 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Support for [`crate::hash_auto`]. Dispatch relies on method resolution preferring
+//! `HashWithProvidedHasher`, which needs no auto-referencing, over `HashWithDefaultHasher`,
+//! which does. The latter is only reached when the former's bounds are unmet.
+
+use crate::{hash_by_summing_hashes_with, ProvidesHasher, UseDefaultHasher, UseProvidedHasher};
+use std::hash::{Hash, Hasher};
+
+pub struct AutoHash<'c, C>(pub &'c C);
+
+pub trait HashWithProvidedHasher {
+    fn hash_auto<H: Hasher>(&self, state: &mut H);
+}
+
+impl<C: ProvidesHasher> HashWithProvidedHasher for AutoHash<'_, C>
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash,
+{
+    fn hash_auto<H: Hasher>(&self, state: &mut H) {
+        hash_by_summing_hashes_with::<C, H, UseProvidedHasher<C>>(self.0, state)
+    }
+}
+
+pub trait HashWithDefaultHasher {
+    fn hash_auto<H: Hasher>(&self, state: &mut H);
+}
+
+impl<C> HashWithDefaultHasher for &AutoHash<'_, C>
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash,
+{
+    fn hash_auto<H: Hasher>(&self, state: &mut H) {
+        hash_by_summing_hashes_with::<C, H, UseDefaultHasher>(self.0, state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{hash_by_summing_hashes_with, UseDefaultHasher, UseProvidedHasher};
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::HashSet;
+    use std::hash::Hasher;
+
+    #[test]
+    fn provided_hasher_if_available() {
+        let set: HashSet<u8> = (0..10).collect();
+        let mut expected = DefaultHasher::new();
+        hash_by_summing_hashes_with::<HashSet<u8>, _, UseProvidedHasher<_>>(&set, &mut expected);
+
+        let mut hasher = DefaultHasher::new();
+        crate::hash_auto!(set, &mut hasher);
+        assert_eq!(expected.finish(), hasher.finish());
+    }
+
+    #[test]
+    fn default_hasher_otherwise() {
+        let vec: Vec<u8> = (0..10).collect();
+        let mut expected = DefaultHasher::new();
+        hash_by_summing_hashes_with::<Vec<u8>, _, UseDefaultHasher>(&vec, &mut expected);
+
+        let mut hasher = DefaultHasher::new();
+        crate::hash_auto!(vec, &mut hasher);
+        assert_eq!(expected.finish(), hasher.finish());
+    }
+}
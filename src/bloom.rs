@@ -0,0 +1,186 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Set-semantic hashing of multisets, skipping duplicates with a Bloom filter
+
+use crate::mix::splitmix64;
+use crate::{hash_of, BuildHasherFromFriend};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::hash::Hash;
+use std::num::Wrapping;
+
+/// The number of bits probed per element
+const PROBES: u64 = 3;
+
+/// A Bloom filter over element hashes, with at least one word of bits
+struct BloomFilter {
+    words: Vec<u64>,
+}
+
+impl BloomFilter {
+    fn with_bits(bits: usize) -> Result<Self, FilterTooLarge> {
+        let mut words = Vec::new();
+        let len = (bits / 64).max(1);
+        words
+            .try_reserve_exact(len)
+            .map_err(|_| FilterTooLarge { bits })?;
+        words.resize(len, 0);
+        Ok(Self { words })
+    }
+
+    /// Inserts the hash, returning whether it was possibly present already
+    fn insert(&mut self, hash: u64) -> bool {
+        let bits = self.words.len() as u64 * 64;
+        // Double hashing; the odd step visits distinct bits for small filters
+        let step = splitmix64(hash) | 1;
+        let mut present = true;
+        for probe in 0..PROBES {
+            let bit = hash.wrapping_add(probe.wrapping_mul(step)) % bits;
+            let (word, mask) = ((bit / 64) as usize, 1 << (bit % 64));
+            present &= self.words[word] & mask != 0;
+            self.words[word] |= mask;
+        }
+        present
+    }
+}
+
+///
+/// Computes the hash of the set of distinct elements of a collection, which may contain
+/// duplicates, as the wrapping sum of the distinct elements' hashes. The result is identical to
+/// summing the hashes of a `HashSet` of the elements with the same strategy.
+///
+/// Each element's hash is tested against a Bloom filter of `filter_bits` bits, rounded down to a
+/// multiple of 64, and at least 64. An element which misses the filter has certainly not been
+/// seen, and contributes without further checks. On a hit, the element is compared for
+/// equality with the seen elements of the same hash, so false positives never drop an element.
+/// The exact index of seen elements is built only once the filter first hits, so inputs without
+/// duplicates are hashed in a single pass without one. About 10 bits per distinct element keeps
+/// false positives near 1%.
+///
+/// Every distinct element is retained until the digest is computed, first in a list, then in
+/// the index, so memory use is O(n) in the number of distinct elements, as with collecting
+/// them into a `HashSet`. The filter only defers building the index.
+///
+/// The filter is allocated before hashing. If it cannot be allocated, a [`FilterTooLarge`]
+/// error is returned, and the collection is not iterated.
+///
+pub fn hash_probabilistic_dedup<C, BH>(
+    collection: &C,
+    filter_bits: usize,
+) -> Result<u64, FilterTooLarge>
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash + Eq,
+    BH: BuildHasherFromFriend<C>,
+{
+    let mut filter = BloomFilter::with_bits(filter_bits)?;
+    let mut seen = Vec::new();
+    let mut index: HashMap<u64, Vec<_>> = HashMap::new();
+    let mut sum = Wrapping(0);
+    for value in collection {
+        let hash = hash_of(&value, BH::build_hasher_from(collection));
+        if filter.insert(hash) {
+            // Catch the index up to the elements which missed the filter
+            for (seen_hash, seen_value) in seen.drain(..) {
+                index.entry(seen_hash).or_default().push(seen_value);
+            }
+            let same_hash = index.entry(hash).or_default();
+            if same_hash.contains(&value) {
+                continue;
+            }
+            same_hash.push(value);
+        } else {
+            seen.push((hash, value));
+        }
+        sum += hash;
+    }
+    Ok(sum.0)
+}
+
+/// The error when the Bloom filter of [`hash_probabilistic_dedup`] cannot be allocated
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FilterTooLarge {
+    bits: usize,
+}
+
+impl FilterTooLarge {
+    /// The requested number of bits
+    pub fn bits(&self) -> usize {
+        self.bits
+    }
+}
+
+impl Display for FilterTooLarge {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "cannot allocate a Bloom filter of {} bits", self.bits)
+    }
+}
+
+impl Error for FilterTooLarge {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{summed_hash, UseDefaultHasher};
+    use std::collections::HashSet;
+
+    fn exact(values: &[u32]) -> u64 {
+        let set: HashSet<u32> = values.iter().copied().collect();
+        summed_hash::<HashSet<u32>, UseDefaultHasher>(&set)
+    }
+
+    #[test]
+    fn matches_set_hashing_with_duplicates() {
+        let mut values: Vec<u32> = (0..10_000).collect();
+        values.extend((0..10_000).step_by(7));
+        values.extend([3, 3, 3, 9_999]);
+        let dedup =
+            hash_probabilistic_dedup::<Vec<u32>, UseDefaultHasher>(&values, 100_000).unwrap();
+        assert_eq!(exact(&values), dedup);
+    }
+
+    #[test]
+    fn false_positives_never_drop_elements() {
+        // A single word of bits saturates, so nearly every element hits the filter
+        let values: Vec<u32> = (0..2_000).chain(0..500).collect();
+        let dedup = hash_probabilistic_dedup::<Vec<u32>, UseDefaultHasher>(&values, 0).unwrap();
+        assert_eq!(exact(&values), dedup);
+        assert_ne!(summed_hash::<Vec<u32>, UseDefaultHasher>(&values), dedup);
+    }
+
+    #[test]
+    fn unique_and_empty_inputs() {
+        let values: Vec<u32> = (0..1_000).rev().collect();
+        let dedup =
+            hash_probabilistic_dedup::<Vec<u32>, UseDefaultHasher>(&values, 10_000).unwrap();
+        assert_eq!(summed_hash::<Vec<u32>, UseDefaultHasher>(&values), dedup);
+        assert_eq!(
+            0,
+            hash_probabilistic_dedup::<Vec<u32>, UseDefaultHasher>(&vec![], 64).unwrap()
+        );
+    }
+
+    #[test]
+    fn oversized_filters_are_errors() {
+        let values: Vec<u32> = (0..10).collect();
+        let error = hash_probabilistic_dedup::<Vec<u32>, UseDefaultHasher>(&values, usize::MAX)
+            .unwrap_err();
+        assert_eq!(usize::MAX, error.bits());
+        assert!(error.to_string().contains("Bloom filter"), "{}", error);
+    }
+}
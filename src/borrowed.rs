@@ -0,0 +1,341 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Hashing of collections and elements which are only borrowed
+
+use crate::{
+    hash_by_summing_with_provided_hasher, sum_of_hashes, summed_hash, BuildHasherFromFriend,
+    ProvidesHasher, SumHashes, UseDefaultHasher,
+};
+use std::borrow::Borrow;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::fmt::{self, Debug, Formatter};
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::marker::PhantomData;
+use std::ops::Deref;
+
+///
+/// Adds hashing to a borrowed collection, like [`crate::SumHashesAnyCollection`], but without
+/// taking ownership. This is useful for hashing an existing collection, such as one stored
+/// elsewhere, without cloning or moving it. `Deref` provides access to the borrowed type.
+///
+/// **Do not use this wrapper with an ordered collection**. The wrapper does not change equality
+/// semantics; it affects hashing only.
+///
+/// ```rust
+/// # use std::collections::HashSet;
+/// use hash_that_set::{SumHashesAnyCollection, SumHashesRef};
+///
+/// let set: HashSet<u8> = [1, 2, 3].into_iter().collect();
+/// let borrowed = SumHashesRef::<_>::new(&set);
+///
+/// assert_eq!(
+///     SumHashesAnyCollection::<_>::new(set.clone()).raw_unordered_hash(),
+///     borrowed.raw_unordered_hash()
+/// );
+/// ```
+///
+pub struct SumHashesRef<'c, C: ?Sized, H = UseDefaultHasher>(&'c C, PhantomData<H>);
+
+impl<'c, C: ?Sized, H> SumHashesRef<'c, C, H> {
+    /// Creates the wrapper
+    #[inline]
+    pub fn new(collection: &'c C) -> Self {
+        Self(collection, PhantomData)
+    }
+
+    /// Returns the borrowed collection, with its original lifetime
+    #[inline]
+    pub fn get(&self) -> &'c C {
+        self.0
+    }
+}
+
+impl<'c, C, BH> SumHashesRef<'c, C, BH>
+where
+    for<'i> &'i C: IntoIterator,
+    for<'i> <&'i C as IntoIterator>::Item: Hash,
+    BH: BuildHasherFromFriend<C>,
+{
+    ///
+    /// Returns the canonical digest of the collection under the configured hasher `BH`.
+    /// This is exactly the value which the `Hash` implementation writes to the outer hasher.
+    ///
+    #[inline]
+    pub fn raw_unordered_hash(&self) -> u64 {
+        summed_hash::<C, BH>(self.0)
+    }
+}
+
+impl<'c, C: ?Sized, H> From<&'c C> for SumHashesRef<'c, C, H> {
+    /// Creates the wrapper
+    #[inline]
+    fn from(value: &'c C) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<'c, C: ?Sized, H> Clone for SumHashesRef<'c, C, H> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'c, C: ?Sized, H> Copy for SumHashesRef<'c, C, H> {}
+
+impl<'c, C: Debug + ?Sized, H> Debug for SumHashesRef<'c, C, H> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SumHashesRef").field(&self.0).finish()
+    }
+}
+
+#[cfg(not(feature = "debug-assert"))]
+impl<'c, C: PartialEq + ?Sized, H> PartialEq for SumHashesRef<'c, C, H> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+#[cfg(not(feature = "debug-assert"))]
+impl<'c, C: Eq + ?Sized, H> Eq for SumHashesRef<'c, C, H> {}
+
+#[cfg(feature = "debug-assert")]
+impl<'c, C: PartialEq, BH> PartialEq for SumHashesRef<'c, C, BH>
+where
+    for<'i> &'i C: IntoIterator,
+    for<'i> <&'i C as IntoIterator>::Item: Hash,
+    BH: BuildHasherFromFriend<C>,
+{
+    fn eq(&self, other: &Self) -> bool {
+        let equal = self.0 == other.0;
+        crate::contract::debug_assert_contract(equal, self, other);
+        equal
+    }
+}
+
+#[cfg(feature = "debug-assert")]
+impl<'c, C: Eq, BH> Eq for SumHashesRef<'c, C, BH>
+where
+    for<'i> &'i C: IntoIterator,
+    for<'i> <&'i C as IntoIterator>::Item: Hash,
+    BH: BuildHasherFromFriend<C>,
+{
+}
+
+impl<'c, C, BH> Hash for SumHashesRef<'c, C, BH>
+where
+    for<'i> &'i C: IntoIterator,
+    for<'i> <&'i C as IntoIterator>::Item: Hash,
+    BH: BuildHasherFromFriend<C>,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.raw_unordered_hash())
+    }
+}
+
+impl<'c, C: ?Sized, H> Deref for SumHashesRef<'c, C, H> {
+    type Target = C;
+
+    fn deref(&self) -> &Self::Target {
+        self.0
+    }
+}
+
+///
+/// Implements hashing by summing the hashes of each borrowed element, for any iterator over
+/// references, such as `set.iter()`. A new [`DefaultHasher`] is created for each element, so
+/// the result is identical to hashing the collection with [`crate::hash_by_summing_hashes`].
+///
+pub fn hash_iter_ref<'a, I, T, H>(elements: I, state: &mut H)
+where
+    I: IntoIterator<Item = &'a T>,
+    T: Hash + ?Sized + 'a,
+    H: Hasher,
+{
+    state.write_u64(sum_of_hashes(elements, DefaultHasher::new));
+}
+
+///
+/// A borrowed collection, as a key for looking up a [`SumHashes`] in a `HashSet` or `HashMap`
+/// without wrapping the collection first. `SumHashes<C>` borrows as `dyn SumHashesKey<C>`,
+/// which hashes and compares like the wrapper. Obtain a key from [`SumHashes::as_key`].
+///
+/// ```rust
+/// # use std::collections::hash_map::DefaultHasher;
+/// # use std::collections::HashSet;
+/// # use std::hash::BuildHasherDefault;
+/// use hash_that_set::SumHashes;
+///
+/// type Group = HashSet<u32, BuildHasherDefault<DefaultHasher>>;
+///
+/// let group: Group = [1, 2, 3].into_iter().collect();
+/// let mut seen = HashSet::new();
+/// seen.insert(SumHashes::new(group.clone()));
+///
+/// assert!(seen.contains(SumHashes::as_key(&group)));
+/// ```
+///
+pub trait SumHashesKey<C> {
+    /// Returns the borrowed collection
+    fn collection(&self) -> &C;
+}
+
+impl<C> SumHashesKey<C> for C {
+    fn collection(&self) -> &C {
+        self
+    }
+}
+
+impl<'k, C: ProvidesHasher + 'k> Borrow<dyn SumHashesKey<C> + 'k> for SumHashes<C> {
+    fn borrow(&self) -> &(dyn SumHashesKey<C> + 'k) {
+        &self.0
+    }
+}
+
+impl<C: PartialEq> PartialEq for dyn SumHashesKey<C> + '_ {
+    fn eq(&self, other: &Self) -> bool {
+        self.collection() == other.collection()
+    }
+}
+
+impl<C: Eq> Eq for dyn SumHashesKey<C> + '_ {}
+
+impl<C: ProvidesHasher> Hash for dyn SumHashesKey<C> + '_
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash_by_summing_with_provided_hasher(self.collection(), state)
+    }
+}
+
+impl<C: ProvidesHasher> SumHashes<C> {
+    ///
+    /// Borrows a collection as a key, with which a wrapper holding an equal collection can be
+    /// looked up. See [`SumHashesKey`].
+    ///
+    #[inline]
+    pub fn as_key(collection: &C) -> &(dyn SumHashesKey<C> + '_) {
+        collection
+    }
+
+    ///
+    /// Determines whether a set of wrappers contains one holding a collection equal to the given
+    /// collection, without cloning or wrapping it.
+    ///
+    pub fn contains_equivalent<S: BuildHasher>(set: &HashSet<Self, S>, collection: &C) -> bool
+    where
+        C: Eq,
+        for<'c> &'c C: IntoIterator,
+        for<'c> <&'c C as IntoIterator>::Item: Hash,
+    {
+        set.contains(Self::as_key(collection))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SumHashes, SumHashesAnyCollection};
+    use std::collections::{HashMap, HashSet};
+
+    fn hash_one<T: Hash>(value: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn iterators_match_owned_wrapper() {
+        let set: HashSet<String> = ["north", "south", "east"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let owned = SumHashesAnyCollection::<_>::new(set.clone());
+
+        let mut from_iter = DefaultHasher::new();
+        hash_iter_ref(set.iter(), &mut from_iter);
+        assert_eq!(hash_one(&owned), from_iter.finish());
+
+        let mut filtered = DefaultHasher::new();
+        hash_iter_ref(set.iter().filter(|name| name.len() == 5), &mut filtered);
+        assert_ne!(from_iter.finish(), filtered.finish());
+
+        let unsized_elements: Vec<&str> = vec!["east", "south", "north"];
+        let mut from_str = DefaultHasher::new();
+        hash_iter_ref(unsized_elements.iter().copied(), &mut from_str);
+        assert_eq!(from_iter.finish(), from_str.finish());
+    }
+
+    #[test]
+    fn borrowed_wrapper_matches_owned_wrapper() {
+        let mut map = HashMap::new();
+        map.insert('a', 1);
+        map.insert('b', 2);
+        let borrowed = SumHashesRef::<_>::new(&map);
+        assert_eq!(
+            hash_one(&SumHashesAnyCollection::<_>::new(map.clone())),
+            hash_one(&borrowed)
+        );
+        assert_eq!(borrowed, borrowed.clone());
+        assert_eq!(2, borrowed.len());
+
+        let provided = SumHashesRef::<_, crate::UseProvidedHasher<_>>::new(&map);
+        assert_eq!(
+            SumHashes::new(map.clone()).raw_unordered_hash(),
+            provided.raw_unordered_hash()
+        );
+    }
+
+    #[test]
+    fn look_up_wrappers_by_key() {
+        use std::hash::BuildHasherDefault;
+
+        type Group = HashSet<u8, BuildHasherDefault<DefaultHasher>>;
+        let group = |elements: &[u8]| elements.iter().copied().collect::<Group>();
+
+        let mut seen = HashSet::new();
+        seen.insert(SumHashes::new(group(&[1, 2, 3])));
+        seen.insert(SumHashes::new(group(&[])));
+
+        assert!(SumHashes::<Group>::contains_equivalent(
+            &seen,
+            &group(&[3, 1, 2])
+        ));
+        assert!(SumHashes::<Group>::contains_equivalent(&seen, &group(&[])));
+        assert!(!SumHashes::<Group>::contains_equivalent(
+            &seen,
+            &group(&[1, 2])
+        ));
+        assert!(!SumHashes::<Group>::contains_equivalent(
+            &seen,
+            &group(&[1, 2, 3, 4])
+        ));
+
+        let removed = group(&[1, 2, 3]);
+        let key = SumHashes::as_key(&removed);
+        assert_eq!(hash_one(&SumHashes::new(group(&[2, 3, 1]))), hash_one(&key));
+        assert!(seen.remove(key));
+        assert_eq!(1, seen.len());
+
+        let mut counts = HashMap::new();
+        counts.insert(SumHashes::new(group(&[4])), 1);
+        *counts.get_mut(SumHashes::as_key(&group(&[4]))).unwrap() += 1;
+        assert_eq!(Some(&2), counts.get(SumHashes::as_key(&group(&[4]))));
+    }
+}
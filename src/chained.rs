@@ -0,0 +1,86 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Hashing of a set followed by ordered data, as two separate regions
+
+use crate::{summed_hash, UseDefaultHasher};
+use std::hash::{Hash, Hasher};
+
+/// Written between the set region and the sequence region
+const SEQUENCE_DELIMITER: u8 = 0xff;
+
+///
+/// Hashes a structure which is a set plus some ordered metadata. The order-independent digest
+/// of the set, as written by [`crate::hash_by_summing_hashes`], is followed by the
+/// order-dependent metadata, so permuting the set does not change the hash, but reordering
+/// `meta` does.
+///
+/// The two regions cannot bleed into each other: the digest is always one `u64`, and it is
+/// followed by a delimiter byte, `0xff`, then by the length of `meta` as a `u64`, then by each
+/// element of `meta` in order.
+///
+pub fn hash_set_then_sequence<C, M, H>(set: &C, meta: &[M], state: &mut H)
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash,
+    M: Hash,
+    H: Hasher,
+{
+    state.write_u64(summed_hash::<C, UseDefaultHasher>(set));
+    state.write_u8(SEQUENCE_DELIMITER);
+    state.write_u64(meta.len() as u64);
+    for element in meta {
+        element.hash(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash_by_summing_hashes;
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::HashSet;
+
+    fn digest<M: Hash>(set: &HashSet<&str>, meta: &[M]) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        hash_set_then_sequence::<HashSet<&str>, _, _>(set, meta, &mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn set_order_is_ignored_and_sequence_order_is_not() {
+        let set: HashSet<&str> = ["red", "green", "blue"].into_iter().collect();
+        let mut rebuilt = HashSet::new();
+        rebuilt.extend(["blue", "red", "green"]);
+        assert_eq!(digest(&set, &[1, 2, 3]), digest(&rebuilt, &[1, 2, 3]));
+        assert_ne!(digest(&set, &[1, 2, 3]), digest(&set, &[3, 2, 1]));
+        assert_ne!(digest(&set, &[1, 2, 3]), digest(&set, &[1, 2]));
+    }
+
+    #[test]
+    fn regions_are_delimited() {
+        let set: HashSet<&str> = ["red"].into_iter().collect();
+        let mut expected = DefaultHasher::new();
+        hash_by_summing_hashes::<HashSet<&str>, _>(&set, &mut expected);
+        expected.write_u8(0xff);
+        expected.write_u64(0);
+        assert_eq!(expected.finish(), digest::<u8>(&set, &[]));
+
+        // An empty set with the sequence "red" differs from the set of "red" without one
+        let empty = HashSet::new();
+        assert_ne!(digest::<u8>(&set, &[]), digest(&empty, &["red"]));
+    }
+}
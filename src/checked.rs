@@ -0,0 +1,229 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Hashing combined with validation or fallible conversion of each element
+
+use crate::{sum_of_hashes, BuildHasherFromFriend};
+use std::collections::hash_map::DefaultHasher;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::hash::{Hash, Hasher};
+
+///
+/// Computes the digest of a collection by summing the hashes of each element, with hashers
+/// from `BH`, while checking that every element satisfies `pred`. Validation and hashing happen
+/// in the same pass, which is useful after deserializing a collection.
+///
+/// Returns the digest, i.e. the value which [`crate::hash_by_summing_hashes_with`] would write,
+/// or an error reporting the position in iteration order of the first element which violates
+/// `pred`. Iteration stops at that element.
+///
+pub fn hash_checked<C, BH, P>(collection: &C, pred: P) -> Result<u64, InvariantViolation>
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash,
+    BH: BuildHasherFromFriend<C>,
+    P: for<'c> Fn(&<&'c C as IntoIterator>::Item) -> bool,
+{
+    let mut violation = None;
+    let valid = collection
+        .into_iter()
+        .enumerate()
+        .map_while(|(position, value)| {
+            if pred(&value) {
+                Some(value)
+            } else {
+                violation = Some(InvariantViolation { position });
+                None
+            }
+        });
+    let sum = sum_of_hashes(valid, || BH::build_hasher_from(collection));
+    violation.map_or(Ok(sum), Err)
+}
+
+/// The error when an element does not satisfy an invariant
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InvariantViolation {
+    position: usize,
+}
+
+impl InvariantViolation {
+    /// The position of the offending element, in iteration order
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl Display for InvariantViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "element at position {} violates the invariant",
+            self.position
+        )
+    }
+}
+
+impl Error for InvariantViolation {}
+
+///
+/// Hashes a collection by summing the hashes of its elements after converting each with
+/// `convert`, so that elements can be validated and normalized in the same pass. A new
+/// [`DefaultHasher`] is created for each converted value.
+///
+/// The first conversion which fails stops iteration, and is returned together with the position
+/// of its element in iteration order. In that case, nothing is written to `state`.
+///
+pub fn hash_try_convert<C, H, U, E, F>(
+    collection: &C,
+    state: &mut H,
+    convert: F,
+) -> Result<(), ConvertError<E>>
+where
+    for<'c> &'c C: IntoIterator,
+    H: Hasher,
+    U: Hash,
+    F: for<'c> Fn(&<&'c C as IntoIterator>::Item) -> Result<U, E>,
+{
+    let mut failure = None;
+    let converted =
+        collection
+            .into_iter()
+            .enumerate()
+            .map_while(|(position, value)| match convert(&value) {
+                Ok(converted) => Some(converted),
+                Err(error) => {
+                    failure = Some(ConvertError { position, error });
+                    None
+                }
+            });
+    let sum = sum_of_hashes(converted, DefaultHasher::new);
+    match failure {
+        Some(failure) => Err(failure),
+        None => {
+            state.write_u64(sum);
+            Ok(())
+        }
+    }
+}
+
+/// The error when an element could not be converted, from [`hash_try_convert`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConvertError<E> {
+    position: usize,
+    error: E,
+}
+
+impl<E> ConvertError<E> {
+    /// The position of the offending element, in iteration order
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// The error returned by the conversion
+    pub fn error(&self) -> &E {
+        &self.error
+    }
+
+    /// Takes the error returned by the conversion
+    pub fn into_error(self) -> E {
+        self.error
+    }
+}
+
+impl<E: Display> Display for ConvertError<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "element at position {} could not be converted: {}",
+            self.position, self.error
+        )
+    }
+}
+
+impl<E: Error + 'static> Error for ConvertError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SumHashesAnyCollection, UseDefaultHasher};
+
+    #[test]
+    fn passing_collection_hashes() {
+        let ports = vec![80_u16, 443, 8080];
+        let digest = hash_checked::<Vec<u16>, UseDefaultHasher, _>(&ports, |port| **port > 0);
+        assert_eq!(
+            Ok(SumHashesAnyCollection::<_>::new(ports).raw_unordered_hash()),
+            digest
+        );
+        assert_eq!(
+            Ok(0),
+            hash_checked::<Vec<u16>, UseDefaultHasher, _>(&vec![], |_| false)
+        );
+    }
+
+    #[test]
+    fn violation_reports_position() {
+        let names = vec!["alice", "bob", "", "carol", ""];
+        let result =
+            hash_checked::<Vec<&str>, UseDefaultHasher, _>(&names, |name| !name.is_empty());
+        let error = result.unwrap_err();
+        assert_eq!(2, error.position());
+        assert_eq!(
+            "element at position 2 violates the invariant",
+            error.to_string()
+        );
+    }
+
+    fn digest(values: Vec<u8>) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        crate::hash_by_summing_hashes::<Vec<u8>, _>(&values, &mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn converted_values_hash_unordered() {
+        let inputs = vec!["3", " 1", "2 "];
+        let mut hasher = DefaultHasher::new();
+        hash_try_convert::<Vec<&str>, _, _, _, _>(&inputs, &mut hasher, |input| {
+            input.trim().parse::<u8>()
+        })
+        .unwrap();
+        assert_eq!(digest(vec![1, 2, 3]), hasher.finish());
+    }
+
+    #[test]
+    fn conversion_failure_reports_position() {
+        let inputs = vec!["1", "2", "many", "-1"];
+        let mut hasher = DefaultHasher::new();
+        let error = hash_try_convert::<Vec<&str>, _, _, _, _>(&inputs, &mut hasher, |input| {
+            input.parse::<u8>()
+        })
+        .unwrap_err();
+        assert_eq!(2, error.position());
+        assert_eq!(
+            "element at position 2 could not be converted: invalid digit found in string",
+            error.to_string()
+        );
+        assert!(error.source().is_some());
+        // Nothing was written
+        assert_eq!(DefaultHasher::new().finish(), hasher.finish());
+    }
+}
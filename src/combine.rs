@@ -0,0 +1,241 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Combiners of element hashes, including ones other than the plain sum
+//!
+//! # Collisions
+//!
+//! The `collision_stats` example measures collisions between distinct inputs, and the balance of
+//! each output bit as a chi-squared statistic over the 64 bits, of which about 64 is expected
+//! and above 93.2 is suspicious. With its defaults, 100000 inputs of 16 values hashed by
+//! [`DefaultHasher`], it reports:
+//!
+//! | Workload                  | Combiner | Collisions | Chi-squared |
+//! |---------------------------|----------|-----------:|------------:|
+//! | Random sets               | Sum      |          0 |        53.8 |
+//! |                           | XOR      |          0 |        75.3 |
+//! |                           | Sorted   |          0 |        58.8 |
+//! | Near-linear sets          | Sum      |          0 |        60.4 |
+//! |                           | XOR      |          0 |        85.2 |
+//! |                           | Sorted   |          0 |        63.1 |
+//! | Duplicate-heavy multisets | Sum      |          0 |        19.4 |
+//! |                           | XOR      |      89187 |     89329.8 |
+//! |                           | Sorted   |          0 |        68.5 |
+//!
+//! The duplicate-heavy workload has 89315 distinct multisets over 8 values. XOR keeps only the
+//! parity of each value's multiplicity, leaving at most 256 digests.
+
+use crate::{hashes_of, BuildHasherFromFriend};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::num::Wrapping;
+
+///
+/// An order-independent way of combining the hashes of every element into one digest. Used by
+/// [`crate::PrecomputedHashes::recombine_with`].
+///
+pub trait HashCombiner {
+    /// Combines the element hashes into a digest. The result must not depend on their order.
+    fn combine<I>(hashes: I) -> u64
+    where
+        I: IntoIterator<Item = u64>;
+}
+
+///
+/// Combines element hashes by their wrapping sum, as do [`crate::hash_by_summing_hashes`] and
+/// the wrappers of this crate
+///
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct SumCombiner(());
+
+impl HashCombiner for SumCombiner {
+    fn combine<I>(hashes: I) -> u64
+    where
+        I: IntoIterator<Item = u64>,
+    {
+        hashes.into_iter().map(Wrapping).sum::<Wrapping<u64>>().0
+    }
+}
+
+///
+/// Combines element hashes by XOR. Pairs of equal element hashes cancel out, so this is only
+/// suitable for collections without duplicates.
+///
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct XorCombiner(());
+
+impl HashCombiner for XorCombiner {
+    fn combine<I>(hashes: I) -> u64
+    where
+        I: IntoIterator<Item = u64>,
+    {
+        hashes.into_iter().fold(0, |xor, hash| xor ^ hash)
+    }
+}
+
+///
+/// Combines element hashes by sorting them, then hashing the sorted sequence with a
+/// [`DefaultHasher`]. Unlike the sum or XOR, the digest is not linear in the element hashes, at
+/// the cost of collecting and sorting them.
+///
+/// Up to [`Self::INLINE_CAPACITY`] hashes are collected in a buffer on the stack, so combining
+/// small collections does not allocate. Larger collections spill to the heap. The digest is the
+/// same either way.
+///
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct SortedCombiner(());
+
+impl SortedCombiner {
+    /// The number of hashes which are combined without allocating
+    pub const INLINE_CAPACITY: usize = 32;
+}
+
+pub(crate) fn hash_sorted(hashes: &mut [u64]) -> u64 {
+    hashes.sort_unstable();
+    let mut hasher = DefaultHasher::new();
+    hashes.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl HashCombiner for SortedCombiner {
+    fn combine<I>(hashes: I) -> u64
+    where
+        I: IntoIterator<Item = u64>,
+    {
+        let mut hashes = hashes.into_iter();
+        let mut inline = [0; Self::INLINE_CAPACITY];
+        let mut len = 0;
+        for slot in inline.iter_mut() {
+            match hashes.next() {
+                Some(hash) => {
+                    *slot = hash;
+                    len += 1;
+                }
+                None => return hash_sorted(&mut inline[..len]),
+            }
+        }
+        match hashes.next() {
+            None => hash_sorted(&mut inline),
+            Some(next) => {
+                let mut spilled = Vec::with_capacity(2 * Self::INLINE_CAPACITY);
+                spilled.extend_from_slice(&inline);
+                spilled.push(next);
+                spilled.extend(hashes);
+                hash_sorted(&mut spilled)
+            }
+        }
+    }
+}
+
+///
+/// Computes both the sum and the XOR of the element hashes of a collection, in a single pass.
+/// Each element is hashed by a new hasher from `BH`. Returns `(sum_hash, xor_hash)`, where
+/// `sum_hash` is the digest which [`crate::hash_by_summing_hashes_with`] would write.
+///
+/// Both combiners are order-independent. Unlike the sum, the XOR cancels out pairs of equal
+/// element hashes, so it is only suitable for collections without duplicates.
+///
+pub fn dual_combine<C, BH>(collection: &C) -> (u64, u64)
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash,
+    BH: BuildHasherFromFriend<C>,
+{
+    let mut sum = Wrapping::default();
+    let mut xor = 0;
+    for hash in hashes_of(collection, || BH::build_hasher_from(collection)) {
+        sum += hash;
+        xor ^= hash;
+    }
+    (sum.0, xor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SumHashesAnyCollection, UseDefaultHasher};
+
+    #[test]
+    fn components_match_single_combiners() {
+        let values = vec!["x", "y", "z", "w"];
+        let reordered = vec!["w", "z", "y", "x"];
+        let (sum, xor) = dual_combine::<Vec<&str>, UseDefaultHasher>(&values);
+
+        assert_eq!(
+            SumHashesAnyCollection::<_>::new(values.clone()).raw_unordered_hash(),
+            sum
+        );
+        let expected_xor = values.iter().fold(0, |xor, value| {
+            let mut hasher = DefaultHasher::new();
+            value.hash(&mut hasher);
+            xor ^ hasher.finish()
+        });
+        assert_eq!(expected_xor, xor);
+        assert_eq!(
+            (sum, xor),
+            dual_combine::<Vec<&str>, UseDefaultHasher>(&reordered)
+        );
+        assert_eq!((0, 0), dual_combine::<Vec<&str>, UseDefaultHasher>(&vec![]));
+    }
+
+    #[test]
+    fn combiners_ignore_order() {
+        let hashes = [3, 1 << 63, u64::MAX, 3];
+        let reordered = [u64::MAX, 3, 3, 1 << 63];
+        assert_eq!(
+            SumCombiner::combine(hashes),
+            SumCombiner::combine(reordered)
+        );
+        assert_eq!((1 << 63) + 5, SumCombiner::combine(hashes));
+        assert_eq!(
+            XorCombiner::combine(hashes),
+            XorCombiner::combine(reordered)
+        );
+        assert_eq!(u64::MAX ^ (1 << 63), XorCombiner::combine(hashes));
+        assert_eq!(
+            SortedCombiner::combine(hashes),
+            SortedCombiner::combine(reordered)
+        );
+        assert_ne!(
+            SortedCombiner::combine(hashes),
+            SortedCombiner::combine([3, 1 << 63, u64::MAX])
+        );
+    }
+
+    #[test]
+    fn sorted_same_across_inline_and_spilled() {
+        // The digest of the previous implementation, which always collected into a Vec
+        fn collected(hashes: &[u64]) -> u64 {
+            let mut hashes = hashes.to_vec();
+            hashes.sort_unstable();
+            let mut hasher = DefaultHasher::new();
+            hashes.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        let capacity = SortedCombiner::INLINE_CAPACITY;
+        for len in [0, 1, 10, capacity - 1, capacity, capacity + 1, 3 * capacity] {
+            let hashes: Vec<u64> = (0..len as u64)
+                .map(|n| n.wrapping_mul(0x9e37_79b9_7f4a_7c15))
+                .collect();
+            let mut reversed = hashes.clone();
+            reversed.reverse();
+            let digest = SortedCombiner::combine(hashes.iter().copied());
+            assert_eq!(collected(&hashes), digest, "{} hashes", len);
+            assert_eq!(digest, SortedCombiner::combine(reversed));
+        }
+    }
+}
@@ -0,0 +1,277 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Checking the contract between `Hash` and `Eq`
+
+use crate::hash_of;
+use std::any::type_name;
+use std::collections::hash_map::DefaultHasher;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::ops::{Deref, DerefMut};
+
+///
+/// Checks that two values which compare equal also hash equally, as the contract between
+/// `Hash` and `Eq` requires. Values which compare unequal always pass.
+///
+/// A wrapper can violate the contract when its equality does not match its order-independent
+/// hashing: for example, when wrapping an ordered collection in a way which compares it without
+/// regard to order, or with [`crate::SumHashes`] over collections whose hashers are seeded
+/// differently. With the `debug-assert` feature, the `PartialEq` implementations of this
+/// crate's wrappers perform this check in debug builds, panicking on a violation. To check
+/// comparisons of other types, wrap them in [`ContractChecked`].
+///
+pub fn check_hash_eq_contract<W>(a: &W, b: &W) -> Result<(), ContractViolation>
+where
+    W: Hash + Eq + ?Sized,
+{
+    if a == b {
+        compare_hashes(a, b)
+    } else {
+        Ok(())
+    }
+}
+
+fn compare_hashes<W: Hash + ?Sized>(a: &W, b: &W) -> Result<(), ContractViolation> {
+    let hash = |value: &W| hash_of(value, DefaultHasher::new());
+    let hashes = (hash(a), hash(b));
+    if hashes.0 == hashes.1 {
+        Ok(())
+    } else {
+        Err(ContractViolation {
+            type_name: type_name::<W>(),
+            hashes,
+        })
+    }
+}
+
+/// Panics, in debug builds, if values which compare equal hash differently
+#[inline]
+pub(crate) fn debug_assert_contract<W: Hash + ?Sized>(equal: bool, a: &W, b: &W) {
+    if cfg!(debug_assertions) && equal {
+        if let Err(violation) = compare_hashes(a, b) {
+            panic!("{}", violation);
+        }
+    }
+}
+
+///
+/// Wraps a value so that its `PartialEq` checks the contract between `Hash` and `Eq`, as
+/// [`check_hash_eq_contract`] does, on every comparison in debug builds. A comparison of values
+/// which are equal but hash differently panics. `Hash` and `Deref` delegate to the wrapped
+/// value, and in release builds, comparisons are not checked.
+///
+/// This is opt-in, so the check costs nothing elsewhere. The `debug-assert` feature performs
+/// the same check in the comparisons of this crate's wrappers; this type extends it to any
+/// other type, such as a user-defined wrapper, in tests or in debug builds of applications:
+///
+/// ```rust
+/// use hash_that_set::{ContractChecked, SumHashesAnyCollection};
+/// use std::collections::HashSet;
+///
+/// let first: HashSet<u8> = [1, 2, 3].into_iter().collect();
+/// let second: HashSet<u8> = [3, 2, 1].into_iter().collect();
+/// let first = ContractChecked(SumHashesAnyCollection::<_>::new(first));
+/// let second = ContractChecked(SumHashesAnyCollection::<_>::new(second));
+/// assert!(first == second);
+/// ```
+///
+#[derive(Clone, Copy, Debug, Default)]
+#[repr(transparent)]
+pub struct ContractChecked<W>(pub W);
+
+impl<W: Hash> Hash for ContractChecked<W> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
+impl<W: Hash + PartialEq> PartialEq for ContractChecked<W> {
+    fn eq(&self, other: &Self) -> bool {
+        let equal = self.0 == other.0;
+        debug_assert_contract(equal, &self.0, &other.0);
+        equal
+    }
+}
+
+impl<W: Hash + Eq> Eq for ContractChecked<W> {}
+
+impl<W> Deref for ContractChecked<W> {
+    type Target = W;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<W> DerefMut for ContractChecked<W> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// The error when values compare equal, but hash differently
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ContractViolation {
+    type_name: &'static str,
+    hashes: (u64, u64),
+}
+
+impl ContractViolation {
+    /// The name of the type whose values violate the contract, as given by
+    /// [`std::any::type_name`]
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// The differing hashes of the two values
+    pub fn hashes(&self) -> (u64, u64) {
+        self.hashes
+    }
+}
+
+impl Display for ContractViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "values of {} compare equal but hash differently ({:#018x} and {:#018x}), \
+             violating the contract between Hash and Eq",
+            self.type_name, self.hashes.0, self.hashes.1
+        )
+    }
+}
+
+impl Error for ContractViolation {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SumHashes, SumHashesAnyCollection};
+    use std::collections::hash_map::RandomState;
+    use std::collections::HashSet;
+
+    /// Deliberately compares without regard to order, yet hashes in order
+    #[derive(Debug)]
+    struct OrderlessVec(Vec<u8>);
+
+    impl Hash for OrderlessVec {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            self.0.hash(state)
+        }
+    }
+
+    impl PartialEq for OrderlessVec {
+        fn eq(&self, other: &Self) -> bool {
+            let mut mine = self.0.clone();
+            let mut theirs = other.0.clone();
+            mine.sort_unstable();
+            theirs.sort_unstable();
+            mine == theirs
+        }
+    }
+
+    impl Eq for OrderlessVec {}
+
+    #[test]
+    fn reports_violation() {
+        let violation =
+            check_hash_eq_contract(&OrderlessVec(vec![1, 2, 3]), &OrderlessVec(vec![3, 2, 1]))
+                .unwrap_err();
+        assert!(violation.type_name().ends_with("OrderlessVec"));
+        assert_ne!(violation.hashes().0, violation.hashes().1);
+        let message = violation.to_string();
+        assert!(message.contains("OrderlessVec"), "{}", message);
+        assert!(message.contains("compare equal but hash differently"));
+
+        // Unequal values are not held to the contract
+        assert_eq!(
+            Ok(()),
+            check_hash_eq_contract(&OrderlessVec(vec![1]), &OrderlessVec(vec![2]))
+        );
+    }
+
+    #[test]
+    fn wrappers_uphold_contract() {
+        let values = [5, 10, 15, 20, 25, 30];
+        let wrapped = SumHashesAnyCollection::<_>::new(values.iter().collect::<HashSet<_>>());
+        let reversed =
+            SumHashesAnyCollection::<_>::new(values.iter().rev().collect::<HashSet<_>>());
+        assert_eq!(Ok(()), check_hash_eq_contract(&wrapped, &reversed));
+
+        let state = RandomState::new();
+        let mut first = HashSet::with_hasher(state.clone());
+        let mut second = HashSet::with_hasher(state);
+        first.extend(values);
+        second.extend(values.iter().rev());
+        assert_eq!(
+            Ok(()),
+            check_hash_eq_contract(&SumHashes::new(first), &SumHashes::new(second))
+        );
+    }
+
+    #[cfg(not(feature = "debug-assert"))]
+    #[test]
+    fn reports_differently_seeded_wrappers() {
+        let values = 0..32;
+        let first = SumHashes::new(values.clone().collect::<HashSet<_>>());
+        let second = SumHashes::new(values.collect::<HashSet<_>>());
+        let violation = check_hash_eq_contract(&first, &second).unwrap_err();
+        assert!(
+            violation.to_string().contains("SumHashes<"),
+            "{}",
+            violation
+        );
+    }
+
+    #[cfg(all(feature = "debug-assert", debug_assertions))]
+    #[test]
+    #[should_panic(expected = "compare equal but hash differently")]
+    fn wrapper_comparison_asserts() {
+        let values = 0..32;
+        let first = SumHashes::new(values.clone().collect::<HashSet<_>>());
+        let second = SumHashes::new(values.collect::<HashSet<_>>());
+        let _ = first == second;
+    }
+
+    #[cfg(all(feature = "debug-assert", debug_assertions))]
+    #[test]
+    #[should_panic(expected = "XorHashes<")]
+    fn wrapper_assertion_names_the_wrapper() {
+        let values = 0..32;
+        let first = crate::XorHashes::new(values.clone().collect::<HashSet<_>>());
+        let second = crate::XorHashes::new(values.collect::<HashSet<_>>());
+        let _ = first == second;
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "compare equal but hash differently")]
+    fn checked_comparison_asserts() {
+        let values = 0..32;
+        let first = ContractChecked(SumHashes::new(values.clone().collect::<HashSet<_>>()));
+        let second = ContractChecked(SumHashes::new(values.collect::<HashSet<_>>()));
+        let _ = first == second;
+    }
+
+    #[test]
+    fn checked_comparison_of_upheld_contract() {
+        let first = ContractChecked(OrderlessVec(vec![1]));
+        assert!(first != ContractChecked(OrderlessVec(vec![2])));
+        assert!(first == ContractChecked(OrderlessVec(vec![1])));
+        assert_eq!(vec![1], first.0 .0);
+    }
+}
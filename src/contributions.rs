@@ -0,0 +1,233 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Side tables of each entry's contribution to a digest, for repeated diffing
+
+use crate::trace::DigestSpan;
+use crate::{hash_of, BuildHasherFromFriend};
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::num::Wrapping;
+
+///
+/// Builds a [`ContributionIndex`] of a map-like collection, storing each entry's hash keyed by a
+/// clone of its key. Entries are hashed as `(&K, &V)` tuples, each by a new hasher from `BH`,
+/// so the index's [`ContributionIndex::digest`] is the value which
+/// [`crate::hash_by_summing_hashes_with`] writes for the same collection and strategy.
+///
+pub fn precompute_contributions<C, BH, K, V>(collection: &C) -> ContributionIndex<K>
+where
+    for<'c> &'c C: IntoIterator<Item = (&'c K, &'c V)>,
+    BH: BuildHasherFromFriend<C>,
+    K: Hash + Eq + Clone,
+    V: Hash,
+{
+    let expected = collection.into_iter().size_hint().0;
+    let mut span = DigestSpan::enter::<BH>("precompute_contributions", expected);
+    let mut index = ContributionIndex::default();
+    for (key, value) in collection {
+        let contribution = entry_contribution::<C, BH, K, V>(collection, key, value);
+        index.update(key.clone(), contribution);
+        span.add_elements(1);
+    }
+    span.finish(index.digest());
+    index
+}
+
+///
+/// Computes the contribution of one entry, as [`precompute_contributions`] does, for passing to
+/// [`ContributionIndex::update`] after the entry changes
+///
+pub fn entry_contribution<C, BH, K, V>(collection: &C, key: &K, value: &V) -> u64
+where
+    BH: BuildHasherFromFriend<C>,
+    K: Hash + ?Sized,
+    V: Hash + ?Sized,
+{
+    hash_of(&(key, value), BH::build_hasher_from(collection))
+}
+
+///
+/// The contribution of each key of a collection to its digest. The digest is kept up to date
+/// as contributions are updated, so it is available in constant time, and two indexes can be
+/// compared without rehashing any entries.
+///
+/// The index must be kept consistent with the collection by the caller, by updating or
+/// removing the contribution of every key which changes.
+///
+#[derive(Clone, Debug)]
+pub struct ContributionIndex<K> {
+    contributions: HashMap<K, u64>,
+    sum: Wrapping<u64>,
+}
+
+impl<K> Default for ContributionIndex<K> {
+    fn default() -> Self {
+        Self {
+            contributions: HashMap::new(),
+            sum: Wrapping(0),
+        }
+    }
+}
+
+impl<K: Hash + Eq> ContributionIndex<K> {
+    /// The digest of the indexed collection, i.e. the wrapping sum of the contributions
+    pub fn digest(&self) -> u64 {
+        self.sum.0
+    }
+
+    /// Sets the contribution of a key, which is inserted or changed. Returns the previous value.
+    pub fn update(&mut self, key: K, contribution: u64) -> Option<u64> {
+        self.sum += contribution;
+        let previous = self.contributions.insert(key, contribution);
+        if let Some(previous) = previous {
+            self.sum -= previous;
+        }
+        previous
+    }
+
+    /// Removes the contribution of a key, which was removed. Returns the previous value.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<u64>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let previous = self.contributions.remove(key);
+        if let Some(previous) = previous {
+            self.sum -= previous;
+        }
+        previous
+    }
+
+    /// The contribution of a key, if it is present
+    pub fn contribution<Q>(&self, key: &Q) -> Option<u64>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.contributions.get(key).copied()
+    }
+
+    /// The number of keys
+    pub fn len(&self) -> usize {
+        self.contributions.len()
+    }
+
+    /// Whether there are no keys
+    pub fn is_empty(&self) -> bool {
+        self.contributions.is_empty()
+    }
+}
+
+impl<K: Hash + Eq + Clone> ContributionIndex<K> {
+    ///
+    /// Returns the keys whose entries differ between the two indexes: those present in only one
+    /// of them, and those with different contributions. This takes one lookup per key. If the
+    /// digests are equal, the result is empty without any lookups, up to collisions of the
+    /// digest. The order of the keys is unspecified.
+    ///
+    pub fn diff(&self, other: &Self) -> Vec<K> {
+        if self.sum == other.sum && self.len() == other.len() {
+            return Vec::new();
+        }
+        let mut keys: Vec<K> = self
+            .contributions
+            .iter()
+            .filter(|(key, contribution)| other.contributions.get(*key) != Some(*contribution))
+            .map(|(key, _)| key.clone())
+            .collect();
+        keys.extend(
+            other
+                .contributions
+                .keys()
+                .filter(|key| !self.contributions.contains_key(*key))
+                .cloned(),
+        );
+        keys
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mix::splitmix64;
+    use crate::{hash_by_summing_hashes_with, UseDefaultHasher};
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::HashSet;
+    use std::hash::Hasher;
+
+    type Map = HashMap<u32, u64>;
+
+    fn standard_digest(map: &Map) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        hash_by_summing_hashes_with::<Map, _, UseDefaultHasher>(map, &mut hasher);
+        hasher.finish()
+    }
+
+    fn index_digest(index: &ContributionIndex<u32>) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        hasher.write_u64(index.digest());
+        hasher.finish()
+    }
+
+    #[test]
+    fn consistent_across_random_mutations() {
+        let mut map: Map = (0..200).map(|key| (key, u64::from(key) * 3)).collect();
+        let mut index = precompute_contributions::<Map, UseDefaultHasher, _, _>(&map);
+        assert_eq!(standard_digest(&map), index_digest(&index));
+
+        let mut random = 17_u64;
+        for _ in 0..2000 {
+            random = splitmix64(random);
+            let key = (random % 300) as u32;
+            if random >> 62 == 0 {
+                map.remove(&key);
+                index.remove(&key);
+            } else {
+                let value = random >> 32;
+                map.insert(key, value);
+                let contribution =
+                    entry_contribution::<Map, UseDefaultHasher, _, _>(&map, &key, &value);
+                index.update(key, contribution);
+            }
+        }
+        assert_eq!(map.len(), index.len());
+        assert_eq!(standard_digest(&map), index_digest(&index));
+        let rebuilt = precompute_contributions::<Map, UseDefaultHasher, _, _>(&map);
+        assert_eq!(rebuilt.digest(), index.digest());
+        assert!(rebuilt.diff(&index).is_empty());
+    }
+
+    #[test]
+    fn diff_finds_changed_keys() {
+        let ours: Map = (0..50).map(|key| (key, 0)).collect();
+        let mut theirs = ours.clone();
+        theirs.insert(3, 1);
+        theirs.remove(&10);
+        theirs.insert(99, 0);
+        let our_index = precompute_contributions::<Map, UseDefaultHasher, _, _>(&ours);
+        let their_index = precompute_contributions::<Map, UseDefaultHasher, _, _>(&theirs);
+
+        let expected: HashSet<u32> = [3, 10, 99].into_iter().collect();
+        let diff: HashSet<u32> = our_index.diff(&their_index).into_iter().collect();
+        assert_eq!(expected, diff);
+        let reverse: HashSet<u32> = their_index.diff(&our_index).into_iter().collect();
+        assert_eq!(expected, reverse);
+        assert_eq!(None, their_index.contribution(&10));
+        assert_ne!(our_index.contribution(&3), their_index.contribution(&3));
+    }
+}
@@ -0,0 +1,286 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Change detection against a previously computed digest, and digests for set reconciliation
+
+use crate::{hash_of, hashes_of, BuildHasherFromFriend, UseDefaultHasher};
+use std::hash::Hash;
+use std::num::Wrapping;
+
+///
+/// Compares a collection with a baseline, given only the baseline's digest and element count,
+/// as recorded by [`crate::PartialHash::of`]. Elements are hashed with a new
+/// [`std::collections::hash_map::DefaultHasher`] each, and counted, in a single pass.
+///
+/// This is lighter than keeping the baseline collection around, but it reports only whether
+/// the content changed and how the element count changed, not which elements differ.
+///
+pub fn delta_hash<C>(current: &C, baseline_hash: u64, baseline_count: usize) -> DeltaReport
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash,
+{
+    delta_hash_with::<C, UseDefaultHasher>(current, baseline_hash, baseline_count)
+}
+
+///
+/// Compares a collection with a baseline like [`delta_hash`], with a means of specifying which
+/// kind of hasher is created per element via the `BH` parameter. The baseline must have been
+/// hashed the same way.
+///
+pub fn delta_hash_with<C, BH>(current: &C, baseline_hash: u64, baseline_count: usize) -> DeltaReport
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash,
+    BH: BuildHasherFromFriend<C>,
+{
+    let mut sum = Wrapping::default();
+    let mut count = 0;
+    for hash in hashes_of(current, || BH::build_hasher_from(current)) {
+        sum += hash;
+        count += 1;
+    }
+    DeltaReport {
+        hash: sum.0,
+        changed: sum.0 != baseline_hash || count != baseline_count,
+        count,
+        baseline_count,
+    }
+}
+
+/// The result of comparing a collection with a baseline, from [`delta_hash`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DeltaReport {
+    hash: u64,
+    changed: bool,
+    count: usize,
+    baseline_count: usize,
+}
+
+impl DeltaReport {
+    /// Whether the content changed, i.e. the digest or the element count differs from the
+    /// baseline. Like any comparison of digests, an unchanged result may be a collision.
+    pub fn changed(&self) -> bool {
+        self.changed
+    }
+
+    /// The digest of the current collection, which can serve as the next baseline
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// The number of elements in the current collection
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// The change in element count from the baseline, saturating at the bounds of `isize`
+    pub fn count_delta(&self) -> isize {
+        if self.count >= self.baseline_count {
+            isize::try_from(self.count - self.baseline_count).unwrap_or(isize::MAX)
+        } else {
+            isize::try_from(self.baseline_count - self.count)
+                .map(|removed| -removed)
+                .unwrap_or(isize::MIN)
+        }
+    }
+}
+
+///
+/// Returns the hashes of the elements of a collection, sorted and deduplicated. Each element is
+/// hashed by a new hasher from `BH`.
+///
+/// Two peers can exchange these digests and merge them, as sorted sequences, to find which
+/// element hashes either one lacks. This is a building block for set synchronization: the
+/// peers then request or send the elements behind the hashes which they do not share. Both
+/// must hash their elements with the same deterministic strategy.
+///
+/// Duplicate elements, and distinct elements whose hashes collide, contribute one hash. The
+/// digest is therefore exact only for sets, up to collisions.
+///
+pub fn reconciliation_digest<C, BH>(collection: &C) -> Vec<u64>
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash,
+    BH: BuildHasherFromFriend<C>,
+{
+    let mut hashes: Vec<u64> =
+        hashes_of(collection, || BH::build_hasher_from(collection)).collect();
+    hashes.sort_unstable();
+    hashes.dedup();
+    hashes
+}
+
+///
+/// Checks whether an element is among the commitments of a collection, i.e. its
+/// [`reconciliation_digest`]. The element is hashed by a new hasher from `BH`, which may take
+/// its hasher from `friend`, as when the digest was computed; its hash is then found by binary
+/// search, so `commitments` must be sorted.
+///
+/// This is a lightweight inclusion proof, up to collisions: an element whose hash collides with
+/// a committed one is reported as present.
+///
+pub fn verify_membership<T, C, BH>(element: &T, friend: &C, commitments: &[u64]) -> bool
+where
+    T: Hash + ?Sized,
+    BH: BuildHasherFromFriend<C>,
+{
+    let hash = hash_of(element, BH::build_hasher_from(friend));
+    commitments.binary_search(&hash).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PartialHash;
+    use std::collections::HashSet;
+    use std::hash::Hasher;
+
+    fn baseline() -> (HashSet<&'static str>, PartialHash) {
+        let set: HashSet<&str> = ["alpha", "beta", "gamma"].into_iter().collect();
+        let partial = PartialHash::of::<HashSet<&str>>(&set);
+        (set, partial)
+    }
+
+    #[test]
+    fn unchanged() {
+        let (set, baseline) = baseline();
+        let reordered: Vec<&str> = vec!["gamma", "alpha", "beta"];
+        for report in [
+            delta_hash::<HashSet<&str>>(&set, baseline.sum, baseline.count),
+            delta_hash::<Vec<&str>>(&reordered, baseline.sum, baseline.count),
+        ] {
+            assert!(!report.changed());
+            assert_eq!(0, report.count_delta());
+            assert_eq!(baseline.sum, report.hash());
+        }
+    }
+
+    #[test]
+    fn added_element() {
+        let (mut set, baseline) = baseline();
+        set.insert("delta");
+        let report = delta_hash::<HashSet<&str>>(&set, baseline.sum, baseline.count);
+        assert!(report.changed());
+        assert_eq!(1, report.count_delta());
+        assert_eq!(4, report.count());
+        assert_eq!(PartialHash::of::<HashSet<&str>>(&set).sum, report.hash());
+    }
+
+    #[test]
+    fn removed_or_replaced_elements() {
+        let (mut set, baseline) = baseline();
+        set.remove("alpha");
+        set.remove("beta");
+        let report = delta_hash::<HashSet<&str>>(&set, baseline.sum, baseline.count);
+        assert!(report.changed());
+        assert_eq!(-2, report.count_delta());
+
+        // Same count, different content
+        set.insert("omega");
+        set.insert("psi");
+        let report = delta_hash::<HashSet<&str>>(&set, baseline.sum, baseline.count);
+        assert!(report.changed());
+        assert_eq!(0, report.count_delta());
+    }
+
+    /// Merges two sorted digests, into the hashes only in the first, and those only in the second
+    fn diff(ours: &[u64], theirs: &[u64]) -> (Vec<u64>, Vec<u64>) {
+        let (mut only_ours, mut only_theirs) = (Vec::new(), Vec::new());
+        let (mut i, mut j) = (0, 0);
+        while i < ours.len() && j < theirs.len() {
+            match ours[i].cmp(&theirs[j]) {
+                std::cmp::Ordering::Less => {
+                    only_ours.push(ours[i]);
+                    i += 1;
+                }
+                std::cmp::Ordering::Greater => {
+                    only_theirs.push(theirs[j]);
+                    j += 1;
+                }
+                std::cmp::Ordering::Equal => {
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        only_ours.extend_from_slice(&ours[i..]);
+        only_theirs.extend_from_slice(&theirs[j..]);
+        (only_ours, only_theirs)
+    }
+
+    fn element_hash(value: &str) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn reconciliation_digests_diff_to_differences() {
+        let ours: HashSet<&str> = ["alpha", "beta", "gamma", "delta"].into_iter().collect();
+        let theirs = vec!["delta", "epsilon", "beta", "beta", "zeta"];
+        let our_digest = reconciliation_digest::<HashSet<&str>, UseDefaultHasher>(&ours);
+        let their_digest = reconciliation_digest::<Vec<&str>, UseDefaultHasher>(&theirs);
+        assert_eq!(4, our_digest.len());
+        assert_eq!(4, their_digest.len());
+        assert!(their_digest.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let (only_ours, only_theirs) = diff(&our_digest, &their_digest);
+        let mut expected_ours = vec![element_hash("alpha"), element_hash("gamma")];
+        let mut expected_theirs = vec![element_hash("epsilon"), element_hash("zeta")];
+        expected_ours.sort_unstable();
+        expected_theirs.sort_unstable();
+        assert_eq!(expected_ours, only_ours);
+        assert_eq!(expected_theirs, only_theirs);
+
+        let same = vec!["delta", "gamma", "beta", "alpha", "gamma"];
+        let same_digest = reconciliation_digest::<Vec<&str>, UseDefaultHasher>(&same);
+        assert_eq!(our_digest, same_digest);
+        assert_eq!((vec![], vec![]), diff(&our_digest, &same_digest));
+        assert!(reconciliation_digest::<Vec<&str>, UseDefaultHasher>(&vec![]).is_empty());
+    }
+
+    #[test]
+    fn membership_against_commitments() {
+        let set: HashSet<&str> = ["alpha", "beta", "gamma"].into_iter().collect();
+        let commitments = reconciliation_digest::<HashSet<&str>, UseDefaultHasher>(&set);
+        for present in ["alpha", "beta", "gamma"] {
+            assert!(verify_membership::<_, _, UseDefaultHasher>(
+                &present,
+                &set,
+                &commitments
+            ));
+        }
+        for absent in ["delta", "", "Alpha"] {
+            assert!(!verify_membership::<_, _, UseDefaultHasher>(
+                &absent,
+                &set,
+                &commitments
+            ));
+        }
+        // Hashing a reference is the same as hashing the element
+        assert!(verify_membership::<str, _, UseDefaultHasher>(
+            "beta",
+            &set,
+            &commitments
+        ));
+        assert!(!verify_membership::<_, _, UseDefaultHasher>(
+            &"alpha",
+            &set,
+            &[]
+        ));
+    }
+}
@@ -0,0 +1,207 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Statistics for comparing hashers and profiling hashing
+
+use crate::{hashes_of, sum_of_hashes, BuildHasherFromFriend};
+use std::collections::HashSet;
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+///
+/// Distribution statistics of one hasher's per-element hashes over a collection
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HasherStats {
+    /// The number of elements hashed
+    pub elements: usize,
+    /// The number of elements whose hash equals that of a previously hashed element
+    pub collisions: usize,
+    /// The mean Shannon entropy of each output bit, between `0.0` and `1.0`. A well-distributed
+    /// hasher approaches `1.0`, whereas bits which never vary lower the figure.
+    pub entropy: f64,
+}
+
+///
+/// The result of [`compare_hashers`], holding statistics for each candidate hasher
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HasherComparison {
+    /// Statistics for the first hasher, `A`
+    pub first: HasherStats,
+    /// Statistics for the second hasher, `B`
+    pub second: HasherStats,
+}
+
+///
+/// Compares the distribution quality of two candidate hashers over the elements of a
+/// collection. This helps in choosing between [`crate::UseDefaultHasher`] and a faster
+/// alternative, for a particular kind of data. Note that collisions include duplicate elements,
+/// which necessarily hash identically.
+///
+pub fn compare_hashers<C, A, B>(collection: &C) -> HasherComparison
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash,
+    A: BuildHasherFromFriend<C>,
+    B: BuildHasherFromFriend<C>,
+{
+    HasherComparison {
+        first: hasher_stats::<C, A>(collection),
+        second: hasher_stats::<C, B>(collection),
+    }
+}
+
+fn hasher_stats<C, BH>(collection: &C) -> HasherStats
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash,
+    BH: BuildHasherFromFriend<C>,
+{
+    let mut distinct = HashSet::new();
+    let mut set_bits = [0usize; 64];
+    let mut elements = 0;
+    for hash in hashes_of(collection, || BH::build_hasher_from(collection)) {
+        distinct.insert(hash);
+        for (bit, count) in set_bits.iter_mut().enumerate() {
+            *count += ((hash >> bit) & 1) as usize;
+        }
+        elements += 1;
+    }
+    let entropy = if elements == 0 {
+        0.0
+    } else {
+        let bit_entropy = |count: usize| {
+            let p = count as f64 / elements as f64;
+            [p, 1.0 - p]
+                .iter()
+                .filter(|p| **p > 0.0)
+                .map(|p| -p * p.log2())
+                .sum::<f64>()
+        };
+        set_bits.iter().copied().map(bit_entropy).sum::<f64>() / 64.0
+    };
+    HasherStats {
+        elements,
+        collisions: elements - distinct.len(),
+        entropy,
+    }
+}
+
+///
+/// Timing statistics reported by [`hash_profiled`]
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ProfileStats {
+    /// The time taken to hash the whole collection
+    pub elapsed: Duration,
+    /// The number of elements hashed
+    pub elements: usize,
+    /// The mean time taken per element, or zero for an empty collection
+    pub per_element: Duration,
+}
+
+///
+/// Computes the wrapping sum of element hashes, which [`crate::hash_by_summing_hashes_with`]
+/// writes, while measuring how long it takes. Each element is hashed by a new hasher from `BH`.
+///
+/// This helps in deciding whether a faster hasher or combiner is worth its trade-offs for a
+/// particular workload. The time is measured with [`Instant`], which is unsupported on some
+/// targets, such as `wasm32-unknown-unknown`.
+///
+pub fn hash_profiled<C, BH>(collection: &C) -> (u64, ProfileStats)
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash,
+    BH: BuildHasherFromFriend<C>,
+{
+    let mut elements = 0;
+    let start = Instant::now();
+    let hash = sum_of_hashes(collection, || {
+        elements += 1;
+        BH::build_hasher_from(collection)
+    });
+    let elapsed = start.elapsed();
+    let per_element = match elements {
+        0 => Duration::ZERO,
+        _ => Duration::from_nanos((elapsed.as_nanos() / elements as u128) as u64),
+    };
+    let stats = ProfileStats {
+        elapsed,
+        elements,
+        per_element,
+    };
+    (hash, stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::UseMaskedHasher;
+    use crate::UseDefaultHasher;
+    use std::hash::Hasher;
+
+    #[test]
+    fn detects_colliding_hasher() {
+        let collection: Vec<u32> = (0..64).collect();
+        let comparison =
+            compare_hashers::<Vec<u32>, UseDefaultHasher, UseMaskedHasher<0b11>>(&collection);
+
+        let default = comparison.first;
+        assert_eq!(64, default.elements);
+        assert_eq!(0, default.collisions);
+        assert!(default.entropy > 0.9, "{}", default.entropy);
+
+        let masked = comparison.second;
+        assert_eq!(64, masked.elements);
+        assert_eq!(60, masked.collisions);
+        assert!(masked.entropy <= 2.0 / 64.0, "{}", masked.entropy);
+    }
+
+    #[test]
+    fn empty_collection() {
+        let comparison = compare_hashers::<Vec<u32>, UseDefaultHasher, UseDefaultHasher>(&vec![]);
+        assert_eq!(0, comparison.first.elements);
+        assert_eq!(0, comparison.second.collisions);
+        assert_eq!(0.0, comparison.first.entropy);
+    }
+
+    #[test]
+    fn profiled_hash_matches_plain_path() {
+        use crate::hash_by_summing_hashes_with;
+        use std::collections::hash_map::DefaultHasher;
+
+        let collection: Vec<u32> = (0..1000).collect();
+        let (hash, stats) = hash_profiled::<Vec<u32>, UseDefaultHasher>(&collection);
+        let mut hasher = DefaultHasher::new();
+        hash_by_summing_hashes_with::<Vec<u32>, _, UseDefaultHasher>(&collection, &mut hasher);
+        let mut expected = DefaultHasher::new();
+        expected.write_u64(hash);
+        assert_eq!(expected.finish(), hasher.finish());
+
+        assert_eq!(1000, stats.elements);
+        assert!(stats.per_element <= stats.elapsed);
+        assert!(stats.per_element * 1000 <= stats.elapsed + Duration::from_nanos(1000));
+    }
+
+    #[test]
+    fn profiles_empty_collection() {
+        let (hash, stats) = hash_profiled::<Vec<u32>, UseDefaultHasher>(&vec![]);
+        assert_eq!(0, hash);
+        assert_eq!(0, stats.elements);
+        assert_eq!(Duration::ZERO, stats.per_element);
+    }
+}
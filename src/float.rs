@@ -0,0 +1,227 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Hashing of floating-point numbers, under a canonicalization policy
+
+use crate::sum_of_hashes;
+use std::collections::hash_map::DefaultHasher;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl Sealed for f32 {}
+    impl Sealed for f64 {}
+}
+
+///
+/// A floating-point type, either `f32` or `f64`, which may be hashed by its canonical bits.
+///
+/// The canonicalization policy is:
+/// * `-0.0` is collapsed to `0.0`, since the two compare equal
+/// * every NaN, regardless of sign or payload, is collapsed to a single NaN
+/// * all other values, including the infinities, are hashed by their bits
+///
+pub trait Float: Copy + sealed::Sealed {
+    /// The bit representation
+    type Bits: Hash + Eq;
+
+    /// Returns the bits of the canonical value which represents this one
+    fn canonical_bits(self) -> Self::Bits;
+
+    /// Whether this value is any NaN
+    fn is_nan(self) -> bool;
+}
+
+macro_rules! impl_float {
+    ($float:ty, $bits:ty) => {
+        impl Float for $float {
+            type Bits = $bits;
+
+            fn canonical_bits(self) -> $bits {
+                if self.is_nan() {
+                    <$float>::NAN.to_bits()
+                } else if self == 0.0 {
+                    0
+                } else {
+                    self.to_bits()
+                }
+            }
+
+            fn is_nan(self) -> bool {
+                <$float>::is_nan(self)
+            }
+        }
+    };
+}
+
+impl_float!(f32, u32);
+impl_float!(f64, u64);
+
+///
+/// Makes a floating-point number hashable, by its canonical bits as described by [`Float`].
+/// Equality is consistent with hashing: `-0.0` equals `0.0`, and every NaN equals every other
+/// NaN. Hence, this wrapper may be used as an element of a `HashSet`.
+///
+#[derive(Clone, Copy, Debug, Default)]
+#[repr(transparent)]
+pub struct FloatBits<F>(pub F);
+
+impl<F: Float> PartialEq for FloatBits<F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.canonical_bits() == other.0.canonical_bits()
+    }
+}
+
+impl<F: Float> Eq for FloatBits<F> {}
+
+impl<F: Float> Hash for FloatBits<F> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.canonical_bits().hash(state)
+    }
+}
+
+impl<F> Deref for FloatBits<F> {
+    type Target = F;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+///
+/// Implements hashing by summing the hashes of each floating-point element, canonicalized as
+/// described by [`Float`]. A new [`DefaultHasher`] is created for each element. The result is
+/// identical to hashing the same elements wrapped in [`FloatBits`] with
+/// [`crate::hash_by_summing_hashes`].
+///
+pub fn hash_floats_by_summing<C, F, H>(collection: &C, state: &mut H)
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Deref<Target = F>,
+    F: Float,
+    H: Hasher,
+{
+    let bits = collection.into_iter().map(|value| FloatBits(*value));
+    state.write_u64(sum_of_hashes(bits, DefaultHasher::new));
+}
+
+///
+/// Implements hashing like [`hash_floats_by_summing`], but rejects any NaN rather than
+/// canonicalizing it. If the collection contains a NaN, nothing is written to `state`.
+///
+pub fn try_hash_floats_by_summing<C, F, H>(collection: &C, state: &mut H) -> Result<(), FoundNan>
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Deref<Target = F>,
+    F: Float,
+    H: Hasher,
+{
+    if collection.into_iter().any(|value| value.is_nan()) {
+        return Err(FoundNan(()));
+    }
+    hash_floats_by_summing(collection, state);
+    Ok(())
+}
+
+/// The error when a NaN is found where it is not permitted
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FoundNan(());
+
+impl Display for FoundNan {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("found a NaN value")
+    }
+}
+
+impl Error for FoundNan {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash_by_summing_hashes;
+    use std::collections::HashSet;
+
+    fn hash_floats(values: &[f64]) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        hash_floats_by_summing::<Vec<f64>, _, _>(&values.to_vec(), &mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn nans_and_zeros_collapse() {
+        let quiet_payload = f64::from_bits(0x7ff8_0000_0000_0001);
+        let signaling = f64::from_bits(0x7ff0_0000_0000_0001);
+        for nan in [quiet_payload, signaling, -f64::NAN] {
+            assert!(nan.is_nan());
+            assert_eq!(FloatBits(f64::NAN), FloatBits(nan));
+            assert_eq!(hash_floats(&[f64::NAN]), hash_floats(&[nan]));
+        }
+        assert_eq!(FloatBits(0.0_f32), FloatBits(-0.0));
+        assert_eq!(hash_floats(&[0.0, 1.0]), hash_floats(&[-0.0, 1.0]));
+
+        let set: HashSet<FloatBits<f64>> = [0.0, -0.0, f64::NAN, quiet_payload, 1.0]
+            .into_iter()
+            .map(FloatBits)
+            .collect();
+        assert_eq!(3, set.len());
+    }
+
+    #[test]
+    fn infinities_are_distinct() {
+        let distinct = [f64::INFINITY, f64::NEG_INFINITY, f64::NAN, 0.0, f64::MAX];
+        for (index, first) in distinct.iter().enumerate() {
+            for second in &distinct[index + 1..] {
+                assert_ne!(FloatBits(*first), FloatBits(*second));
+                assert_ne!(hash_floats(&[*first]), hash_floats(&[*second]));
+            }
+        }
+    }
+
+    #[test]
+    fn permutations_and_wrapping_agree() {
+        let values = [1.5, -0.0, f64::NEG_INFINITY, f64::NAN, 1e300];
+        let reversed: Vec<f64> = values.iter().rev().copied().collect();
+        assert_eq!(hash_floats(&values), hash_floats(&reversed));
+
+        let wrapped: Vec<FloatBits<f64>> = values.iter().copied().map(FloatBits).collect();
+        let mut hasher = DefaultHasher::new();
+        hash_by_summing_hashes::<Vec<FloatBits<f64>>, _>(&wrapped, &mut hasher);
+        assert_eq!(hash_floats(&values), hasher.finish());
+    }
+
+    #[test]
+    fn fallible_variant_rejects_nan() {
+        let mut hasher = DefaultHasher::new();
+        let untouched = hasher.finish();
+        let with_nan = vec![1.0_f32, f32::NAN];
+        assert_eq!(
+            Err(FoundNan(())),
+            try_hash_floats_by_summing::<Vec<f32>, _, _>(&with_nan, &mut hasher)
+        );
+        assert_eq!(untouched, hasher.finish());
+
+        let without_nan = vec![1.0_f32, -0.0];
+        assert_eq!(
+            Ok(()),
+            try_hash_floats_by_summing::<Vec<f32>, _, _>(&without_nan, &mut hasher)
+        );
+        assert_ne!(untouched, hasher.finish());
+    }
+}
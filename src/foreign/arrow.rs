@@ -0,0 +1,218 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Multiset hashing of the values of Arrow arrays
+
+use crate::sum_of_hashes;
+use ::arrow_array::cast::AsArray;
+use ::arrow_array::types::{
+    Float32Type, Float64Type, Int16Type, Int32Type, Int64Type, Int8Type, UInt16Type, UInt32Type,
+    UInt64Type, UInt8Type,
+};
+use ::arrow_array::Array;
+use ::arrow_schema::DataType;
+use std::collections::hash_map::DefaultHasher;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::hash::{Hash, Hasher};
+
+///
+/// Hashes an Arrow array as a multiset of its values, by summing the hash of each value. A new
+/// [`DefaultHasher`] is created for each value. This compares columns across partitions or
+/// datasets without sorting them.
+///
+/// Integer, boolean, string and binary arrays hash like a `Vec` of their values under
+/// [`crate::hash_by_summing_hashes`]: an `Int64Array` like a `Vec<i64>`, a `StringArray` like
+/// a `Vec<&str>`, and a `BinaryArray` like a `Vec<&[u8]>`. Floating-point values are hashed by
+/// their bits, as by `f64::to_bits`. Sliced arrays hash their visible values only.
+///
+/// Each null is hashed as a sentinel element: the bytes `[0xff, 0xff, 0xff]`, which no value
+/// of a supported type writes. Nulls are therefore counted: an array with two nulls differs
+/// from the same array with one, and from the array without nulls.
+///
+/// Arrays of other types, such as dictionaries, lists and structs, are rejected with
+/// [`UnsupportedType`], in which case nothing is written to `state`.
+///
+pub fn hash_arrow_array<H: Hasher>(
+    array: &dyn Array,
+    state: &mut H,
+) -> Result<(), UnsupportedType> {
+    let unsupported = || UnsupportedType {
+        data_type: array.data_type().clone(),
+    };
+    macro_rules! digest {
+        ($values:expr) => {
+            match $values {
+                Some(values) => sum_of_hashes(values.map(NullOr), DefaultHasher::new),
+                None => return Err(unsupported()),
+            }
+        };
+    }
+    let digest = match array.data_type() {
+        DataType::Int8 => digest!(array.as_primitive_opt::<Int8Type>().map(|a| a.iter())),
+        DataType::Int16 => digest!(array.as_primitive_opt::<Int16Type>().map(|a| a.iter())),
+        DataType::Int32 => digest!(array.as_primitive_opt::<Int32Type>().map(|a| a.iter())),
+        DataType::Int64 => digest!(array.as_primitive_opt::<Int64Type>().map(|a| a.iter())),
+        DataType::UInt8 => digest!(array.as_primitive_opt::<UInt8Type>().map(|a| a.iter())),
+        DataType::UInt16 => digest!(array.as_primitive_opt::<UInt16Type>().map(|a| a.iter())),
+        DataType::UInt32 => digest!(array.as_primitive_opt::<UInt32Type>().map(|a| a.iter())),
+        DataType::UInt64 => digest!(array.as_primitive_opt::<UInt64Type>().map(|a| a.iter())),
+        DataType::Float32 => digest!(array
+            .as_primitive_opt::<Float32Type>()
+            .map(|a| a.iter().map(|value| value.map(f32::to_bits)))),
+        DataType::Float64 => digest!(array
+            .as_primitive_opt::<Float64Type>()
+            .map(|a| a.iter().map(|value| value.map(f64::to_bits)))),
+        DataType::Boolean => digest!(array.as_boolean_opt().map(|a| a.iter())),
+        DataType::Utf8 => digest!(array.as_string_opt::<i32>().map(|a| a.iter())),
+        DataType::LargeUtf8 => digest!(array.as_string_opt::<i64>().map(|a| a.iter())),
+        DataType::Binary => digest!(array.as_binary_opt::<i32>().map(|a| a.iter())),
+        DataType::LargeBinary => digest!(array.as_binary_opt::<i64>().map(|a| a.iter())),
+        _ => return Err(unsupported()),
+    };
+    state.write_u64(digest);
+    Ok(())
+}
+
+/// A value, or null, which is hashed as the value or as the sentinel
+struct NullOr<T>(Option<T>);
+
+impl<T: Hash> Hash for NullOr<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match &self.0 {
+            Some(value) => value.hash(state),
+            None => state.write(&[0xff; 3]),
+        }
+    }
+}
+
+/// The error when an Arrow array's type is not supported by [`hash_arrow_array`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnsupportedType {
+    data_type: DataType,
+}
+
+impl UnsupportedType {
+    /// The type of the array
+    pub fn data_type(&self) -> &DataType {
+        &self.data_type
+    }
+}
+
+impl Display for UnsupportedType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "arrays of type {} cannot be hashed", self.data_type)
+    }
+}
+
+impl Error for UnsupportedType {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash_by_summing_hashes;
+    use ::arrow_array::{
+        BinaryArray, BooleanArray, Float64Array, Int64Array, LargeStringArray, ListArray,
+        StringArray, UInt8Array,
+    };
+
+    fn digest(array: &dyn Array) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        hash_arrow_array(array, &mut hasher).unwrap();
+        hasher.finish()
+    }
+
+    fn vec_digest<T: Hash>(values: Vec<T>) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        hash_by_summing_hashes::<Vec<T>, _>(&values, &mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn arrays_hash_like_vectors() {
+        assert_eq!(
+            vec_digest(vec![3_i64, -1, 3]),
+            digest(&Int64Array::from(vec![-1, 3, 3]))
+        );
+        assert_eq!(vec_digest(vec![7_u8]), digest(&UInt8Array::from(vec![7])));
+        assert_eq!(
+            vec_digest(vec!["b", "a"]),
+            digest(&StringArray::from(vec!["a", "b"]))
+        );
+        assert_eq!(
+            digest(&StringArray::from(vec!["a", "b"])),
+            digest(&LargeStringArray::from(vec!["b", "a"]))
+        );
+        let bytes: Vec<&[u8]> = vec![b"xy", b""];
+        assert_eq!(vec_digest(bytes.clone()), digest(&BinaryArray::from(bytes)));
+        assert_eq!(
+            vec_digest(vec![true, true]),
+            digest(&BooleanArray::from(vec![true, true]))
+        );
+        assert_eq!(
+            vec_digest(vec![1.5_f64.to_bits()]),
+            digest(&Float64Array::from(vec![1.5]))
+        );
+    }
+
+    #[test]
+    fn nulls_are_counted_as_sentinels() {
+        let mut sentinel = DefaultHasher::new();
+        sentinel.write(&[0xff; 3]);
+        let sentinel = sentinel.finish();
+        let values =
+            |values: Vec<i64>| crate::SumHashesAnyCollection::<_>::new(values).raw_unordered_hash();
+
+        let mut expected = DefaultHasher::new();
+        expected.write_u64(values(vec![1, 2]).wrapping_add(sentinel.wrapping_mul(2)));
+        let with_nulls = Int64Array::from(vec![None, Some(1), None, Some(2)]);
+        assert_eq!(expected.finish(), digest(&with_nulls));
+
+        let one_null = Int64Array::from(vec![Some(1), None, Some(2)]);
+        assert_ne!(digest(&with_nulls), digest(&one_null));
+        assert_ne!(digest(&one_null), digest(&Int64Array::from(vec![1, 2])));
+        let strings = StringArray::from(vec![None, Some("")]);
+        assert_ne!(
+            digest(&strings),
+            digest(&StringArray::from(vec![Some(""), Some("")]))
+        );
+    }
+
+    #[test]
+    fn sliced_arrays_hash_visible_values() {
+        let array = StringArray::from(vec![Some("skip"), Some("b"), None, Some("a"), Some("end")]);
+        let sliced = array.slice(1, 3);
+        assert_eq!(
+            digest(&StringArray::from(vec![Some("a"), None, Some("b")])),
+            digest(&sliced)
+        );
+
+        let ints = Int64Array::from(vec![10, 20, 30, 40]);
+        assert_eq!(vec_digest(vec![20_i64, 30]), digest(&ints.slice(1, 2)));
+    }
+
+    #[test]
+    fn unsupported_types() {
+        let list = ListArray::from_iter_primitive::<Int64Type, _, _>(vec![Some(vec![Some(1)])]);
+        let mut hasher = DefaultHasher::new();
+        let error = hash_arrow_array(&list, &mut hasher).unwrap_err();
+        assert!(matches!(error.data_type(), DataType::List(_)));
+        assert!(
+            error.to_string().starts_with("arrays of type List"),
+            "{error}"
+        );
+    }
+}
@@ -0,0 +1,75 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! The `TransparentWrapper` implementations are derived on the wrappers themselves. This
+//! module only holds tests.
+
+#[cfg(test)]
+mod tests {
+    use crate::{SumHashes, SumHashesAnyCollection, XorHashes, XorHashesAnyCollection};
+    use ::bytemuck::TransparentWrapper;
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::HashMap;
+    use std::hash::{Hash, Hasher};
+
+    fn hash<T: Hash>(value: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn wrap_slice_in_place() {
+        let maps: Vec<HashMap<u8, &str>> = vec![
+            [(1, "one"), (2, "two")].into_iter().collect(),
+            [(3, "three")].into_iter().collect(),
+            HashMap::new(),
+        ];
+
+        let wrapped = SumHashesAnyCollection::<HashMap<u8, &str>>::wrap_slice(&maps);
+        for (map, wrapped) in maps.iter().zip(wrapped) {
+            assert_eq!(
+                hash(&SumHashesAnyCollection::<_>::new(map.clone())),
+                hash(wrapped)
+            );
+        }
+
+        let wrapped = SumHashes::wrap_slice(&maps);
+        for (map, wrapped) in maps.iter().zip(wrapped) {
+            // Cloning the map keeps its RandomState, and thus its digest
+            assert_eq!(hash(&SumHashes::new(map.clone())), hash(wrapped));
+        }
+        assert_eq!(&maps[..], SumHashes::peel_slice(wrapped));
+    }
+
+    #[test]
+    fn wrap_mut_then_peel() {
+        let mut map: HashMap<u8, &str> = HashMap::new();
+        SumHashes::wrap_mut(&mut map).insert(4, "four");
+        let wrapped: &SumHashes<_> = SumHashes::wrap_ref(&map);
+        assert_eq!(Some(&"four"), wrapped.get(&4));
+        assert_eq!(&map, SumHashes::peel_ref(wrapped));
+
+        XorHashes::wrap_mut(&mut map).insert(5, "five");
+        assert_eq!(Some(&"five"), XorHashes::wrap_ref(&map).get(&5));
+        let maps = [map];
+        let wrapped = XorHashesAnyCollection::<HashMap<u8, &str>>::wrap_slice(&maps);
+        assert_eq!(
+            hash(&XorHashesAnyCollection::<_>::new(maps[0].clone())),
+            hash(&wrapped[0])
+        );
+    }
+}
@@ -0,0 +1,155 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Hashing of `DashMap`s like the equivalent `HashMap`s
+
+use crate::hash_of;
+use ::dashmap::DashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::num::Wrapping;
+
+///
+/// Hashes a concurrent map by summing the hashes of each entry, as a `(&K, &V)` tuple. A new
+/// [`DefaultHasher`] is created for each entry. The result is the same as hashing an
+/// equivalent `HashMap<K, V>` with [`crate::hash_by_summing_hashes`].
+///
+/// A `DashMap` has no global snapshot: each shard is read-locked while its entries are hashed,
+/// then released before the next shard is visited. If the map is mutated concurrently, the
+/// result is unspecified, but is still a digest of entries which were present during hashing,
+/// and hashing never fails. Without concurrent mutation, the result is exact. As with any
+/// iteration of a `DashMap`, holding a guard into the map on the same thread may deadlock.
+///
+pub fn hash_dashmap<K, V, S, H>(map: &DashMap<K, V, S>, state: &mut H)
+where
+    K: Eq + Hash,
+    V: Hash,
+    S: BuildHasher + Clone,
+    H: Hasher,
+{
+    let mut sum = Wrapping::default();
+    for entry in map.iter() {
+        sum += hash_of(&(entry.key(), entry.value()), DefaultHasher::new());
+    }
+    state.write_u64(sum.0);
+}
+
+///
+/// Hashes a concurrent map like [`hash_dashmap`], with each shard's entries hashed on the rayon
+/// thread pool. The partial sums are reduced by wrapping addition, so the result is identical
+/// to [`hash_dashmap`]'s.
+///
+/// The same caveats about concurrent mutation apply: each shard is read-locked while it is
+/// hashed, and the result is exact only without concurrent mutation. Holding a guard into the
+/// map on any thread of the pool may deadlock.
+///
+#[cfg(feature = "rayon")]
+pub fn par_hash_dashmap<K, V, S, H>(map: &DashMap<K, V, S>, state: &mut H)
+where
+    K: Eq + Hash + Send + Sync,
+    V: Hash + Send + Sync,
+    S: BuildHasher + Clone + Send + Sync,
+    H: Hasher,
+{
+    use crate::{trace::DigestSpan, UseDefaultHasher};
+    use ::rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+
+    let mut span = DigestSpan::enter::<UseDefaultHasher>("par_hash_dashmap", map.len());
+    span.add_elements(map.len());
+    let sum = map
+        .par_iter()
+        .map(|entry| Wrapping(hash_of(&(entry.key(), entry.value()), DefaultHasher::new())))
+        .reduce(Wrapping::default, |left, right| left + right);
+    state.write_u64(span.finish(sum.0));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash_by_summing_hashes;
+    use std::collections::HashMap;
+
+    #[test]
+    fn dashmap_hashes_like_hashmap() {
+        let entries = (0..500).map(|n| (n, n.to_string()));
+        let map: HashMap<i32, String> = entries.clone().collect();
+        let concurrent: DashMap<i32, String> = entries.rev().collect();
+
+        let mut from_map = DefaultHasher::new();
+        hash_by_summing_hashes::<HashMap<i32, String>, _>(&map, &mut from_map);
+        let mut from_concurrent = DefaultHasher::new();
+        hash_dashmap(&concurrent, &mut from_concurrent);
+        assert_eq!(from_map.finish(), from_concurrent.finish());
+
+        concurrent.insert(0, String::from("zero"));
+        let mut changed = DefaultHasher::new();
+        hash_dashmap(&concurrent, &mut changed);
+        assert_ne!(from_map.finish(), changed.finish());
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_matches_sequential() {
+        let map: DashMap<u32, String> = (0..20_000).map(|n| (n, n.to_string())).collect();
+        let mut sequential = DefaultHasher::new();
+        hash_dashmap(&map, &mut sequential);
+        let mut parallel = DefaultHasher::new();
+        par_hash_dashmap(&map, &mut parallel);
+        assert_eq!(sequential.finish(), parallel.finish());
+
+        let mut empty = DefaultHasher::new();
+        par_hash_dashmap(&DashMap::<u32, u32>::new(), &mut empty);
+        let mut expected = DefaultHasher::new();
+        expected.write_u64(0);
+        assert_eq!(expected.finish(), empty.finish());
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_hashing_with_active_writers() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+
+        let map: Arc<DashMap<u32, u32>> = Arc::new((0..10_000).map(|n| (n, n)).collect());
+        let stop = Arc::new(AtomicBool::new(false));
+        let writers: Vec<_> = (0..4)
+            .map(|writer| {
+                let map = Arc::clone(&map);
+                let stop = Arc::clone(&stop);
+                std::thread::spawn(move || {
+                    let mut n: u32 = writer;
+                    while !stop.load(Ordering::Relaxed) {
+                        let key = n.wrapping_mul(2_654_435_761) % 20_000;
+                        if n % 3 == 0 {
+                            map.remove(&key);
+                        } else {
+                            map.insert(key, n);
+                        }
+                        n = n.wrapping_add(4);
+                    }
+                })
+            })
+            .collect();
+        for _ in 0..50 {
+            let mut hasher = DefaultHasher::new();
+            par_hash_dashmap(&map, &mut hasher);
+        }
+        stop.store(true, Ordering::Relaxed);
+        for writer in writers {
+            writer.join().unwrap();
+        }
+    }
+}
@@ -0,0 +1,94 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! The wrappers format as the wrapped collection. For collections which do not implement
+//! `Format`, [`FormatDigest`] formats the digest instead.
+
+use crate::{
+    BuildHasherFromFriend, ProvidesHasher, SumHashes, SumHashesAnyCollection, XorHashes,
+    XorHashesAnyCollection,
+};
+use ::defmt::{Format, Formatter};
+use std::hash::Hash;
+
+///
+/// Formats a wrapper compactly as its digest, `{=u64}`. This is useful for collections which
+/// do not implement `Format` themselves, such as heapless' `IndexMap`, and for keeping logs
+/// small. Structs containing this type can derive `Format`.
+///
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct FormatDigest<W>(pub W);
+
+macro_rules! impl_defmt {
+    ($provided:ident, $any:ident) => {
+        impl<C: ProvidesHasher + Format> Format for $provided<C> {
+            fn format(&self, fmt: Formatter) {
+                self.0.format(fmt)
+            }
+        }
+
+        impl<C: Format, H> Format for $any<C, H> {
+            fn format(&self, fmt: Formatter) {
+                self.0.format(fmt)
+            }
+        }
+
+        impl<C, BH> Format for FormatDigest<$any<C, BH>>
+        where
+            for<'c> &'c C: IntoIterator,
+            for<'c> <&'c C as IntoIterator>::Item: Hash,
+            BH: BuildHasherFromFriend<C>,
+        {
+            fn format(&self, fmt: Formatter) {
+                ::defmt::write!(fmt, "{=u64}", self.0.raw_unordered_hash())
+            }
+        }
+
+        impl<C: ProvidesHasher> Format for FormatDigest<$provided<C>>
+        where
+            for<'c> &'c C: IntoIterator,
+            for<'c> <&'c C as IntoIterator>::Item: Hash,
+        {
+            fn format(&self, fmt: Formatter) {
+                ::defmt::write!(fmt, "{=u64}", self.0.raw_unordered_hash())
+            }
+        }
+    };
+}
+
+impl_defmt!(SumHashes, SumHashesAnyCollection);
+impl_defmt!(XorHashes, XorHashesAnyCollection);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use heapless::index_map::FnvIndexMap;
+
+    #[test]
+    fn embedded_collections_impl_format() {
+        static_assertions::assert_impl_all!(SumHashesAnyCollection<heapless::Vec<u8, 8>>: Format, Hash);
+        static_assertions::assert_impl_all!(FormatDigest<SumHashesAnyCollection<FnvIndexMap<u8, u32, 8>>>: Format, Hash);
+        static_assertions::assert_impl_all!(XorHashesAnyCollection<heapless::Vec<u8, 8>>: Format, Hash);
+        static_assertions::assert_impl_all!(FormatDigest<XorHashesAnyCollection<FnvIndexMap<u8, u32, 8>>>: Format, Hash);
+    }
+
+    #[allow(dead_code)]
+    #[derive(Format)]
+    struct State {
+        readings: FormatDigest<SumHashesAnyCollection<FnvIndexMap<u8, u32, 8>>>,
+        pending: SumHashesAnyCollection<heapless::Vec<u8, 8>>,
+    }
+}
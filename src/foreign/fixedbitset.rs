@@ -0,0 +1,62 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Hashing of `FixedBitSet`s like the equivalent sets of indices
+
+use ::fixedbitset::FixedBitSet;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hasher;
+
+///
+/// Hashes a bitset as the set of indices of its set bits, by summing the hashes of each index.
+/// The result is the same as hashing an equivalent `HashSet<usize>` with
+/// [`crate::hash_by_summing_hashes`], so a bitset and a set of indices are interchangeable.
+///
+pub fn hash_bitset<H: Hasher>(bits: &FixedBitSet, state: &mut H) {
+    state.write_u64(crate::sum_of_hashes(bits.ones(), DefaultHasher::new));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash_by_summing_hashes;
+    use std::collections::HashSet;
+
+    #[test]
+    fn bitset_hashes_like_set_of_indices() {
+        let indices: HashSet<usize> = [0, 3, 64, 65, 200].into_iter().collect();
+        let mut bits = FixedBitSet::with_capacity(256);
+        for index in &indices {
+            bits.insert(*index);
+        }
+
+        let mut from_set = DefaultHasher::new();
+        hash_by_summing_hashes::<HashSet<usize>, _>(&indices, &mut from_set);
+        let mut from_bits = DefaultHasher::new();
+        hash_bitset(&bits, &mut from_bits);
+        assert_eq!(from_set.finish(), from_bits.finish());
+
+        bits.grow(1024);
+        let mut grown = DefaultHasher::new();
+        hash_bitset(&bits, &mut grown);
+        assert_eq!(from_set.finish(), grown.finish());
+
+        bits.insert(1000);
+        let mut changed = DefaultHasher::new();
+        hash_bitset(&bits, &mut changed);
+        assert_ne!(from_set.finish(), changed.finish());
+    }
+}
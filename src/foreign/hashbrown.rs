@@ -0,0 +1,69 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Implementations for the `HashMap` and `HashSet` of hashbrown
+
+use crate::{NewCollection, ProvidesHasher};
+use ::hashbrown::{HashMap, HashSet};
+use std::hash::BuildHasher;
+
+impl<K, V, S> ProvidesHasher for HashMap<K, V, S>
+where
+    S: BuildHasher,
+{
+    type Hasher = S;
+
+    fn hasher(&self) -> &Self::Hasher {
+        HashMap::hasher(self)
+    }
+}
+
+impl<O, S> ProvidesHasher for HashSet<O, S>
+where
+    S: BuildHasher,
+{
+    type Hasher = S;
+
+    fn hasher(&self) -> &Self::Hasher {
+        HashSet::hasher(self)
+    }
+}
+
+impl<K, V, S> NewCollection for HashMap<K, V, S>
+where
+    S: BuildHasher,
+{
+    fn with_hasher(hasher: Self::Hasher) -> Self {
+        HashMap::with_hasher(hasher)
+    }
+
+    fn with_capacity_and_hasher(capacity: usize, hasher: Self::Hasher) -> Self {
+        HashMap::with_capacity_and_hasher(capacity, hasher)
+    }
+}
+
+impl<O, S> NewCollection for HashSet<O, S>
+where
+    S: BuildHasher,
+{
+    fn with_hasher(hasher: Self::Hasher) -> Self {
+        HashSet::with_hasher(hasher)
+    }
+
+    fn with_capacity_and_hasher(capacity: usize, hasher: Self::Hasher) -> Self {
+        HashSet::with_capacity_and_hasher(capacity, hasher)
+    }
+}
@@ -0,0 +1,122 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Implementations for the persistent `HashMap` and `HashSet` of im
+
+use crate::{NewCollection, ProvidesHasher};
+use ::im::{HashMap, HashSet};
+use std::hash::BuildHasher;
+
+impl<K, V, S> ProvidesHasher for HashMap<K, V, S>
+where
+    S: BuildHasher,
+{
+    type Hasher = S;
+
+    fn hasher(&self) -> &Self::Hasher {
+        HashMap::hasher(self)
+    }
+}
+
+impl<O, S> ProvidesHasher for HashSet<O, S>
+where
+    S: BuildHasher,
+{
+    type Hasher = S;
+
+    fn hasher(&self) -> &Self::Hasher {
+        HashSet::hasher(self)
+    }
+}
+
+// Persistent collections allocate per node, so there is no capacity to reserve
+
+impl<K, V, S> NewCollection for HashMap<K, V, S>
+where
+    S: BuildHasher,
+{
+    fn with_hasher(hasher: Self::Hasher) -> Self {
+        HashMap::with_hasher(hasher)
+    }
+
+    fn with_capacity_and_hasher(_: usize, hasher: Self::Hasher) -> Self {
+        HashMap::with_hasher(hasher)
+    }
+}
+
+impl<O, S> NewCollection for HashSet<O, S>
+where
+    S: BuildHasher,
+{
+    fn with_hasher(hasher: Self::Hasher) -> Self {
+        HashSet::with_hasher(hasher)
+    }
+
+    fn with_capacity_and_hasher(_: usize, hasher: Self::Hasher) -> Self {
+        HashSet::with_hasher(hasher)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hash_by_summing_hashes, SumHashes};
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{BuildHasherDefault, Hash, Hasher};
+
+    type Fixed = BuildHasherDefault<DefaultHasher>;
+
+    fn hash_one<T: Hash>(value: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn persistent_set_hashes_like_std() {
+        let std_set: std::collections::HashSet<u32, Fixed> = (0..300).collect();
+        let persistent: HashSet<u32, Fixed> = (0..300_u32).rev().collect();
+        assert_eq!(
+            hash_one(&SumHashes::new(std_set.clone())),
+            hash_one(&SumHashes::new(persistent.clone()))
+        );
+
+        let mut from_std = DefaultHasher::new();
+        hash_by_summing_hashes::<std::collections::HashSet<u32, Fixed>, _>(&std_set, &mut from_std);
+        let mut from_persistent = DefaultHasher::new();
+        hash_by_summing_hashes::<HashSet<u32, Fixed>, _>(&persistent, &mut from_persistent);
+        assert_eq!(from_std.finish(), from_persistent.finish());
+
+        // Structural sharing does not affect the digest of either version
+        let updated = persistent.update(300);
+        assert_ne!(
+            hash_one(&SumHashes::new(persistent)),
+            hash_one(&SumHashes::new(updated))
+        );
+    }
+
+    #[test]
+    fn persistent_map_hashes_like_std() {
+        let entries = (0..100).map(|n| (n, n.to_string()));
+        let std_map: std::collections::HashMap<i32, String, Fixed> = entries.clone().collect();
+        let mut wrapped =
+            SumHashes::new(HashMap::<i32, String, Fixed>::with_hasher(Fixed::default()));
+        for (key, value) in entries {
+            wrapped.insert(key, value);
+        }
+        assert_eq!(hash_one(&SumHashes::new(std_map)), hash_one(&wrapped));
+    }
+}
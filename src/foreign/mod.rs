@@ -0,0 +1,63 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Implementations for collections from other crates, enabled by feature flags
+
+#[cfg(feature = "arrow")]
+mod arrow;
+#[cfg(feature = "bytemuck")]
+mod bytemuck;
+#[cfg(feature = "dashmap")]
+mod dashmap;
+#[cfg(feature = "defmt")]
+mod defmt;
+#[cfg(feature = "fixedbitset")]
+mod fixedbitset;
+#[cfg(feature = "hashbrown")]
+mod hashbrown;
+#[cfg(feature = "im")]
+mod im;
+#[cfg(feature = "rayon")]
+mod rayon;
+#[cfg(feature = "serde")]
+mod serde;
+#[cfg(feature = "serde_with")]
+mod serde_with;
+#[cfg(feature = "subtle")]
+mod subtle;
+#[cfg(feature = "unicode")]
+mod unicode;
+#[cfg(feature = "xxh3")]
+mod xxh3;
+
+#[cfg(feature = "arrow")]
+pub use self::arrow::{hash_arrow_array, UnsupportedType};
+#[cfg(feature = "dashmap")]
+pub use self::dashmap::hash_dashmap;
+#[cfg(all(feature = "dashmap", feature = "rayon"))]
+pub use self::dashmap::par_hash_dashmap;
+#[cfg(feature = "defmt")]
+pub use self::defmt::FormatDigest;
+#[cfg(feature = "fixedbitset")]
+pub use self::fixedbitset::hash_bitset;
+#[cfg(feature = "serde_with")]
+pub use self::serde_with::{UnorderedCanonical, UnorderedTransparent};
+#[cfg(feature = "subtle")]
+pub use self::subtle::verify_digest_ct;
+#[cfg(feature = "unicode")]
+pub use self::unicode::{hash_nfkc_strings, hash_normalized_strings};
+#[cfg(feature = "xxh3")]
+pub use self::xxh3::UseXxh3;
@@ -0,0 +1,211 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Parallel iteration over, collection into and extension of the wrappers with rayon
+
+use crate::{ProvidesHasher, SumHashes, SumHashesAnyCollection, XorHashes, XorHashesAnyCollection};
+use ::rayon::iter::{FromParallelIterator, IntoParallelIterator, ParallelExtend};
+
+macro_rules! impl_rayon {
+    ($provided:ident, $any:ident) => {
+        impl<C, A> FromParallelIterator<A> for $provided<C>
+        where
+            C: ProvidesHasher + FromParallelIterator<A>,
+            A: Send,
+        {
+            fn from_par_iter<I>(par_iter: I) -> Self
+            where
+                I: IntoParallelIterator<Item = A>,
+            {
+                Self::new(C::from_par_iter(par_iter))
+            }
+        }
+
+        impl<C, H, A> FromParallelIterator<A> for $any<C, H>
+        where
+            C: FromParallelIterator<A>,
+            A: Send,
+        {
+            fn from_par_iter<I>(par_iter: I) -> Self
+            where
+                I: IntoParallelIterator<Item = A>,
+            {
+                Self::new(C::from_par_iter(par_iter))
+            }
+        }
+
+        impl<C, A> ParallelExtend<A> for $provided<C>
+        where
+            C: ProvidesHasher + ParallelExtend<A>,
+            A: Send,
+        {
+            fn par_extend<I>(&mut self, par_iter: I)
+            where
+                I: IntoParallelIterator<Item = A>,
+            {
+                self.0.par_extend(par_iter)
+            }
+        }
+
+        impl<C, H, A> ParallelExtend<A> for $any<C, H>
+        where
+            C: ParallelExtend<A>,
+            A: Send,
+        {
+            fn par_extend<I>(&mut self, par_iter: I)
+            where
+                I: IntoParallelIterator<Item = A>,
+            {
+                self.0.par_extend(par_iter)
+            }
+        }
+
+        impl<C: ProvidesHasher + IntoParallelIterator> IntoParallelIterator for $provided<C> {
+            type Iter = C::Iter;
+            type Item = C::Item;
+
+            fn into_par_iter(self) -> Self::Iter {
+                self.0.into_par_iter()
+            }
+        }
+
+        impl<'c, C: ProvidesHasher> IntoParallelIterator for &'c $provided<C>
+        where
+            &'c C: IntoParallelIterator,
+        {
+            type Iter = <&'c C as IntoParallelIterator>::Iter;
+            type Item = <&'c C as IntoParallelIterator>::Item;
+
+            fn into_par_iter(self) -> Self::Iter {
+                self.0.into_par_iter()
+            }
+        }
+
+        impl<'c, C: ProvidesHasher> IntoParallelIterator for &'c mut $provided<C>
+        where
+            &'c mut C: IntoParallelIterator,
+        {
+            type Iter = <&'c mut C as IntoParallelIterator>::Iter;
+            type Item = <&'c mut C as IntoParallelIterator>::Item;
+
+            fn into_par_iter(self) -> Self::Iter {
+                (&mut self.0).into_par_iter()
+            }
+        }
+
+        impl<C: IntoParallelIterator, H> IntoParallelIterator for $any<C, H> {
+            type Iter = C::Iter;
+            type Item = C::Item;
+
+            fn into_par_iter(self) -> Self::Iter {
+                self.0.into_par_iter()
+            }
+        }
+
+        impl<'c, C, H> IntoParallelIterator for &'c $any<C, H>
+        where
+            &'c C: IntoParallelIterator,
+        {
+            type Iter = <&'c C as IntoParallelIterator>::Iter;
+            type Item = <&'c C as IntoParallelIterator>::Item;
+
+            fn into_par_iter(self) -> Self::Iter {
+                self.0.into_par_iter()
+            }
+        }
+
+        impl<'c, C, H> IntoParallelIterator for &'c mut $any<C, H>
+        where
+            &'c mut C: IntoParallelIterator,
+        {
+            type Iter = <&'c mut C as IntoParallelIterator>::Iter;
+            type Item = <&'c mut C as IntoParallelIterator>::Item;
+
+            fn into_par_iter(self) -> Self::Iter {
+                (&mut self.0).into_par_iter()
+            }
+        }
+    };
+}
+
+impl_rayon!(SumHashes, SumHashesAnyCollection);
+impl_rayon!(XorHashes, XorHashesAnyCollection);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::{HashMap, HashSet};
+    use std::hash::{BuildHasherDefault, Hash, Hasher};
+
+    type Map = HashMap<u32, String, BuildHasherDefault<DefaultHasher>>;
+
+    fn hash_one<T: Hash>(value: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn collect_matches_sequential() {
+        let keys: Vec<u32> = (0..1000).collect();
+        let parallel = keys
+            .par_iter()
+            .map(|key| (*key, key.to_string()))
+            .collect::<SumHashes<Map>>();
+        let sequential = SumHashes::new(
+            keys.iter()
+                .map(|key| (*key, key.to_string()))
+                .collect::<Map>(),
+        );
+        assert_eq!(sequential, parallel);
+        assert_eq!(hash_one(&sequential), hash_one(&parallel));
+
+        let any: SumHashesAnyCollection<HashSet<u32>> = keys.par_iter().copied().collect();
+        let sequential_any =
+            SumHashesAnyCollection::<HashSet<u32>>::new(keys.iter().copied().collect());
+        assert_eq!(hash_one(&sequential_any), hash_one(&any));
+
+        let xor: XorHashesAnyCollection<HashSet<u32>> = keys.par_iter().copied().collect();
+        let sequential_xor =
+            XorHashesAnyCollection::<HashSet<u32>>::new(keys.iter().copied().collect());
+        assert_eq!(hash_one(&sequential_xor), hash_one(&xor));
+        let mut xor = XorHashes::new(Map::default());
+        xor.par_extend((0..10_u32).into_par_iter().map(|key| (key, String::new())));
+        assert_eq!(45, (&xor).into_par_iter().map(|(key, _)| *key).sum::<u32>());
+    }
+
+    #[test]
+    fn extend_and_iterate() {
+        let mut wrapped = SumHashes::new(Map::default());
+        wrapped.par_extend((0..100_u32).into_par_iter().map(|key| (key, String::new())));
+        assert_eq!(100, wrapped.len());
+        (&mut wrapped)
+            .into_par_iter()
+            .for_each(|(key, value)| *value = key.to_string());
+        assert_eq!(
+            (0..100_u32).sum::<u32>(),
+            (&wrapped).into_par_iter().map(|(key, _)| *key).sum::<u32>()
+        );
+        let sequential: Map = (0..100).map(|key: u32| (key, key.to_string())).collect();
+        assert_eq!(hash_one(&SumHashes::new(sequential)), hash_one(&wrapped));
+
+        let mut any = SumHashesAnyCollection::<Vec<u32>>::default();
+        any.par_extend((0..10_u32).into_par_iter());
+        assert_eq!(45, any.into_par_iter().sum::<u32>());
+    }
+}
@@ -0,0 +1,100 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! The wrappers serialize and deserialize transparently, i.e. as the wrapped collection
+
+use crate::{ProvidesHasher, SumHashes, SumHashesAnyCollection, XorHashes, XorHashesAnyCollection};
+use ::serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+macro_rules! impl_serde {
+    ($provided:ident, $any:ident) => {
+        impl<C: ProvidesHasher + Serialize> Serialize for $provided<C> {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                self.0.serialize(serializer)
+            }
+        }
+
+        impl<'de, C: ProvidesHasher + Deserialize<'de>> Deserialize<'de> for $provided<C> {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                C::deserialize(deserializer).map(Self)
+            }
+        }
+
+        impl<C: Serialize, H> Serialize for $any<C, H> {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                self.0.serialize(serializer)
+            }
+        }
+
+        impl<'de, C: Deserialize<'de>, H> Deserialize<'de> for $any<C, H> {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                C::deserialize(deserializer).map(Self::from)
+            }
+        }
+    };
+}
+
+impl_serde!(SumHashes, SumHashesAnyCollection);
+impl_serde!(XorHashes, XorHashesAnyCollection);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{HashMap, HashSet};
+
+    #[test]
+    fn round_trip() {
+        let map: HashMap<String, i32> = [("one", 1), ("two", 2)]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect();
+        let map = SumHashes::new(map);
+        let json = serde_json::to_string(&map).unwrap();
+        assert_eq!(json, serde_json::to_string(&*map).unwrap());
+
+        let deserialized: SumHashes<HashMap<String, i32>> = serde_json::from_str(&json).unwrap();
+        // The deserialized map has its own RandomState, so compare the contents: the wrappers
+        // would compare equal while hashing differently
+        assert_eq!(*map, *deserialized);
+
+        let set = SumHashesAnyCollection::<HashSet<i32>>::new([1, 2, 3].into_iter().collect());
+        let json = serde_json::to_string(&set).unwrap();
+        let deserialized: SumHashesAnyCollection<HashSet<i32>> =
+            serde_json::from_str(&json).unwrap();
+        assert_eq!(set.raw_unordered_hash(), deserialized.raw_unordered_hash());
+        assert_eq!(set, deserialized);
+    }
+
+    #[test]
+    fn xor_wrappers_round_trip() {
+        let set = XorHashesAnyCollection::<HashSet<i32>>::new([1, 2, 3].into_iter().collect());
+        let json = serde_json::to_string(&set).unwrap();
+        let deserialized: XorHashesAnyCollection<HashSet<i32>> =
+            serde_json::from_str(&json).unwrap();
+        assert_eq!(set.raw_unordered_hash(), deserialized.raw_unordered_hash());
+
+        let map: XorHashes<HashMap<String, u32>> = serde_json::from_str(r#"{"a": 1}"#).unwrap();
+        assert_eq!(Some(&1), map.get("a"));
+        assert_eq!(r#"{"a":1}"#, serde_json::to_string(&map).unwrap());
+    }
+
+    #[test]
+    fn malformed_input() {
+        assert!(serde_json::from_str::<SumHashes<HashMap<String, u32>>>(r#"{"a": -1}"#).is_err());
+        assert!(serde_json::from_str::<SumHashes<HashSet<u32>>>(r#"{"a": 1}"#).is_err());
+        assert!(serde_json::from_str::<SumHashesAnyCollection<Vec<u32>>>("[1, 2").is_err());
+    }
+}
@@ -0,0 +1,196 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Adapters for `#[serde_as]`, applicable to plain `HashMap` and `HashSet` fields
+
+use ::serde::{Deserialize, Deserializer, Serialize, Serializer};
+use ::serde_with::{DeserializeAs, SerializeAs};
+use std::collections::{HashMap, HashSet};
+use std::hash::{BuildHasher, Hash};
+
+///
+/// A `serde_with` adapter which serializes a `HashMap` or `HashSet` canonically, in sorted
+/// order. The serialized form is then independent of iteration order, so that equal
+/// collections produce identical output. Deserialization is unchanged.
+///
+/// ```rust
+/// # use std::collections::HashMap;
+/// use hash_that_set::UnorderedCanonical;
+/// use serde::Serialize;
+/// use serde_with::serde_as;
+///
+/// #[serde_as]
+/// #[derive(Serialize)]
+/// struct Config {
+///     #[serde_as(as = "UnorderedCanonical")]
+///     limits: HashMap<String, u32>,
+/// }
+/// ```
+///
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct UnorderedCanonical(());
+
+///
+/// A `serde_with` adapter which serializes a `HashMap` or `HashSet` as-is, in iteration order.
+/// This is the same as not using an adapter, and is provided for symmetry with
+/// [`UnorderedCanonical`].
+///
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct UnorderedTransparent(());
+
+impl<K, V, S> SerializeAs<HashMap<K, V, S>> for UnorderedCanonical
+where
+    K: Serialize + Ord,
+    V: Serialize,
+{
+    fn serialize_as<Se: Serializer>(
+        source: &HashMap<K, V, S>,
+        serializer: Se,
+    ) -> Result<Se::Ok, Se::Error> {
+        let mut entries: Vec<_> = source.iter().collect();
+        entries.sort_unstable_by_key(|(key, _)| *key);
+        serializer.collect_map(entries)
+    }
+}
+
+impl<T, S> SerializeAs<HashSet<T, S>> for UnorderedCanonical
+where
+    T: Serialize + Ord,
+{
+    fn serialize_as<Se: Serializer>(
+        source: &HashSet<T, S>,
+        serializer: Se,
+    ) -> Result<Se::Ok, Se::Error> {
+        let mut elements: Vec<_> = source.iter().collect();
+        elements.sort_unstable();
+        serializer.collect_seq(elements)
+    }
+}
+
+impl<K, V, S> SerializeAs<HashMap<K, V, S>> for UnorderedTransparent
+where
+    K: Serialize,
+    V: Serialize,
+{
+    fn serialize_as<Se: Serializer>(
+        source: &HashMap<K, V, S>,
+        serializer: Se,
+    ) -> Result<Se::Ok, Se::Error> {
+        serializer.collect_map(source)
+    }
+}
+
+impl<T, S> SerializeAs<HashSet<T, S>> for UnorderedTransparent
+where
+    T: Serialize,
+{
+    fn serialize_as<Se: Serializer>(
+        source: &HashSet<T, S>,
+        serializer: Se,
+    ) -> Result<Se::Ok, Se::Error> {
+        serializer.collect_seq(source)
+    }
+}
+
+macro_rules! deserialize_as_is {
+    ($adapter:ty) => {
+        impl<'de, K, V, S> DeserializeAs<'de, HashMap<K, V, S>> for $adapter
+        where
+            K: Deserialize<'de> + Eq + Hash,
+            V: Deserialize<'de>,
+            S: BuildHasher + Default,
+        {
+            fn deserialize_as<D: Deserializer<'de>>(
+                deserializer: D,
+            ) -> Result<HashMap<K, V, S>, D::Error> {
+                HashMap::deserialize(deserializer)
+            }
+        }
+
+        impl<'de, T, S> DeserializeAs<'de, HashSet<T, S>> for $adapter
+        where
+            T: Deserialize<'de> + Eq + Hash,
+            S: BuildHasher + Default,
+        {
+            fn deserialize_as<D: Deserializer<'de>>(
+                deserializer: D,
+            ) -> Result<HashSet<T, S>, D::Error> {
+                HashSet::deserialize(deserializer)
+            }
+        }
+    };
+}
+
+deserialize_as_is!(UnorderedCanonical);
+deserialize_as_is!(UnorderedTransparent);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::serde_with::serde_as;
+
+    #[serde_as]
+    #[derive(Debug, Deserialize, PartialEq, Serialize)]
+    struct Config {
+        #[serde_as(as = "UnorderedCanonical")]
+        limits: HashMap<String, usize>,
+        #[serde_as(as = "UnorderedCanonical")]
+        tags: HashSet<String>,
+    }
+
+    #[serde_as]
+    #[derive(Debug, Deserialize, PartialEq, Serialize)]
+    struct Aliases {
+        #[serde_as(as = "UnorderedTransparent")]
+        names: HashMap<String, String>,
+        #[serde_as(as = "UnorderedTransparent")]
+        unused: HashSet<String>,
+    }
+
+    fn config<'w>(words: impl Iterator<Item = &'w str> + Clone) -> Config {
+        Config {
+            limits: words.clone().map(|w| (w.to_string(), w.len())).collect(),
+            tags: words.map(String::from).collect(),
+        }
+    }
+
+    #[test]
+    fn canonical_output_is_deterministic() {
+        let words = ["delta", "alpha", "charlie", "bravo", "echo", "foxtrot"];
+        let expected = serde_json::to_string(&config(words.iter().copied())).unwrap();
+        assert!(expected.starts_with(r#"{"limits":{"alpha":5,"bravo":5,"charlie":7"#));
+        for _ in 0..8 {
+            // Fresh RandomStates and reversed insertion both change iteration order
+            let permuted = config(words.iter().rev().copied());
+            assert_eq!(expected, serde_json::to_string(&permuted).unwrap());
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let config = config(["one", "two", "three"].iter().copied());
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(config, serde_json::from_str(&json).unwrap());
+
+        let aliases = Aliases {
+            names: [("a".to_string(), "b".to_string())].into_iter().collect(),
+            unused: ["c".to_string()].into_iter().collect(),
+        };
+        let json = serde_json::to_string(&aliases).unwrap();
+        assert_eq!(r#"{"names":{"a":"b"},"unused":["c"]}"#, json);
+        assert_eq!(aliases, serde_json::from_str(&json).unwrap());
+    }
+}
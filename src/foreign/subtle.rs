@@ -0,0 +1,57 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Constant-time comparison of digests with subtle
+
+use ::subtle::{Choice, ConstantTimeEq};
+
+///
+/// Compares two digests in constant time, for when the digests may be attacker-influenced and
+/// comparing with `==` would leak timing information. This accepts the `u64` digests produced
+/// by this crate, such as from [`crate::SumHashes::raw_unordered_hash`], as well as byte
+/// slices.
+///
+/// Convert the result to a `bool` only at the point of decision, e.g. via `bool::from`.
+///
+#[inline]
+pub fn verify_digest_ct<D: ConstantTimeEq + ?Sized>(expected: &D, actual: &D) -> Choice {
+    expected.ct_eq(actual)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SumHashesAnyCollection;
+
+    #[test]
+    fn agrees_with_eq() {
+        let digest = |elements: &[u32]| {
+            SumHashesAnyCollection::<_>::new(elements.to_vec()).raw_unordered_hash()
+        };
+        let expected = digest(&[1, 2, 3]);
+        for (actual, equal) in [(digest(&[3, 2, 1]), true), (digest(&[1, 2]), false)] {
+            assert_eq!(equal, expected == actual);
+            assert_eq!(equal, bool::from(verify_digest_ct(&expected, &actual)));
+        }
+
+        let bytes = [7u8; 32];
+        let mut other = bytes;
+        assert!(bool::from(verify_digest_ct(&bytes[..], &other[..])));
+        other[31] = 0;
+        assert!(!bool::from(verify_digest_ct(&bytes[..], &other[..])));
+        assert!(!bool::from(verify_digest_ct(&bytes[..], &other[..16])));
+    }
+}
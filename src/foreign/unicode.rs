@@ -0,0 +1,125 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Hashing of strings after Unicode normalization
+
+use crate::hash_of;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hasher;
+use std::num::Wrapping;
+use unicode_normalization::UnicodeNormalization;
+
+///
+/// Implements hashing of a collection of strings by summing the hashes of each string after
+/// normalizing it to Unicode Normalization Form C (NFC). Strings which are canonically
+/// equivalent, such as a precomposed `"é"` and `"e"` followed by a combining acute accent,
+/// therefore hash equally. The result is the same as hashing the NFC forms of the strings with
+/// [`crate::hash_by_summing_hashes`].
+///
+/// Every element is normalized into a string buffer, which is allocated once and reused. A new
+/// [`DefaultHasher`] is created for each element.
+///
+pub fn hash_normalized_strings<C, H>(collection: &C, state: &mut H)
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: AsRef<str>,
+    H: Hasher,
+{
+    hash_with_normalization(collection, state, |value, buffer| {
+        buffer.extend(value.nfc())
+    })
+}
+
+///
+/// Implements hashing like [`hash_normalized_strings`], but normalizes to Normalization Form KC
+/// (NFKC). Strings which are compatibility equivalent, such as the ligature `"ﬁ"` and `"fi"`,
+/// also hash equally.
+///
+pub fn hash_nfkc_strings<C, H>(collection: &C, state: &mut H)
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: AsRef<str>,
+    H: Hasher,
+{
+    hash_with_normalization(collection, state, |value, buffer| {
+        buffer.extend(value.nfkc())
+    })
+}
+
+fn hash_with_normalization<C, H, N>(collection: &C, state: &mut H, normalize: N)
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: AsRef<str>,
+    H: Hasher,
+    N: Fn(&str, &mut String),
+{
+    let mut buffer = String::new();
+    let mut sum = Wrapping::default();
+    for value in collection {
+        buffer.clear();
+        normalize(value.as_ref(), &mut buffer);
+        sum += hash_of(buffer.as_str(), DefaultHasher::new());
+    }
+    state.write_u64(sum.0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash_by_summing_hashes;
+    use std::collections::HashSet;
+
+    fn normalized(strings: &HashSet<&str>) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        hash_normalized_strings::<HashSet<&str>, _>(strings, &mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn composed_and_decomposed_hash_equally() {
+        let composed: HashSet<&str> = ["caf\u{e9}", "na\u{ef}ve", "plain"].into_iter().collect();
+        let decomposed: HashSet<&str> = ["cafe\u{301}", "nai\u{308}ve", "plain"]
+            .into_iter()
+            .collect();
+        assert_ne!(composed, decomposed);
+        assert_eq!(normalized(&composed), normalized(&decomposed));
+
+        // The same as hashing the composed forms directly
+        let mut hasher = DefaultHasher::new();
+        hash_by_summing_hashes::<HashSet<&str>, _>(&composed, &mut hasher);
+        assert_eq!(hasher.finish(), normalized(&composed));
+
+        let different: HashSet<&str> = ["cafe", "naive", "plain"].into_iter().collect();
+        assert_ne!(normalized(&composed), normalized(&different));
+    }
+
+    #[test]
+    fn compatibility_forms() {
+        let hash_nfkc = |strings: &Vec<String>| {
+            let mut hasher = DefaultHasher::new();
+            hash_nfkc_strings::<Vec<String>, _>(strings, &mut hasher);
+            hasher.finish()
+        };
+        let ligature = vec!["\u{fb01}le".to_string()];
+        let letters = vec!["file".to_string()];
+        assert_eq!(hash_nfkc(&ligature), hash_nfkc(&letters));
+
+        // Canonical normalization keeps the ligature distinct
+        let nfc: HashSet<&str> = ["\u{fb01}le"].into_iter().collect();
+        let plain: HashSet<&str> = ["file"].into_iter().collect();
+        assert_ne!(normalized(&nfc), normalized(&plain));
+    }
+}
@@ -0,0 +1,82 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! A strategy hashing elements with XXH3
+
+use crate::{BuildHasherFromFriend, OneShotStrategy};
+use ::xxhash_rust::xxh3::{xxh3_64, Xxh3};
+
+///
+/// Implementation of [`BuildHasherFromFriend`] which uses XXH3 with its default secret, and
+/// seed zero. XXH3 is not keyed by a random seed, so it offers no resistance against chosen
+/// inputs.
+///
+/// Through [`OneShotStrategy`], collections of strings and byte slices can be hashed with the
+/// one-shot XXH3 function in [`crate::hash_bytes_by_summing`], which is faster on short inputs.
+///
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct UseXxh3(());
+
+impl<F> BuildHasherFromFriend<F> for UseXxh3 {
+    type Hasher = Xxh3;
+
+    fn build_hasher_from<'f>(_: &'f F) -> Self::Hasher
+    where
+        Self::Hasher: 'f,
+    {
+        Xxh3::new()
+    }
+}
+
+impl OneShotStrategy for UseXxh3 {
+    fn hash_bytes(bytes: &[u8]) -> u64 {
+        xxh3_64(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hash_bytes_by_summing, SumHashesAnyCollection};
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::Hasher;
+
+    #[test]
+    fn one_shot_matches_streaming_write() {
+        for length in [0, 1, 3, 8, 16, 17, 128, 129, 240, 241, 1024] {
+            let bytes: Vec<u8> = (0..length).map(|byte| byte as u8).collect();
+            let mut streaming = <UseXxh3 as BuildHasherFromFriend<()>>::build_hasher_from(&());
+            streaming.write(&bytes);
+            assert_eq!(streaming.finish(), UseXxh3::hash_bytes(&bytes));
+        }
+    }
+
+    #[test]
+    fn one_shot_digest_is_order_independent() {
+        let digest = |strings: &[&str]| {
+            let mut hasher = DefaultHasher::new();
+            hash_bytes_by_summing::<[&str], UseXxh3, _>(strings, &mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(digest(&["a", "bc", "def"]), digest(&["def", "a", "bc"]));
+        assert_ne!(digest(&["a", "bc", "def"]), digest(&["ab", "c", "def"]));
+
+        // The streaming strategy is usable with the wrappers too
+        let wrapped = SumHashesAnyCollection::<_, UseXxh3>::new(vec!["a", "bc", "def"]);
+        let reordered = SumHashesAnyCollection::<_, UseXxh3>::new(vec!["bc", "def", "a"]);
+        assert_eq!(wrapped.raw_unordered_hash(), reordered.raw_unordered_hash());
+    }
+}
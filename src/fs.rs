@@ -0,0 +1,317 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Digests of the files in a directory tree, independent of the order of iteration
+
+use crate::{PortablePath, StableHasher};
+use std::fs::{self, File};
+use std::hash::{Hash, Hasher};
+use std::io::{self, Read};
+use std::num::Wrapping;
+use std::path::{Path, PathBuf};
+
+///
+/// Options for [`hash_dir_contents`]. By default, symbolic links are not followed, and empty
+/// directories are not included.
+///
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct DirOptions {
+    follow_symlinks: bool,
+    include_empty_dirs: bool,
+}
+
+impl DirOptions {
+    /// Creates the default options
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// Sets whether symbolic links are followed. If they are, a link is hashed as the file or
+    /// directory it points to, and a link to one of its own ancestors is an error. Otherwise, a
+    /// link is hashed as the portable form of its target path, and is never followed.
+    ///
+    pub fn follow_symlinks(mut self, follow: bool) -> Self {
+        self.follow_symlinks = follow;
+        self
+    }
+
+    ///
+    /// Sets whether directories without entries are included. Otherwise, directories
+    /// contribute only through their files, so an empty directory does not change the digest.
+    ///
+    pub fn include_empty_dirs(mut self, include: bool) -> Self {
+        self.include_empty_dirs = include;
+        self
+    }
+}
+
+/// Distinguishes the kinds of entries in the hash of each
+#[derive(Clone, Copy)]
+enum EntryKind {
+    File = 0,
+    EmptyDir = 1,
+    Symlink = 2,
+}
+
+///
+/// Computes a digest of the files in a directory tree, which is the same for the same files
+/// with the same contents, regardless of the order in which the directory is iterated. This
+/// is suitable for build caching.
+///
+/// Each file is an element, hashed from its path relative to `path`, in the form given by
+/// [`PortablePath`], and from the hash of its contents. Contents are read in chunks, so files
+/// are never loaded whole. The hashes of the elements are then summed.
+///
+/// All hashing uses [`StableHasher`], so the digest is the same on every platform and in every
+/// release, given the same relative paths and contents. File metadata, such as permissions and
+/// modification times, is not hashed.
+///
+/// Only regular files are read. Other entries which are neither directories nor symbolic
+/// links, such as FIFOs, sockets and device files, are skipped, since reading them may block or
+/// never end. They do not change the digest, nor make their directory count as non-empty.
+///
+/// Errors from reading the tree are returned as they occur, as is an error of kind
+/// [`io::ErrorKind::Other`] for a cycle of followed symbolic links.
+///
+pub fn hash_dir_contents<P: AsRef<Path>>(path: P, options: DirOptions) -> io::Result<u64> {
+    let root = path.as_ref();
+    let mut walk = Walk {
+        options,
+        sum: Wrapping(0),
+        ancestors: Vec::new(),
+    };
+    walk.visit_dir(root, &mut PathBuf::new())?;
+    Ok(walk.sum.0)
+}
+
+struct Walk {
+    options: DirOptions,
+    sum: Wrapping<u64>,
+    /// The canonical paths of the directories being visited, to detect cycles
+    ancestors: Vec<PathBuf>,
+}
+
+impl Walk {
+    fn visit_dir(&mut self, dir: &Path, relative: &mut PathBuf) -> io::Result<()> {
+        if self.options.follow_symlinks {
+            let canonical = fs::canonicalize(dir)?;
+            if self.ancestors.contains(&canonical) {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("symbolic link cycle at {}", dir.display()),
+                ));
+            }
+            self.ancestors.push(canonical);
+        }
+        let mut empty = true;
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            relative.push(entry.file_name());
+            let path = entry.path();
+            let mut file_type = entry.file_type()?;
+            if file_type.is_symlink() {
+                if self.options.follow_symlinks {
+                    file_type = fs::metadata(&path)?.file_type();
+                } else {
+                    let target = PortablePath(fs::read_link(&path)?).normalized();
+                    self.add(relative, EntryKind::Symlink, hash_of(target.as_str()));
+                    relative.pop();
+                    empty = false;
+                    continue;
+                }
+            }
+            if file_type.is_dir() {
+                self.visit_dir(&path, relative)?;
+                empty = false;
+            } else if file_type.is_file() {
+                let contents = hash_contents(File::open(&path)?)?;
+                self.add(relative, EntryKind::File, contents);
+                empty = false;
+            }
+            relative.pop();
+        }
+        if empty && self.options.include_empty_dirs && !relative.as_os_str().is_empty() {
+            self.add(relative, EntryKind::EmptyDir, 0);
+        }
+        if self.options.follow_symlinks {
+            self.ancestors.pop();
+        }
+        Ok(())
+    }
+
+    fn add(&mut self, relative: &Path, kind: EntryKind, contents: u64) {
+        let mut hasher = StableHasher::new();
+        PortablePath(relative).hash(&mut hasher);
+        hasher.write_u8(kind as u8);
+        hasher.write_u64(contents);
+        self.sum += hasher.finish();
+    }
+}
+
+fn hash_of<T: Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = StableHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn hash_contents(mut file: File) -> io::Result<u64> {
+    let mut hasher = StableHasher::new();
+    let mut buffer = [0; 8192];
+    loop {
+        match file.read(&mut buffer) {
+            Ok(0) => return Ok(hasher.finish()),
+            Ok(read) => hasher.write(&buffer[..read]),
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn tree(files: &[(&str, &str)]) -> TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for (path, contents) in files {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        dir
+    }
+
+    fn digest(dir: &TempDir) -> u64 {
+        hash_dir_contents(dir.path(), DirOptions::new()).unwrap()
+    }
+
+    const FILES: [(&str, &str); 4] = [
+        ("Cargo.toml", "[package]"),
+        ("src/lib.rs", "pub fn answer() -> u32 { 42 }"),
+        ("src/nested/mod.rs", ""),
+        ("README.md", "# Readme"),
+    ];
+
+    #[test]
+    fn creation_order_is_ignored() {
+        let forward = tree(&FILES);
+        let mut reversed = FILES;
+        reversed.reverse();
+        let reversed = tree(&reversed);
+        assert_eq!(digest(&forward), digest(&reversed));
+        assert_ne!(0, digest(&forward));
+    }
+
+    #[test]
+    fn contents_and_paths_contribute() {
+        let original = digest(&tree(&FILES));
+
+        let modified = tree(&FILES);
+        fs::write(
+            modified.path().join("src/lib.rs"),
+            "pub fn answer() -> u32 { 43 }",
+        )
+        .unwrap();
+        assert_ne!(original, digest(&modified));
+
+        let renamed = tree(&FILES);
+        fs::rename(
+            renamed.path().join("README.md"),
+            renamed.path().join("src/README.md"),
+        )
+        .unwrap();
+        assert_ne!(original, digest(&renamed));
+
+        // Large files are streamed in chunks
+        let large = "0123456789".repeat(5000);
+        let first = tree(&[("data.txt", &large)]);
+        let second = tree(&[("data.txt", &large[..large.len() - 1])]);
+        assert_ne!(digest(&first), digest(&second));
+    }
+
+    #[test]
+    fn empty_directories() {
+        let without = tree(&FILES);
+        let with = tree(&FILES);
+        fs::create_dir_all(with.path().join("target/debug")).unwrap();
+        assert_eq!(digest(&without), digest(&with));
+
+        let options = DirOptions::new().include_empty_dirs(true);
+        let including = hash_dir_contents(with.path(), options).unwrap();
+        assert_ne!(digest(&without), including);
+        assert_eq!(
+            digest(&without),
+            hash_dir_contents(without.path(), options).unwrap()
+        );
+        assert_eq!(0, digest(&tree(&[])));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn special_files_are_skipped() {
+        use std::os::unix::net::UnixListener;
+        use std::process::Command;
+
+        let plain = tree(&FILES);
+        let special = tree(&FILES);
+        let fifo = special.path().join("src/pipe");
+        let status = Command::new("mkfifo").arg(&fifo).status().unwrap();
+        assert!(status.success());
+        let _socket = UnixListener::bind(special.path().join("socket")).unwrap();
+        // Reading the FIFO would block forever, as no process writes to it
+        assert_eq!(digest(&plain), digest(&special));
+
+        let options = DirOptions::new().include_empty_dirs(true);
+        let only_special = tempfile::tempdir().unwrap();
+        fs::create_dir(only_special.path().join("dir")).unwrap();
+        let status = Command::new("mkfifo")
+            .arg(only_special.path().join("dir/pipe"))
+            .status()
+            .unwrap();
+        assert!(status.success());
+        let empty = tempfile::tempdir().unwrap();
+        fs::create_dir(empty.path().join("dir")).unwrap();
+        assert_eq!(
+            hash_dir_contents(empty.path(), options).unwrap(),
+            hash_dir_contents(only_special.path(), options).unwrap()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symbolic_links() {
+        use std::os::unix::fs::symlink;
+
+        let linked = tree(&FILES);
+        symlink("Cargo.toml", linked.path().join("manifest")).unwrap();
+        let copied = tree(&FILES);
+        fs::write(copied.path().join("manifest"), "[package]").unwrap();
+
+        assert_ne!(digest(&linked), digest(&copied));
+        let follow = DirOptions::new().follow_symlinks(true);
+        assert_eq!(
+            hash_dir_contents(linked.path(), follow).unwrap(),
+            hash_dir_contents(copied.path(), follow).unwrap()
+        );
+
+        symlink("..", linked.path().join("src/parent")).unwrap();
+        let error = hash_dir_contents(linked.path(), follow).unwrap_err();
+        assert_eq!(io::ErrorKind::Other, error.kind());
+        assert_ne!(digest(&copied), digest(&linked));
+    }
+}
@@ -0,0 +1,241 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Hardened combiners, resistant to collisions from structure in the element hashes
+
+use crate::{hashes_of, sum_of_hashes, BuildHasherFromFriend, UseDefaultHasher};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::num::Wrapping;
+
+///
+/// Implements hashing by summing the mixed hashes of each element. A new
+/// [`std::collections::hash_map::DefaultHasher`] is created for each element.
+///
+/// The plain sum computed by [`crate::hash_by_summing_hashes`] is linear: `{x, x}` collides
+/// with `{y}` whenever `2·h(x) == h(y)`, and likewise for any small linear relation between
+/// element hashes. The hardened mode passes each element hash through a non-linear finalizer,
+/// parameterized by the number of elements in the collection, before summing. Linear relations
+/// between element hashes no longer carry over to the sum, and collections of different sizes
+/// mix their elements differently.
+///
+/// The result differs from that of [`crate::hash_by_summing_hashes`], and the two must not be
+/// mixed. The collection is iterated twice, first to count its elements.
+///
+pub fn hash_by_summing_hashes_hardened<C, H>(collection: &C, state: &mut H)
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash,
+    H: Hasher,
+{
+    hash_by_summing_hashes_hardened_with::<C, H, UseDefaultHasher>(collection, state)
+}
+
+///
+/// Implements hashing like [`hash_by_summing_hashes_hardened`], with a means of specifying
+/// which kind of hasher is created per element via the `BH` parameter.
+///
+pub fn hash_by_summing_hashes_hardened_with<C, H, BH>(collection: &C, state: &mut H)
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash,
+    BH: BuildHasherFromFriend<C>,
+    H: Hasher,
+{
+    let count = collection.into_iter().count() as u64;
+    // Spreads the count over every bit, so that nearby counts give unrelated mixes
+    let key = mix(count.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+    let mixed = hashes_of(collection, || BH::build_hasher_from(collection))
+        .map(|hash| mix(hash ^ key))
+        .map(Wrapping);
+    state.write_u64(mixed.sum::<Wrapping<u64>>().0);
+}
+
+///
+/// A two-pass finalization of a sum of element hashes. The sum is mixed, then mixed again
+/// together with the number of elements and a constant, so the output depends non-linearly on
+/// both. Each pass is the finalizer of MurmurHash3.
+///
+/// The cost is two finalizer passes, about four multiplications, once per collection rather
+/// than per element, which is negligible beside hashing the elements.
+///
+/// A single pass already avalanches well over the bits of the sum, and the second pass does not
+/// improve on it measurably. Its benefit is in binding the number of elements: under a single
+/// pass, collections of different sizes whose sums are equal, such as `{x, x}` and `{y}` where
+/// `2·h(x) == h(y)`, or the empty collection and `{a, b}` where `h(a) + h(b) == 0`, collide.
+/// Under this finalizer, every bit of the element count avalanches into the output. Unlike
+/// [`hash_by_summing_hashes_hardened`], element hashes are summed unmixed, so collisions of
+/// equal-size collections with equal sums remain.
+///
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct StrongFinalizer(());
+
+impl StrongFinalizer {
+    /// Finalizes the sum of `count` element hashes
+    pub fn finalize(sum: u64, count: u64) -> u64 {
+        mix(mix(sum) ^ count.wrapping_mul(0x9e37_79b9_7f4a_7c15) ^ 0x2545_f491_4f6c_dd1d)
+    }
+}
+
+///
+/// Implements hashing by summing the hashes of each element, then finalizing the sum and the
+/// number of elements with the [`StrongFinalizer`]. A new [`DefaultHasher`] is created for
+/// each element. The collection is iterated once.
+///
+/// The result differs from that of [`crate::hash_by_summing_hashes`], and the two must not be
+/// mixed.
+///
+pub fn hash_strong_unordered<C, H>(collection: &C, state: &mut H)
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash,
+    H: Hasher,
+{
+    let mut count = 0_u64;
+    let sum = sum_of_hashes(collection, || {
+        count += 1;
+        DefaultHasher::new()
+    });
+    state.write_u64(StrongFinalizer::finalize(sum, count));
+}
+
+/// The finalizer of MurmurHash3, a bijection with good avalanche behavior
+fn mix(mut value: u64) -> u64 {
+    value ^= value >> 33;
+    value = value.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    value ^= value >> 33;
+    value = value.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    value ^= value >> 33;
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::UseIdentityHasher;
+    use crate::{hash_by_summing_hashes_with, SumHashesAnyCollection};
+
+    fn plain(values: &Vec<u64>) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        hash_by_summing_hashes_with::<Vec<u64>, _, UseIdentityHasher>(values, &mut hasher);
+        hasher.finish()
+    }
+
+    fn hardened(values: &Vec<u64>) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        hash_by_summing_hashes_hardened_with::<Vec<u64>, _, UseIdentityHasher>(values, &mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn multiplicity_collisions_are_separated() {
+        // With identity hashing, element hashes are the elements themselves
+        let collisions = [
+            (vec![1, 1], vec![2]),
+            (vec![1, 3], vec![2, 2]),
+            (vec![5, 5, 5], vec![15]),
+            (vec![u64::MAX, 1], vec![]),
+            (vec![1 << 63, 1 << 63], vec![0, 0]),
+        ];
+        for (first, second) in collisions {
+            assert_eq!(plain(&first), plain(&second));
+            assert_ne!(hardened(&first), hardened(&second));
+        }
+    }
+
+    #[test]
+    fn hardened_mode_is_order_independent() {
+        let values: Vec<u64> = (0..100).map(|n| n * n).collect();
+        let reversed: Vec<u64> = values.iter().rev().copied().collect();
+        assert_eq!(hardened(&values), hardened(&reversed));
+
+        let strings = vec!["one", "two", "three"];
+        let reordered = vec!["three", "one", "two"];
+        let (mut first, mut second) = (DefaultHasher::new(), DefaultHasher::new());
+        hash_by_summing_hashes_hardened::<Vec<&str>, _>(&strings, &mut first);
+        hash_by_summing_hashes_hardened::<Vec<&str>, _>(&reordered, &mut second);
+        assert_eq!(first.finish(), second.finish());
+    }
+
+    /// The mean and the worst deviation from one half of the probability that flipping an input
+    /// bit flips an output bit, over every pair of input and output bits
+    fn avalanche_bias(finalize: impl Fn(u64, u64) -> u64) -> (f64, f64) {
+        // Structured inputs: small sums and counts, as of small collections of small values
+        let inputs: Vec<(u64, u64)> = (0..4096_u64).map(|n| (n * 3, n % 17)).collect();
+        let input_bits = (0..64)
+            .map(|bit| (1_u64 << bit, 0))
+            .chain((0..8).map(|bit| (0, 1_u64 << bit)));
+        let (mut total, mut worst, mut pairs) = (0.0, 0.0_f64, 0);
+        for (sum_flip, count_flip) in input_bits {
+            let mut flips = [0_u32; 64];
+            for (sum, count) in &inputs {
+                let difference =
+                    finalize(*sum, *count) ^ finalize(sum ^ sum_flip, count ^ count_flip);
+                for (bit, flipped) in flips.iter_mut().enumerate() {
+                    *flipped += ((difference >> bit) & 1) as u32;
+                }
+            }
+            for flipped in flips {
+                let bias = (f64::from(flipped) / inputs.len() as f64 - 0.5).abs();
+                total += bias;
+                worst = worst.max(bias);
+                pairs += 1;
+            }
+        }
+        (total / f64::from(pairs), worst)
+    }
+
+    #[test]
+    fn strong_finalizer_avalanches_sum_and_count() {
+        let single_pass = |sum, _count| mix(sum);
+        let (single_mean, single_worst) = avalanche_bias(single_pass);
+        let (strong_mean, strong_worst) = avalanche_bias(StrongFinalizer::finalize);
+        // A single pass ignores the count entirely, so flipping a bit of it flips nothing
+        assert_eq!(0.5, single_worst);
+        assert!(single_mean > 0.05, "{single_mean}");
+        // Sampling noise over 4096 inputs is around 0.006 on average
+        assert!(strong_mean < 0.01, "{strong_mean}");
+        assert!(strong_worst < 0.05, "{strong_worst}");
+    }
+
+    #[test]
+    fn strong_mode_separates_sizes() {
+        fn strong(values: &[u64]) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            let sum = values
+                .iter()
+                .fold(0_u64, |sum, value| sum.wrapping_add(*value));
+            hasher.write_u64(StrongFinalizer::finalize(sum, values.len() as u64));
+            hasher.finish()
+        }
+        for (first, second) in [(vec![1, 1], vec![2]), (vec![u64::MAX, 1], vec![])] {
+            assert_eq!(plain(&first), plain(&second));
+            assert_ne!(strong(&first), strong(&second));
+        }
+
+        let strings = vec!["one", "two", "three"];
+        let reordered = vec!["three", "one", "two"];
+        let (mut first, mut second) = (DefaultHasher::new(), DefaultHasher::new());
+        hash_strong_unordered::<Vec<&str>, _>(&strings, &mut first);
+        hash_strong_unordered::<Vec<&str>, _>(&reordered, &mut second);
+        assert_eq!(first.finish(), second.finish());
+
+        let mut expected = DefaultHasher::new();
+        let sum = SumHashesAnyCollection::<_>::new(strings.clone()).raw_unordered_hash();
+        expected.write_u64(StrongFinalizer::finalize(sum, 3));
+        assert_eq!(expected.finish(), first.finish());
+    }
+}
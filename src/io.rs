@@ -0,0 +1,188 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Hashing of elements which are read from a stream
+
+use crate::sum_of_hashes;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hasher;
+use std::io::{self, BufRead, Read};
+use std::num::Wrapping;
+
+///
+/// Computes the order-independent digest of the lines of a reader, such as two configuration
+/// dumps which contain the same lines in different orders. Each line is hashed as a `str` by a
+/// new [`DefaultHasher`], and the hashes are summed. The result is the digest of a `Vec<String>`
+/// of the lines, which [`crate::hash_by_summing_hashes`] would write.
+///
+/// Lines are split as by [`BufRead::lines`]: the terminator, either `\n` or `\r\n`, is excluded,
+/// so files with either line ending hash equally. A final line terminator is optional, so a
+/// file hashes the same with or without one, and an empty file has the digest of no lines,
+/// `0`. Lines must be valid UTF-8, or an error of kind [`io::ErrorKind::InvalidData`] is
+/// returned.
+///
+/// The lines are treated as a multiset: a duplicated line contributes once per occurrence, so
+/// a file with a line repeated twice differs from the same file with it once.
+///
+pub fn hash_unordered_lines<R: BufRead>(reader: R) -> io::Result<u64> {
+    let mut error = None;
+    let lines = reader.lines().map_while(|line| match line {
+        Ok(line) => Some(line),
+        Err(e) => {
+            error = Some(e);
+            None
+        }
+    });
+    let digest = sum_of_hashes(lines, DefaultHasher::new);
+    match error {
+        Some(error) => Err(error),
+        None => Ok(digest),
+    }
+}
+
+///
+/// Implements hashing by summing element hashes which are read from a stream, one at a time,
+/// so that a collection larger than memory can be hashed, such as one stored in a file or
+/// memory-mapped. A memory-mapped file can be read as a `&[u8]`.
+///
+/// `parse` reads the hash of the next element from the reader, returning `None` at the end of
+/// the stream. It may hash an element which it decodes, or read a hash which was stored
+/// directly. To hash equally with [`crate::hash_by_summing_hashes`], elements should be hashed
+/// by a new [`DefaultHasher`] each. An error cannot be returned through `parse`; record it in
+/// a captured variable and return `None` to stop, then check it afterwards.
+///
+/// ```rust
+/// use hash_that_set::hash_from_reader;
+/// use std::collections::hash_map::DefaultHasher;
+/// use std::io::Read;
+///
+/// // Element hashes stored as consecutive little-endian u64s
+/// let stored: Vec<u8> = [1_u64, 2, 3].iter().flat_map(|hash| hash.to_le_bytes()).collect();
+/// let mut hasher = DefaultHasher::new();
+/// hash_from_reader(
+///     &stored[..],
+///     |reader| {
+///         let mut bytes = [0; 8];
+///         reader.read_exact(&mut bytes).ok()?;
+///         Some(u64::from_le_bytes(bytes))
+///     },
+///     &mut hasher,
+/// );
+/// ```
+///
+pub fn hash_from_reader<R, H, F>(mut reader: R, mut parse: F, state: &mut H)
+where
+    R: Read,
+    H: Hasher,
+    F: FnMut(&mut R) -> Option<u64>,
+{
+    let mut sum = Wrapping(0);
+    while let Some(hash) = parse(&mut reader) {
+        sum += hash;
+    }
+    state.write_u64(sum.0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hash_by_summing_hashes, SumHashesAnyCollection, UseDefaultHasher};
+    use std::hash::Hash;
+
+    fn digest(text: &[u8]) -> u64 {
+        hash_unordered_lines(text).unwrap()
+    }
+
+    #[test]
+    fn line_endings_and_trailing_newlines() {
+        let lf = digest(b"host=a\nport=1\n");
+        assert_eq!(lf, digest(b"port=1\nhost=a\n"));
+        assert_eq!(lf, digest(b"host=a\r\nport=1\r\n"));
+        assert_eq!(lf, digest(b"host=a\nport=1"));
+        assert_ne!(lf, digest(b"host=a\nport=1\n\n"));
+        assert_ne!(lf, digest(b"host=a\nport=1\nport=1\n"));
+        assert_eq!(0, digest(b""));
+    }
+
+    #[test]
+    fn agrees_with_collected_lines() {
+        let lines: Vec<String> = vec!["b".into(), "".into(), "a".into()];
+        let expected = SumHashesAnyCollection::<_, UseDefaultHasher>::new(lines);
+        assert_eq!(expected.raw_unordered_hash(), digest(b"a\n\nb\n"));
+    }
+
+    #[test]
+    fn invalid_utf8_is_an_error() {
+        let error = hash_unordered_lines(&b"valid\n\xff\n"[..]).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, error.kind());
+    }
+
+    #[test]
+    fn stream_of_element_hashes_matches_in_memory() {
+        let elements: Vec<u32> = (0..10_000).map(|n| n * 31).collect();
+        let stream: Vec<u8> = elements
+            .iter()
+            .flat_map(|element| {
+                let mut hasher = DefaultHasher::new();
+                element.hash(&mut hasher);
+                hasher.finish().to_le_bytes()
+            })
+            .collect();
+
+        let mut expected = DefaultHasher::new();
+        hash_by_summing_hashes::<Vec<u32>, _>(&elements, &mut expected);
+
+        let mut reads = 0;
+        let mut from_stream = DefaultHasher::new();
+        hash_from_reader(
+            &stream[..],
+            |reader| {
+                let mut bytes = [0; 8];
+                reader.read_exact(&mut bytes).ok()?;
+                reads += 1;
+                Some(u64::from_le_bytes(bytes))
+            },
+            &mut from_stream,
+        );
+        assert_eq!(expected.finish(), from_stream.finish());
+        assert_eq!(elements.len(), reads);
+    }
+
+    #[test]
+    fn stream_of_encoded_elements() {
+        // Elements are decoded from the stream, then hashed
+        let stream = b"\x03abc\x01d\x00";
+        let mut from_stream = DefaultHasher::new();
+        hash_from_reader(
+            &stream[..],
+            |reader| {
+                let mut len = [0];
+                reader.read_exact(&mut len).ok()?;
+                let mut element = vec![0; len[0] as usize];
+                reader.read_exact(&mut element).ok()?;
+                let mut hasher = DefaultHasher::new();
+                element.hash(&mut hasher);
+                Some(hasher.finish())
+            },
+            &mut from_stream,
+        );
+
+        let mut expected = DefaultHasher::new();
+        let elements: Vec<Vec<u8>> = vec![vec![], b"d".to_vec(), b"abc".to_vec()];
+        hash_by_summing_hashes::<Vec<Vec<u8>>, _>(&elements, &mut expected);
+        assert_eq!(expected.finish(), from_stream.finish());
+    }
+}
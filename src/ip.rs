@@ -0,0 +1,156 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Hashing of IP addresses in a canonical representation
+
+use crate::sum_of_hashes;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::net::{IpAddr, Ipv4Addr};
+use std::ops::Deref;
+
+///
+/// Makes an IP address hash and compare by its canonical representation, as returned by
+/// [`CanonicalIp::canonical`]. The policy is that an IPv4-mapped IPv6 address, such as
+/// `::ffff:1.2.3.4`, is the same as the IPv4 address it maps, such as `1.2.3.4`. Every other
+/// address is left as is. In particular, the deprecated IPv4-compatible addresses, such as
+/// `::1.2.3.4`, are not mapped, so that `::1` remains the IPv6 loopback address.
+///
+/// `IpAddr` has no scope identifier, so there is none to canonicalize; scope identifiers are a
+/// property of `SocketAddrV6`.
+///
+#[derive(Clone, Copy, Debug)]
+pub struct CanonicalIp(pub IpAddr);
+
+impl CanonicalIp {
+    /// Returns the canonical representation of the address
+    pub fn canonical(&self) -> IpAddr {
+        match self.0 {
+            IpAddr::V6(address) => match address.segments() {
+                [0, 0, 0, 0, 0, 0xffff, high, low] => {
+                    let [a, b] = high.to_be_bytes();
+                    let [c, d] = low.to_be_bytes();
+                    IpAddr::V4(Ipv4Addr::new(a, b, c, d))
+                }
+                _ => self.0,
+            },
+            IpAddr::V4(_) => self.0,
+        }
+    }
+}
+
+impl<A: Into<IpAddr>> From<A> for CanonicalIp {
+    fn from(address: A) -> Self {
+        Self(address.into())
+    }
+}
+
+impl PartialEq for CanonicalIp {
+    fn eq(&self, other: &Self) -> bool {
+        self.canonical() == other.canonical()
+    }
+}
+
+impl Eq for CanonicalIp {}
+
+impl Hash for CanonicalIp {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.canonical().hash(state)
+    }
+}
+
+///
+/// Implements hashing by summing the hashes of each IP address in its canonical
+/// representation, as described by [`CanonicalIp`]. A new [`DefaultHasher`] is created for each
+/// address. Elements may be of any type convertible to an `IpAddr`, such as `Ipv4Addr`.
+///
+pub fn hash_ips_by_summing<C, T, H>(collection: &C, state: &mut H)
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Deref<Target = T>,
+    T: Into<IpAddr> + Copy,
+    H: Hasher,
+{
+    let addresses = collection
+        .into_iter()
+        .map(|address| CanonicalIp::from(*address));
+    state.write_u64(sum_of_hashes(addresses, DefaultHasher::new));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash_by_summing_hashes;
+    use std::collections::HashSet;
+    use std::net::Ipv6Addr;
+
+    fn parse(addresses: &[&str]) -> HashSet<IpAddr> {
+        addresses
+            .iter()
+            .map(|address| address.parse().unwrap())
+            .collect()
+    }
+
+    fn canonical_hash(addresses: &HashSet<IpAddr>) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        hash_ips_by_summing::<HashSet<IpAddr>, _, _>(addresses, &mut hasher);
+        hasher.finish()
+    }
+
+    fn plain_hash(addresses: &HashSet<IpAddr>) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        hash_by_summing_hashes::<HashSet<IpAddr>, _>(addresses, &mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn mapped_addresses_hash_as_ipv4() {
+        let plain = parse(&["1.2.3.4", "10.0.0.1", "2001:db8::1"]);
+        let mapped = parse(&["::ffff:1.2.3.4", "::ffff:a00:1", "2001:db8::1"]);
+        assert_eq!(canonical_hash(&plain), canonical_hash(&mapped));
+        assert_ne!(plain_hash(&plain), plain_hash(&mapped));
+
+        let v4: Vec<Ipv4Addr> = vec![Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(1, 2, 3, 4)];
+        let mut from_v4 = DefaultHasher::new();
+        hash_ips_by_summing::<Vec<Ipv4Addr>, _, _>(&v4, &mut from_v4);
+        assert_eq!(
+            canonical_hash(&parse(&["::ffff:10.0.0.1", "1.2.3.4"])),
+            from_v4.finish()
+        );
+    }
+
+    #[test]
+    fn other_addresses_are_unchanged() {
+        for address in [
+            "::1",
+            "::1.2.3.4",
+            "::ffff:0:1.2.3.4",
+            "64:ff9b::1.2.3.4",
+            "::",
+        ] {
+            let address: IpAddr = address.parse().unwrap();
+            assert_eq!(address, CanonicalIp(address).canonical());
+        }
+        assert_eq!(
+            CanonicalIp::from(Ipv4Addr::LOCALHOST),
+            CanonicalIp::from(Ipv4Addr::LOCALHOST.to_ipv6_mapped())
+        );
+        assert_ne!(
+            CanonicalIp::from(Ipv4Addr::LOCALHOST),
+            CanonicalIp::from(Ipv6Addr::LOCALHOST)
+        );
+    }
+}
@@ -0,0 +1,212 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Compatibility with the hash codes of Java's sets
+
+use std::hash::Hasher;
+
+///
+/// Types whose `hashCode()` in Java can be computed in Rust, i.e. the hash code of the
+/// equivalent boxed Java object. Integers hash like `Byte`, `Short`, `Integer` and `Long`, a
+/// `bool` like `Boolean`, floats like `Float` and `Double`, and strings like `String`, over
+/// their UTF-16 code units.
+///
+/// Java has no unsigned integers, so they are not implemented. For other objects, compute the
+/// hash code in Java and wrap it in [`JavaHashCodeValue`].
+///
+pub trait JavaHashCode {
+    /// The value of `x.hashCode()` in Java, for the equivalent object `x`
+    fn java_hash_code(&self) -> i32;
+}
+
+/// A hash code computed by Java, such as the result of `x.hashCode()` for an arbitrary object
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct JavaHashCodeValue(pub i32);
+
+impl JavaHashCode for JavaHashCodeValue {
+    fn java_hash_code(&self) -> i32 {
+        self.0
+    }
+}
+
+impl<T: JavaHashCode + ?Sized> JavaHashCode for &T {
+    fn java_hash_code(&self) -> i32 {
+        (**self).java_hash_code()
+    }
+}
+
+macro_rules! java_hash_int {
+    ($($int:ty),*) => {
+        $(
+            impl JavaHashCode for $int {
+                fn java_hash_code(&self) -> i32 {
+                    i32::from(*self)
+                }
+            }
+        )*
+    };
+}
+
+java_hash_int!(i8, i16, i32);
+
+/// Computes the hash code of a `long`, which folds its high bits into its low bits
+fn hash_long(value: i64) -> i32 {
+    let value = value as u64;
+    (value ^ (value >> 32)) as i32
+}
+
+impl JavaHashCode for i64 {
+    fn java_hash_code(&self) -> i32 {
+        hash_long(*self)
+    }
+}
+
+impl JavaHashCode for bool {
+    fn java_hash_code(&self) -> i32 {
+        if *self {
+            1231
+        } else {
+            1237
+        }
+    }
+}
+
+impl JavaHashCode for f32 {
+    fn java_hash_code(&self) -> i32 {
+        // floatToIntBits collapses every NaN to the canonical one
+        let value = if self.is_nan() { f32::NAN } else { *self };
+        value.to_bits() as i32
+    }
+}
+
+impl JavaHashCode for f64 {
+    fn java_hash_code(&self) -> i32 {
+        // doubleToLongBits collapses every NaN to the canonical one
+        let value = if self.is_nan() { f64::NAN } else { *self };
+        hash_long(value.to_bits() as i64)
+    }
+}
+
+impl JavaHashCode for str {
+    fn java_hash_code(&self) -> i32 {
+        self.encode_utf16().fold(0_i32, |hash, unit| {
+            hash.wrapping_mul(31).wrapping_add(i32::from(unit))
+        })
+    }
+}
+
+impl JavaHashCode for String {
+    fn java_hash_code(&self) -> i32 {
+        self.as_str().java_hash_code()
+    }
+}
+
+///
+/// Computes the hash code which Java's `AbstractSet.hashCode()` gives a set of the elements:
+/// the sum of the elements' hash codes, wrapping as an `int`. Every standard `Set`, such as
+/// `HashSet`, `TreeSet` and the sets of `Set.of`, computes its hash code this way.
+///
+/// The elements must be distinct, as in a set, and each element's [`JavaHashCode`] must equal
+/// the `hashCode()` of the corresponding Java object, or the results will not agree with Java.
+/// The result can itself be wrapped in a [`JavaHashCodeValue`] to hash nested sets.
+///
+pub fn java_set_digest<C>(collection: &C) -> i32
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: JavaHashCode,
+{
+    collection
+        .into_iter()
+        .fold(0_i32, |sum, value| sum.wrapping_add(value.java_hash_code()))
+}
+
+///
+/// Implements hashing by writing the hash code which Java gives a set of the elements, as
+/// computed by [`java_set_digest`], to `state` as an `i32`.
+///
+pub fn java_set_hashcode<C, H>(collection: &C, state: &mut H)
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: JavaHashCode,
+    H: Hasher,
+{
+    state.write_i32(java_set_digest(collection));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{BTreeSet, HashSet};
+
+    // Expected values are from `hashCode()` of the equivalent `java.util.HashSet` in OpenJDK 17
+
+    #[test]
+    fn integer_elements() {
+        assert_eq!(0, java_set_digest::<Vec<i32>>(&vec![]));
+        let set: HashSet<i32> = [1, 2, 3].into_iter().collect();
+        assert_eq!(6, java_set_digest::<HashSet<i32>>(&set));
+        assert_eq!(4950, java_set_digest::<BTreeSet<i32>>(&(0..100).collect()));
+        // {Integer.MAX_VALUE, 1} overflows
+        assert_eq!(i32::MIN, java_set_digest::<Vec<i32>>(&vec![i32::MAX, 1]));
+        assert_eq!(-1, java_set_digest::<Vec<i8>>(&vec![-1]));
+    }
+
+    #[test]
+    fn long_elements() {
+        // Long.hashCode(-1L) == 0 and Long.hashCode(1L << 32) == 1
+        assert_eq!(1, java_set_digest::<Vec<i64>>(&vec![-1, 1 << 32]));
+        assert_eq!(
+            -2_147_483_647,
+            java_set_digest::<Vec<i64>>(&vec![i64::MIN, 1])
+        );
+    }
+
+    #[test]
+    fn other_elements() {
+        assert_eq!(2468, java_set_digest::<Vec<bool>>(&vec![true, false]));
+        assert_eq!(195, java_set_digest::<Vec<&str>>(&vec!["a", "b"]));
+        assert_eq!(99_162_322, "hello".java_hash_code());
+        // Outside the Basic Multilingual Plane, Java hashes the surrogate pair
+        assert_eq!(54_959_989, "\u{1f600}x".java_hash_code());
+        // "Aa" and "BB" famously collide
+        assert_eq!("Aa".java_hash_code(), "BB".java_hash_code());
+        assert_eq!(1_072_693_248, 1.0_f64.java_hash_code());
+        assert_eq!(1_065_353_216, 1.0_f32.java_hash_code());
+        assert_eq!(
+            f64::NAN.java_hash_code(),
+            f64::from_bits(0x7ff0_0000_0000_0001).java_hash_code()
+        );
+    }
+
+    #[test]
+    fn nested_sets() {
+        // {{1, 2}, {}}
+        let inner = [
+            JavaHashCodeValue(java_set_digest::<Vec<i32>>(&vec![1, 2])),
+            JavaHashCodeValue(java_set_digest::<Vec<i32>>(&vec![])),
+        ];
+        assert_eq!(3, java_set_digest::<[JavaHashCodeValue; 2]>(&inner));
+    }
+
+    #[test]
+    fn writes_the_digest() {
+        let mut hashed = std::collections::hash_map::DefaultHasher::new();
+        java_set_hashcode::<Vec<i32>, _>(&vec![1, 2, 3], &mut hashed);
+        let mut expected = std::collections::hash_map::DefaultHasher::new();
+        expected.write_i32(6);
+        assert_eq!(expected.finish(), hashed.finish());
+    }
+}
@@ -15,6 +15,8 @@
  */
 
 #![forbid(unsafe_code)]
+#![cfg_attr(feature = "nightly-simd", feature(portable_simd))]
+#![cfg_attr(feature = "nightly-allocator-api", feature(allocator_api))]
 
 //!
 //! This crate is dedicated to the sole purpose of hashing collections of elements
@@ -24,12 +26,146 @@
 //! Documentation referring to a "collection" means any type `C` where `&C: IntoIterator`
 //!
 
+use crate::trace::DigestSpan;
 use std::collections::hash_map::DefaultHasher;
 use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
 use std::hash::{BuildHasher, Hash, Hasher};
 use std::marker::PhantomData;
-use std::num::Wrapping;
-use std::ops::{Deref, DerefMut};
+
+mod adapters;
+#[cfg(feature = "nightly-allocator-api")]
+mod allocator;
+#[doc(hidden)]
+pub mod assert;
+#[doc(hidden)]
+pub mod auto;
+mod bloom;
+mod borrowed;
+mod chained;
+mod checked;
+mod combine;
+mod contract;
+mod contributions;
+mod delta;
+#[cfg(feature = "diagnostics")]
+mod diagnostics;
+mod field;
+mod float;
+mod foreign;
+#[cfg(feature = "fs")]
+mod fs;
+mod hardened;
+mod io;
+mod ip;
+#[cfg(feature = "jvm-compat")]
+mod java;
+mod mix;
+mod multiset;
+mod oneshot;
+mod order_independent;
+mod partial;
+mod paths;
+mod precomputed;
+mod prehashed;
+#[cfg(feature = "python-compat")]
+mod python;
+mod records;
+mod robust;
+mod scoped;
+mod seed;
+mod sketch;
+mod sorted;
+mod stable;
+mod tagged;
+#[cfg(test)]
+mod test_support;
+mod trace;
+mod trie;
+mod weighted;
+mod wrapper;
+mod xor;
+
+pub use adapters::{
+    digest_borrowed, digest_borrowed_map, hash_as_u64, hash_btreemap_keys_as_set, hash_by_display,
+    hash_by_display_with, hash_by_equivalence, hash_distribution, hash_edge_set, hash_modulo_eq,
+    hash_soa,
+};
+#[cfg(feature = "nightly-allocator-api")]
+pub use allocator::{combine_sorted_in, reconciliation_digest_in};
+pub use bloom::{hash_probabilistic_dedup, FilterTooLarge};
+pub use borrowed::{hash_iter_ref, SumHashesKey, SumHashesRef};
+pub use chained::hash_set_then_sequence;
+pub use checked::{hash_checked, hash_try_convert, ConvertError, InvariantViolation};
+pub use combine::{dual_combine, HashCombiner, SortedCombiner, SumCombiner, XorCombiner};
+pub use contract::{check_hash_eq_contract, ContractChecked, ContractViolation};
+pub use contributions::{entry_contribution, precompute_contributions, ContributionIndex};
+pub use delta::{
+    delta_hash, delta_hash_with, reconciliation_digest, verify_membership, DeltaReport,
+};
+#[cfg(feature = "diagnostics")]
+pub use diagnostics::{
+    compare_hashers, hash_profiled, HasherComparison, HasherStats, ProfileStats,
+};
+pub use field::poly_field_hash;
+pub use float::{hash_floats_by_summing, try_hash_floats_by_summing, Float, FloatBits, FoundNan};
+#[cfg(feature = "fixedbitset")]
+pub use foreign::hash_bitset;
+#[cfg(feature = "dashmap")]
+pub use foreign::hash_dashmap;
+#[cfg(all(feature = "dashmap", feature = "rayon"))]
+pub use foreign::par_hash_dashmap;
+#[cfg(feature = "subtle")]
+pub use foreign::verify_digest_ct;
+#[cfg(feature = "defmt")]
+pub use foreign::FormatDigest;
+#[cfg(feature = "xxh3")]
+pub use foreign::UseXxh3;
+#[cfg(feature = "arrow")]
+pub use foreign::{hash_arrow_array, UnsupportedType};
+#[cfg(feature = "unicode")]
+pub use foreign::{hash_nfkc_strings, hash_normalized_strings};
+#[cfg(feature = "serde_with")]
+pub use foreign::{UnorderedCanonical, UnorderedTransparent};
+#[cfg(feature = "fs")]
+pub use fs::{hash_dir_contents, DirOptions};
+pub use hardened::{
+    hash_by_summing_hashes_hardened, hash_by_summing_hashes_hardened_with, hash_strong_unordered,
+    StrongFinalizer,
+};
+pub use io::{hash_from_reader, hash_unordered_lines};
+pub use ip::{hash_ips_by_summing, CanonicalIp};
+#[cfg(feature = "jvm-compat")]
+pub use java::{java_set_digest, java_set_hashcode, JavaHashCode, JavaHashCodeValue};
+pub use mix::{
+    hash_primitive_slice_by_summing, IdentityMixHasher, PrimitiveElement, UseIdentityMix,
+};
+pub use multiset::{
+    compare_by_hash, hashes_would_equal, hashes_would_equal_with, multiset_eq,
+    verify_equal_by_hash, verify_equal_by_hash_with, EqualityResult, Unordered,
+};
+pub use oneshot::{hash_bytes_by_summing, OneShotStrategy};
+pub use order_independent::{OrderIndependentHasher, OrderIndependentState};
+pub use partial::{merge_partial_hashes, read_varint_frame, write_varint_frame, PartialHash};
+pub use paths::{hash_paths_by_summing, hash_paths_by_summing_with, PortablePath};
+pub use precomputed::{combine_precomputed_hashes, PrecomputedHashes};
+pub use prehashed::{Prehashed, PrehashedHasher, UsePrehashed};
+#[cfg(feature = "python-compat")]
+pub use python::{python_frozenset_digest, python_frozenset_hash, PythonHash, PythonHashValue};
+pub use records::{hash_records, RecordFields, RecordSchema};
+pub use robust::{hash_robust, hash_robust_with};
+pub use scoped::hash_by_summing_parallel_scoped;
+pub use seed::Seed;
+pub use sketch::{
+    countmin_fingerprint, fuzzy_hash, hash_prefix_unordered, lsh_bucket, multi_seed_hashes,
+    CountMinSketch, SketchTooLarge,
+};
+pub use sorted::SortedSumHashes;
+pub use stable::{hash_versioned, versioned_digest, Algorithm, StableHasher, UseStableHasher};
+pub use tagged::{hash_by_summing_tagged, Domain, DomainTag, UseTaggedHasher};
+pub use trie::{hash_trie_keys, TrieKeys};
+pub use weighted::hash_time_weighted;
+pub use xor::{XorHashes, XorHashesAnyCollection};
 
 ///
 /// Implements hashing by summing the hashes of each element. A new [`DefaultHasher`]
@@ -56,13 +192,225 @@ where
     BH: BuildHasherFromFriend<C>,
     H: Hasher,
 {
-    let mut sum = Wrapping::default();
-    for value in collection {
-        let mut hasher = BH::build_hasher_from(collection);
-        Hash::hash(&value, &mut hasher);
-        sum += hasher.finish();
-    }
-    state.write_u64(sum.0);
+    state.write_u64(summed_hash::<C, BH>(collection));
+}
+
+///
+/// Implements hashing by XORing the hashes of each element, as an alternative to summing. A new
+/// [`DefaultHasher`] is created for each element. Like summing, the result is independent of
+/// iteration order.
+///
+/// An element which appears twice cancels out, as does any even number of occurrences: a
+/// multiset hashes like the set of its elements which occur an odd number of times. Only use
+/// this for collections without duplicates, such as sets and maps.
+///
+pub fn hash_by_xoring_hashes<C, H>(collection: &C, state: &mut H)
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash,
+    H: Hasher,
+{
+    hash_by_xoring_hashes_with::<C, H, UseDefaultHasher>(collection, state)
+}
+
+///
+/// Implements hashing by XORing the hashes of each element, like [`hash_by_xoring_hashes`],
+/// with a means of specifying which kind of hasher is created per element via the `BH`
+/// parameter. The value written is the one which [`XorCombiner`] computes from the element
+/// hashes.
+///
+/// Because XOR is its own inverse, the digest can be maintained incrementally, by XORing in the
+/// hash of each element which is added or removed. Duplicate elements cancel out, as with
+/// [`hash_by_xoring_hashes`].
+///
+pub fn hash_by_xoring_hashes_with<C, H, BH>(collection: &C, state: &mut H)
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash,
+    BH: BuildHasherFromFriend<C>,
+    H: Hasher,
+{
+    state.write_u64(xored_hash::<C, BH>(collection));
+}
+
+///
+/// Implements hashing by summing the hashes of each element, using the hasher provided by the
+/// collection. This writes exactly what the `Hash` implementation of [`SumHashes`] writes.
+///
+pub fn hash_by_summing_with_provided_hasher<C, H>(collection: &C, state: &mut H)
+where
+    C: ProvidesHasher,
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash,
+    H: Hasher,
+{
+    hash_by_summing_hashes_with::<C, H, UseProvidedHasher<C>>(collection, state)
+}
+
+///
+/// Hashes a segmented collection, such as a rope or chunked buffer, by summing the hashes of
+/// every element of every segment. A new [`DefaultHasher`] is created for each element.
+///
+/// Because segments are combined additively, the result is independent of both segment
+/// order and the order of elements within each segment. It is identical to hashing the
+/// flattened elements with [`hash_by_summing_hashes`].
+///
+pub fn hash_segmented<Seg, H>(segments: &Seg, state: &mut H)
+where
+    for<'s> &'s Seg: IntoIterator,
+    for<'s> <&'s Seg as IntoIterator>::Item: IntoIterator,
+    for<'s> <<&'s Seg as IntoIterator>::Item as IntoIterator>::Item: Hash,
+    H: Hasher,
+{
+    hash_segmented_with::<Seg, H, UseDefaultHasher>(segments, state)
+}
+
+///
+/// Hashes a segmented collection like [`hash_segmented`], with a means of specifying which
+/// kind of hasher is created per element via the `BH` parameter.
+///
+pub fn hash_segmented_with<Seg, H, BH>(segments: &Seg, state: &mut H)
+where
+    for<'s> &'s Seg: IntoIterator,
+    for<'s> <&'s Seg as IntoIterator>::Item: IntoIterator,
+    for<'s> <<&'s Seg as IntoIterator>::Item as IntoIterator>::Item: Hash,
+    BH: BuildHasherFromFriend<Seg>,
+    H: Hasher,
+{
+    let values = segments.into_iter().flatten();
+    state.write_u64(sum_of_hashes(values, || BH::build_hasher_from(segments)));
+}
+
+///
+/// Implements hashing by summing the hashes of each element yielded by the iterator which
+/// `elements` creates. A new [`DefaultHasher`] is created for each element. This adapts any
+/// means of iteration, including one which needs setup beforehand, for types which neither
+/// implement `IntoIterator` by reference nor provide a hasher. For equal elements, the result
+/// is identical to that of [`hash_by_summing_hashes`].
+///
+pub fn hash_by_closure<F, I, H>(elements: F, state: &mut H)
+where
+    F: FnOnce() -> I,
+    I: Iterator,
+    I::Item: Hash,
+    H: Hasher,
+{
+    state.write_u64(sum_of_hashes(elements(), DefaultHasher::new));
+}
+
+///
+/// Determines whether two wrapped collections are likely equal, by comparing digests which are
+/// computed with a new [`DefaultHasher`] per element. Unlike the `Hash` implementation, this
+/// does not depend on the hashers provided by the collections, so it is comparable across
+/// independently created collections.
+///
+/// A result of `false` means the collections are certainly unequal. A result of `true` may be a
+/// false positive, as with any comparison of hashes; confirm with `==` where it matters.
+///
+pub fn likely_equal<C>(a: &SumHashes<C>, b: &SumHashes<C>) -> bool
+where
+    C: ProvidesHasher,
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash,
+{
+    a.digest_with::<UseDefaultHasher>() == b.digest_with::<UseDefaultHasher>()
+}
+
+///
+/// Hashes a collection by summing the hashes of each element, using the collection's own
+/// hasher if it implements [`ProvidesHasher`], and the [`DefaultHasher`] otherwise. Takes the
+/// collection and a `&mut` reference to the outer hasher.
+///
+/// ```rust
+/// # use std::collections::hash_map::DefaultHasher;
+/// # use std::collections::HashSet;
+/// use hash_that_set::hash_auto;
+///
+/// let mut hasher = DefaultHasher::new();
+/// let set: HashSet<u8> = (0..10).collect();
+/// hash_auto!(set, &mut hasher); // Uses the set's RandomState
+/// let vec: Vec<u8> = (0..10).collect();
+/// hash_auto!(vec, &mut hasher); // Uses the DefaultHasher
+/// ```
+///
+/// This is a macro rather than a function because, without specialization, the choice can
+/// only be made where the collection's type is concrete. Inside generic code, the bounds of
+/// the generic function decide, so use [`hash_by_summing_hashes_with`] there.
+///
+#[macro_export]
+macro_rules! hash_auto {
+    ($collection:expr, $state:expr) => {{
+        #[allow(unused_imports)]
+        use $crate::auto::{HashWithDefaultHasher as _, HashWithProvidedHasher as _};
+        (&$crate::auto::AutoHash(&$collection)).hash_auto($state)
+    }};
+}
+
+/// Computes the wrapping sum of element hashes, i.e. the value written by
+/// [`hash_by_summing_hashes_with`]
+fn summed_hash<C, BH>(collection: &C) -> u64
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash,
+    BH: BuildHasherFromFriend<C>,
+{
+    let expected = collection.into_iter().size_hint().0;
+    let mut span = DigestSpan::enter::<BH>("summed_hash", expected);
+    let sum = sum_of_hashes(collection, || {
+        span.add_elements(1);
+        BH::build_hasher_from(collection)
+    });
+    span.finish(sum)
+}
+
+/// Computes the XOR of element hashes, i.e. the value written by [`hash_by_xoring_hashes_with`]
+fn xored_hash<C, BH>(collection: &C) -> u64
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash,
+    BH: BuildHasherFromFriend<C>,
+{
+    let expected = collection.into_iter().size_hint().0;
+    let mut span = DigestSpan::enter::<BH>("xored_hash", expected);
+    let xor = XorCombiner::combine(hashes_of(collection, || {
+        span.add_elements(1);
+        BH::build_hasher_from(collection)
+    }));
+    span.finish(xor)
+}
+
+/// Computes the wrapping sum of the hashes of the given values, creating a new hasher for each
+fn sum_of_hashes<I, S, F>(values: I, new_hasher: F) -> u64
+where
+    I: IntoIterator,
+    I::Item: Hash,
+    S: Hasher,
+    F: FnMut() -> S,
+{
+    SumCombiner::combine(hashes_of(values, new_hasher))
+}
+
+/// Hashes each of the given values with a new hasher, in iteration order
+pub(crate) fn hashes_of<I, S, F>(values: I, mut new_hasher: F) -> impl Iterator<Item = u64>
+where
+    I: IntoIterator,
+    I::Item: Hash,
+    S: Hasher,
+    F: FnMut() -> S,
+{
+    values
+        .into_iter()
+        .map(move |value| hash_of(&value, new_hasher()))
+}
+
+/// Hashes a single value with the given hasher
+pub(crate) fn hash_of<T, S>(value: &T, mut hasher: S) -> u64
+where
+    T: Hash + ?Sized,
+    S: Hasher,
+{
+    value.hash(&mut hasher);
+    hasher.finish()
 }
 
 ///
@@ -83,13 +431,31 @@ where
 /// This may be used with any collection, although it requires the wrapped collection to implement
 /// [`ProvidesHasher`].
 ///
+/// # Hash and Eq
+///
+/// Elements are hashed using the wrapped collection's own hasher. Two wrapped collections which
+/// compare equal therefore hash equally **only if their hashers are identically seeded**. For
+/// example, a [`BuildHasherDefault`](std::hash::BuildHasherDefault) always is, as is a
+/// `RandomState` cloned from another collection. By contrast, two `HashMap`s which are each
+/// created through `HashMap::new` have different `RandomState` seeds; they may compare equal,
+/// yet hash differently, which violates the contract between `Hash` and `Eq`. If the wrapper is
+/// used as a key, or otherwise compared against collections created independently, either
+/// share a deterministic `BuildHasher` between all of them, or use
+/// [`SumHashesAnyCollection`], which hashes elements with the default hasher.
+///
 /// The layout of this struct is guaranteed to be the same as the wrapped collection. This means
 /// it is possible to transmute references; however, [`hash_by_summing_hashes`] is usually a better
-/// option than relying on `unsafe`.
+/// option than relying on `unsafe`. With the `bytemuck` feature, `bytemuck::TransparentWrapper`
+/// is implemented, which provides safe conversions of references and slices.
 ///
-#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(
+    feature = "bytemuck",
+    derive(bytemuck::TransparentWrapper),
+    transparent(C)
+)]
 #[repr(transparent)]
-pub struct SumHashes<C: ProvidesHasher>(SumHashesAnyCollection<C, UseProvidedHasher<C>>);
+pub struct SumHashes<C: ProvidesHasher>(C);
 
 ///
 /// Adds hashing to any collection according to the hash of each element, but without
@@ -101,53 +467,66 @@ pub struct SumHashes<C: ProvidesHasher>(SumHashesAnyCollection<C, UseProvidedHas
 ///
 /// The layout of this struct is guaranteed to be the same as the wrapped collection. This means
 /// it is possible to transmute references; however, [`hash_by_summing_hashes_with`] is usually a
-/// better option than relying on `unsafe`.
+/// better option than relying on `unsafe`. With the `bytemuck` feature,
+/// `bytemuck::TransparentWrapper` is implemented, which provides safe conversions of references
+/// and slices.
 ///
-#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "bytemuck",
+    derive(bytemuck::TransparentWrapper),
+    transparent(C)
+)]
 #[repr(transparent)]
 pub struct SumHashesAnyCollection<C, H = UseDefaultHasher>(C, PhantomData<H>);
 
-impl<C: ProvidesHasher> From<C> for SumHashes<C> {
-    /// Creates the wrapper
-    #[inline]
-    fn from(value: C) -> Self {
-        Self(SumHashesAnyCollection::from(value))
-    }
-}
-
-impl<C: ProvidesHasher> SumHashes<C> {
-    /// Creates the wrapper
-    #[inline]
-    pub fn new(value: C) -> Self {
-        Self::from(value)
-    }
+wrapper::wrapper_impls!(SumHashes, SumHashesAnyCollection, summed_hash);
 
-    /// Destructures into the inner collection
-    #[inline]
-    pub fn into_inner(self) -> C {
-        self.0 .0
-    }
-}
-
-impl<C, H> From<C> for SumHashesAnyCollection<C, H> {
-    /// Creates the wrapper
-    #[inline]
-    fn from(value: C) -> Self {
-        Self(value, PhantomData)
+impl<K, V, S> SumHashes<HashMap<K, V, S>>
+where
+    K: Eq + Hash,
+    V: PartialEq,
+    S: BuildHasher,
+{
+    ///
+    /// Determines whether both maps have equal entries, even if their hashers differ. Their
+    /// digests can then be compared with [`SumHashes::digest_with`], using hashers which do not
+    /// depend on either map's, such as those of [`UseDefaultHasher`]:
+    ///
+    /// ```rust
+    /// # use std::collections::HashMap;
+    /// # use std::collections::hash_map::DefaultHasher;
+    /// # use std::hash::BuildHasherDefault;
+    /// use hash_that_set::{SumHashes, UseDefaultHasher};
+    ///
+    /// let mut first: SumHashes<HashMap<i8, &str>> = Default::default();
+    /// let mut second: SumHashes<HashMap<i8, &str, BuildHasherDefault<DefaultHasher>>> =
+    ///     Default::default();
+    /// first.insert(1, "one");
+    /// second.insert(1, "one");
+    ///
+    /// assert!(first.eq_contents(&second));
+    /// assert_eq!(
+    ///     first.digest_with::<UseDefaultHasher>(),
+    ///     second.digest_with::<UseDefaultHasher>()
+    /// );
+    /// ```
+    ///
+    pub fn eq_contents<S2: BuildHasher>(&self, other: &SumHashes<HashMap<K, V, S2>>) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .all(|(key, value)| other.get(key) == Some(value))
     }
 }
 
-impl<C, H> SumHashesAnyCollection<C, H> {
-    /// Creates the wrapper
-    #[inline]
-    pub fn new(value: C) -> Self {
-        Self::from(value)
-    }
-
-    /// Destructures into the inner collection
-    #[inline]
-    pub fn into_inner(self) -> C {
-        self.0
+impl<O, S> SumHashes<HashSet<O, S>>
+where
+    O: Eq + Hash,
+    S: BuildHasher,
+{
+    /// Determines whether both sets have equal elements, even if their hashers differ
+    pub fn eq_contents<S2: BuildHasher>(&self, other: &SumHashes<HashSet<O, S2>>) -> bool {
+        self.len() == other.len() && self.iter().all(|value| other.contains(value))
     }
 }
 
@@ -198,6 +577,8 @@ impl<C: ProvidesHasher> BuildHasherFromFriend<C> for UseProvidedHasher<C> {
 /// for `HashMap` and `HashSet`. It allows the wrapper [`SumHashes`] to use the same
 /// hashing implementation for elements as is used for the whole hash result.
 ///
+/// With the `hashbrown` feature, it is also implemented for hashbrown's `HashMap` and `HashSet`,
+/// and with the `im` feature, for im's persistent `HashMap` and `HashSet`.
 /// PRs are welcome to add features for collections from other crates which yield their hashers.
 ///
 pub trait ProvidesHasher {
@@ -230,76 +611,52 @@ where
     }
 }
 
-impl<C: ProvidesHasher> Hash for SumHashes<C>
-where
-    for<'c> &'c C: IntoIterator,
-    for<'c> <&'c C as IntoIterator>::Item: Hash,
-{
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        Hash::hash(&self.0, state)
-    }
+///
+/// Trait for collections which can be created empty, given a hashing implementation and an
+/// initial capacity. This is automatically implemented for `HashMap` and `HashSet`. It allows
+/// the wrappers to be constructed directly, which is useful in generic code.
+///
+pub trait NewCollection: ProvidesHasher {
+    /// Creates an empty collection which will use the given hasher
+    fn with_hasher(hasher: Self::Hasher) -> Self;
+
+    /// Creates an empty collection with at least the specified capacity, which will use the
+    /// given hasher
+    fn with_capacity_and_hasher(capacity: usize, hasher: Self::Hasher) -> Self;
 }
 
-impl<C, BH> Hash for SumHashesAnyCollection<C, BH>
+impl<K, V, S> NewCollection for HashMap<K, V, S>
 where
-    for<'c> &'c C: IntoIterator,
-    for<'c> <&'c C as IntoIterator>::Item: Hash,
-    BH: BuildHasherFromFriend<C>,
+    S: BuildHasher,
 {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        hash_by_summing_hashes_with::<C, H, BH>(&self.0, state)
-    }
-}
-
-impl<C: ProvidesHasher + IntoIterator> IntoIterator for SumHashes<C> {
-    type Item = <C as IntoIterator>::Item;
-    type IntoIter = <C as IntoIterator>::IntoIter;
-
-    fn into_iter(self) -> Self::IntoIter {
-        self.0 .0.into_iter()
-    }
-}
-
-impl<C: IntoIterator> IntoIterator for SumHashesAnyCollection<C> {
-    type Item = <C as IntoIterator>::Item;
-    type IntoIter = <C as IntoIterator>::IntoIter;
-
-    fn into_iter(self) -> Self::IntoIter {
-        self.0.into_iter()
-    }
-}
-
-impl<C: ProvidesHasher> Deref for SumHashes<C> {
-    type Target = C;
-
-    fn deref(&self) -> &Self::Target {
-        &self.0 .0
+    fn with_hasher(hasher: Self::Hasher) -> Self {
+        HashMap::with_hasher(hasher)
     }
-}
 
-impl<C: ProvidesHasher> DerefMut for SumHashes<C> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0 .0
+    fn with_capacity_and_hasher(capacity: usize, hasher: Self::Hasher) -> Self {
+        HashMap::with_capacity_and_hasher(capacity, hasher)
     }
 }
 
-impl<C> Deref for SumHashesAnyCollection<C> {
-    type Target = C;
-
-    fn deref(&self) -> &Self::Target {
-        &self.0
+impl<O, S> NewCollection for HashSet<O, S>
+where
+    S: BuildHasher,
+{
+    fn with_hasher(hasher: Self::Hasher) -> Self {
+        HashSet::with_hasher(hasher)
     }
-}
 
-impl<C> DerefMut for SumHashesAnyCollection<C> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+    fn with_capacity_and_hasher(capacity: usize, hasher: Self::Hasher) -> Self {
+        HashSet::with_capacity_and_hasher(capacity, hasher)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::hash_map::RandomState;
+    use std::hash::BuildHasherDefault;
+    use std::ops::DerefMut;
 
     #[test]
     fn maps_and_sets_impl_hash() {
@@ -319,6 +676,180 @@ mod tests {
         let _: SumHashes<HashMap<i8, &str>> = Default::default();
     }
 
+    fn build_wrappers<C>(hasher: C::Hasher) -> (SumHashes<C>, SumHashesAnyCollection<C>)
+    where
+        C: NewCollection,
+        C::Hasher: Clone + Default,
+    {
+        let _ = SumHashes::<C>::with_capacity(8);
+        let _ = SumHashesAnyCollection::<C>::with_hasher(hasher.clone());
+        (
+            SumHashes::with_hasher(hasher.clone()),
+            SumHashesAnyCollection::with_capacity_and_hasher(16, hasher),
+        )
+    }
+
+    #[test]
+    fn construct_through_new_collection() {
+        let (mut map, mut other_map) = build_wrappers::<
+            HashMap<i8, &str, BuildHasherDefault<DefaultHasher>>,
+        >(Default::default());
+        map.insert(1, "hi");
+        other_map.insert(1, "hi");
+        assert_eq!(map.raw_unordered_hash(), other_map.raw_unordered_hash());
+        assert!(other_map.capacity() >= 16);
+
+        let state = RandomState::new();
+        let (set, other_set) = build_wrappers::<HashSet<i8>>(state);
+        assert!(set.is_empty() && other_set.is_empty());
+
+        #[cfg(feature = "hashbrown")]
+        {
+            let (mut map, _) = build_wrappers::<hashbrown::HashMap<i8, &str>>(Default::default());
+            map.insert(1, "hi");
+            assert_eq!(Some(&"hi"), map.get(&1));
+        }
+    }
+
+    /// A single write to a [`RecordingHasher`]
+    #[derive(Debug, Eq, PartialEq)]
+    enum Write {
+        U64(u64),
+        Bytes(Vec<u8>),
+    }
+
+    /// Records every write, to observe exactly what a `Hash` implementation writes
+    #[derive(Default)]
+    struct RecordingHasher(Vec<Write>);
+
+    impl Hasher for RecordingHasher {
+        fn finish(&self) -> u64 {
+            unreachable!("the recorded writes are inspected instead of the finished hash")
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            self.0.push(Write::Bytes(bytes.to_vec()));
+        }
+
+        fn write_u64(&mut self, i: u64) {
+            self.0.push(Write::U64(i));
+        }
+    }
+
+    #[test]
+    fn raw_unordered_hash_is_what_hash_writes() {
+        let set: HashSet<&str> = ["a", "b", "c"].into_iter().collect();
+
+        let mut free_fn = RecordingHasher::default();
+        hash_by_summing_hashes_with::<HashSet<&str>, _, UseDefaultHasher>(&set, &mut free_fn);
+
+        let any_collection = SumHashesAnyCollection::<_, UseDefaultHasher>::new(set.clone());
+        let mut wrapper = RecordingHasher::default();
+        Hash::hash(&any_collection, &mut wrapper);
+
+        assert_eq!(
+            vec![Write::U64(any_collection.raw_unordered_hash())],
+            free_fn.0
+        );
+        assert_eq!(free_fn.0, wrapper.0);
+
+        let mut free_fn = RecordingHasher::default();
+        hash_by_summing_hashes_with::<HashSet<&str>, _, UseProvidedHasher<_>>(&set, &mut free_fn);
+
+        let sum_hashes = SumHashes::new(set);
+        let mut wrapper = RecordingHasher::default();
+        Hash::hash(&sum_hashes, &mut wrapper);
+
+        assert_eq!(vec![Write::U64(sum_hashes.raw_unordered_hash())], free_fn.0);
+        assert_eq!(free_fn.0, wrapper.0);
+    }
+
+    #[test]
+    fn provided_hasher_free_fn_matches_wrapper() {
+        let map: HashMap<u16, char> = (0..200).zip('a'..).collect();
+        let mut free_fn = RecordingHasher::default();
+        hash_by_summing_with_provided_hasher::<HashMap<u16, char>, _>(&map, &mut free_fn);
+
+        let wrapped = SumHashes::new(map);
+        let mut wrapper = RecordingHasher::default();
+        Hash::hash(&wrapped, &mut wrapper);
+        assert_eq!(free_fn.0, wrapper.0);
+        assert_eq!(vec![Write::U64(wrapped.raw_unordered_hash())], wrapper.0);
+
+        // Unlike the default hasher, which ignores the collection's seed
+        let mut default_hasher = RecordingHasher::default();
+        hash_by_summing_hashes::<HashMap<u16, char>, _>(&wrapped, &mut default_hasher);
+        assert_ne!(default_hasher.0, wrapper.0);
+    }
+
+    #[test]
+    fn compare_across_hashers() {
+        type FixedState = BuildHasherDefault<DefaultHasher>;
+
+        let entries = [(1, "one"), (2, "two"), (3, "three")];
+        let map: SumHashes<HashMap<i8, &str>> = SumHashes::new(entries.into_iter().collect());
+        let other_map: SumHashes<HashMap<i8, &str, FixedState>> =
+            SumHashes::new(entries.into_iter().rev().collect());
+        assert!(map.eq_contents(&other_map));
+        assert_eq!(
+            map.digest_with::<UseDefaultHasher>(),
+            other_map.digest_with::<UseDefaultHasher>()
+        );
+
+        let mut changed = other_map.clone();
+        changed.insert(3, "drei");
+        assert!(!map.eq_contents(&changed));
+        assert_ne!(
+            map.digest_with::<UseDefaultHasher>(),
+            changed.digest_with::<UseDefaultHasher>()
+        );
+        changed.remove(&3);
+        assert!(!map.eq_contents(&changed));
+
+        let set: SumHashes<HashSet<i8>> = SumHashes::new((0..10).collect());
+        let other_set: SumHashes<HashSet<i8, FixedState>> = SumHashes::new((0..10).rev().collect());
+        assert!(set.eq_contents(&other_set));
+        assert_eq!(
+            set.digest_with::<UseDefaultHasher>(),
+            other_set.digest_with::<UseDefaultHasher>()
+        );
+        let any_collection = SumHashesAnyCollection::<Vec<i8>>::new((0..10).collect());
+        assert_eq!(
+            set.digest_with::<UseDefaultHasher>(),
+            any_collection.digest_with::<UseDefaultHasher>()
+        );
+    }
+
+    #[test]
+    fn resegmenting_produces_identical_hash() {
+        fn hash(segments: &Vec<Vec<&str>>) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            hash_segmented::<Vec<Vec<&str>>, _>(segments, &mut hasher);
+            hasher.finish()
+        }
+        let flat = vec!["a", "rope", "of", "several", "chunks"];
+        let expected = {
+            let mut hasher = DefaultHasher::new();
+            hash_by_summing_hashes::<Vec<&str>, _>(&flat, &mut hasher);
+            hasher.finish()
+        };
+
+        let single = vec![flat.clone()];
+        let split = vec![vec!["a", "rope"], vec!["of"], vec!["several", "chunks"]];
+        let reordered = vec![
+            vec!["chunks"],
+            vec![],
+            vec!["of", "a"],
+            vec!["several", "rope"],
+        ];
+        for segments in [single, split, reordered] {
+            assert_eq!(expected, hash(&segments));
+        }
+
+        let different = vec![vec!["a", "rope"], vec!["of", "other", "chunks"]];
+        assert_ne!(expected, hash(&different));
+    }
+
     #[test]
     fn same_elements_produce_identical_hash() {
         // To simulate differences in iteration order, use sorting and different data structure
@@ -351,4 +882,185 @@ mod tests {
             assert_eq!(hash, other);
         }
     }
+
+    #[test]
+    fn xoring_is_independent_of_order() {
+        let unsorted = vec![(4, ""), (1, "hi"), (-3, "hello"), (20, "good bye")];
+        let mut sorted = Vec::from_iter(unsorted.clone());
+        sorted.sort();
+        let map: HashMap<i8, &str> = unsorted.iter().cloned().collect();
+        let sorted_map: HashMap<i8, &str> = sorted.iter().cloned().collect();
+        let set: HashSet<(i8, &str)> = unsorted.iter().cloned().collect();
+        let sorted_set: HashSet<(i8, &str)> = sorted.iter().cloned().collect();
+
+        macro_rules! gen_hash {
+            ($var:ident, $collection:ty) => {{
+                let mut hasher = DefaultHasher::new();
+                hash_by_xoring_hashes::<$collection, _>(&$var, &mut hasher);
+                hasher.finish()
+            }};
+        }
+        let all_hashes = [
+            gen_hash!(unsorted, Vec<(i8, &str)>),
+            gen_hash!(sorted, Vec<(i8, &str)>),
+            gen_hash!(map, HashMap<i8, &str>),
+            gen_hash!(sorted_map, HashMap<i8, &str>),
+            gen_hash!(set, HashSet<(i8, &str)>),
+            gen_hash!(sorted_set, HashSet<(i8, &str)>),
+        ];
+        let hash = all_hashes[0];
+        for other in all_hashes {
+            assert_eq!(hash, other);
+        }
+    }
+
+    #[test]
+    fn xoring_cancels_duplicate_pairs() {
+        fn xor_hash(values: &Vec<u8>) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            hash_by_xoring_hashes_with::<Vec<u8>, _, UseDefaultHasher>(values, &mut hasher);
+            hasher.finish()
+        }
+        assert_eq!(xor_hash(&vec![]), xor_hash(&vec![7, 7]));
+        assert_eq!(xor_hash(&vec![1]), xor_hash(&vec![1, 2, 2]));
+        assert_eq!(xor_hash(&vec![1]), xor_hash(&vec![2, 1, 2, 2, 2]));
+        assert_ne!(xor_hash(&vec![1]), xor_hash(&vec![1, 2]));
+    }
+
+    #[test]
+    fn equal_maps_with_shared_hasher_hash_equally() {
+        type Deterministic = BuildHasherDefault<DefaultHasher>;
+        fn hash_one<T: Hash>(value: &T) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            value.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        let first: HashMap<i32, &str, Deterministic> =
+            [(1, "one"), (2, "two"), (3, "three")].into_iter().collect();
+        let mut second: HashMap<i32, &str, Deterministic> = HashMap::default();
+        second.insert(3, "three");
+        second.insert(1, "one");
+        second.insert(2, "two");
+        assert_eq!(first, second);
+
+        let mut outer = HashSet::new();
+        outer.insert(SumHashes::new(first));
+        assert!(!outer.insert(SumHashes::new(second)));
+
+        let first: HashSet<i32> = (0..100).collect();
+        let mut second = HashSet::with_hasher(first.hasher().clone());
+        second.extend((0..100).rev());
+        assert_eq!(
+            hash_one(&SumHashes::new(first)),
+            hash_one(&SumHashes::new(second))
+        );
+
+        let first: HashSet<i32, RandomState> = (0..100).collect();
+        let second: HashSet<i32, RandomState> = (0..100).rev().collect();
+        assert_eq!(
+            hash_one(&SumHashesAnyCollection::<_, UseDefaultHasher>::new(first)),
+            hash_one(&SumHashesAnyCollection::<_, UseDefaultHasher>::new(second))
+        );
+    }
+
+    #[test]
+    fn hash_without_equality() {
+        use std::collections::BinaryHeap;
+
+        static_assertions::assert_impl_all!(SumHashesAnyCollection<BinaryHeap<i32>>: Hash, Clone, Debug, Default);
+        static_assertions::assert_not_impl_any!(SumHashesAnyCollection<BinaryHeap<i32>>: PartialEq);
+
+        #[derive(Debug, Default)]
+        struct Bag(Vec<f32>);
+        #[derive(Hash)]
+        struct Bits(u32);
+        impl<'c> IntoIterator for &'c Bag {
+            type Item = Bits;
+            type IntoIter = std::iter::Map<std::slice::Iter<'c, f32>, fn(&f32) -> Bits>;
+
+            fn into_iter(self) -> Self::IntoIter {
+                self.0.iter().map(|float| Bits(float.to_bits()))
+            }
+        }
+        // A marker which implements none of the derivable traits
+        struct UseBagHasher;
+        impl BuildHasherFromFriend<Bag> for UseBagHasher {
+            type Hasher = DefaultHasher;
+
+            fn build_hasher_from<'f>(_: &'f Bag) -> Self::Hasher
+            where
+                Self::Hasher: 'f,
+            {
+                DefaultHasher::new()
+            }
+        }
+        static_assertions::assert_impl_all!(SumHashesAnyCollection<Bag, UseBagHasher>: Hash, Debug, Default);
+        static_assertions::assert_not_impl_any!(SumHashesAnyCollection<Bag, UseBagHasher>: Clone, PartialEq);
+
+        let mut heap = SumHashesAnyCollection::<BinaryHeap<i32>>::default();
+        heap.extend([3, 1, 2]);
+        let mut bag = SumHashesAnyCollection::<Bag, UseBagHasher>::new(Bag(vec![1.5, 0.5]));
+        bag.deref_mut().0.push(2.0);
+        assert_eq!(3, heap.clone().into_iter().count());
+        assert_eq!(
+            "SumHashesAnyCollection(Bag([1.5, 0.5, 2.0]))",
+            format!("{:?}", bag)
+        );
+        assert_ne!(heap.raw_unordered_hash(), bag.raw_unordered_hash());
+    }
+
+    #[test]
+    fn closure_yielding_computed_sequence() {
+        let squares: HashSet<u64> = (1..=10).map(|n| n * n).collect();
+        let mut expected = DefaultHasher::new();
+        hash_by_summing_hashes::<HashSet<u64>, _>(&squares, &mut expected);
+
+        let mut from_closure = DefaultHasher::new();
+        hash_by_closure(|| (1..=10_u64).rev().map(|n| n * n), &mut from_closure);
+        assert_eq!(expected.finish(), from_closure.finish());
+
+        // Setup happens inside the closure, which runs exactly once
+        let mut calls = 0;
+        let mut with_setup = DefaultHasher::new();
+        hash_by_closure(
+            || {
+                calls += 1;
+                let mut buffer: Vec<u64> = squares.iter().copied().collect();
+                buffer.sort_unstable();
+                buffer.into_iter()
+            },
+            &mut with_setup,
+        );
+        assert_eq!(1, calls);
+        assert_eq!(expected.finish(), with_setup.finish());
+
+        let mut empty = DefaultHasher::new();
+        hash_by_closure(std::iter::empty::<u64>, &mut empty);
+        let mut zero = DefaultHasher::new();
+        zero.write_u64(0);
+        assert_eq!(zero.finish(), empty.finish());
+    }
+
+    #[test]
+    fn likely_equal_across_seeds() {
+        let first = SumHashes::new((0..50).collect::<HashSet<u8>>());
+        let second = SumHashes::new((0..50).rev().collect::<HashSet<u8>>());
+        let fewer = SumHashes::new((1..50).collect::<HashSet<u8>>());
+        let other = SumHashes::new((50..100).collect::<HashSet<u8>>());
+
+        assert!(likely_equal::<HashSet<u8>>(&first, &second));
+        assert!(likely_equal::<HashSet<u8>>(&first, &first));
+        assert!(!likely_equal::<HashSet<u8>>(&first, &fewer));
+        assert!(!likely_equal::<HashSet<u8>>(&first, &other));
+
+        let empty = SumHashes::new(HashMap::<u8, u8>::new());
+        let mut single = SumHashes::new(HashMap::new());
+        single.insert(0, 0);
+        assert!(likely_equal::<HashMap<u8, u8>>(
+            &empty,
+            &SumHashes::new(HashMap::new())
+        ));
+        assert!(!likely_equal::<HashMap<u8, u8>>(&empty, &single));
+    }
 }
@@ -0,0 +1,308 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! A fast per-element strategy for integers, mixing their values instead of hashing them
+
+use crate::BuildHasherFromFriend;
+use std::hash::Hasher;
+
+///
+/// Implementation of [`BuildHasherFromFriend`] which, instead of running a hash function, mixes
+/// each integer written by an element with a fixed 64-bit finalizer. For collections of
+/// integers, this is much faster than creating a [`std::collections::hash_map::DefaultHasher`]
+/// per element. It must be chosen explicitly; the defaults of this crate are unaffected.
+///
+/// An element which writes a single integer `x`, widened to a `u64`, hashes as
+/// `splitmix64(x)`, the output function of the SplitMix64 generator:
+///
+/// ```text
+/// z = x + 0x9e3779b97f4a7c15
+/// z = (z ^ (z >> 30)) * 0xbf58476d1ce4e5b9
+/// z = (z ^ (z >> 27)) * 0x94d049bb133111eb
+/// z ^ (z >> 31)
+/// ```
+///
+/// with wrapping arithmetic. Further writes are mixed into the previous result as
+/// `splitmix64(state ^ x)`. Byte slices are split into little-endian 64-bit words, followed by
+/// their length, so that elements other than integers still hash deterministically.
+///
+/// The mix is unkeyed and invertible, so it offers no resistance against chosen inputs. Use it
+/// for trusted data only.
+///
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct UseIdentityMix(());
+
+impl<F> BuildHasherFromFriend<F> for UseIdentityMix {
+    type Hasher = IdentityMixHasher;
+
+    fn build_hasher_from<'f>(_: &'f F) -> Self::Hasher
+    where
+        Self::Hasher: 'f,
+    {
+        IdentityMixHasher::default()
+    }
+}
+
+/// The hasher of [`UseIdentityMix`]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct IdentityMixHasher {
+    state: u64,
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+///
+/// Primitive integers, for [`hash_primitive_slice_by_summing`]. Sealed; implemented for the
+/// integer types of at most 64 bits.
+///
+pub trait PrimitiveElement: Copy + sealed::Sealed {
+    /// Widens to a `u64` exactly as [`IdentityMixHasher`] does when the value is hashed
+    fn widen(self) -> u64;
+}
+
+macro_rules! primitive_element {
+    ($($int:ty => |$value:ident| $widen:expr),*) => {
+        $(
+            impl sealed::Sealed for $int {}
+
+            impl PrimitiveElement for $int {
+                #[inline]
+                fn widen(self) -> u64 {
+                    let $value = self;
+                    $widen
+                }
+            }
+        )*
+    };
+}
+
+// The standard library writes smaller signed integers as the unsigned type of the same width
+primitive_element!(
+    u8 => |value| u64::from(value),
+    u16 => |value| u64::from(value),
+    u32 => |value| u64::from(value),
+    u64 => |value| value,
+    usize => |value| value as u64,
+    i8 => |value| u64::from(value as u8),
+    i16 => |value| u64::from(value as u16),
+    i32 => |value| u64::from(value as u32),
+    i64 => |value| value as u64,
+    isize => |value| value as i64 as u64
+);
+
+///
+/// Implements hashing of a slice of integers, as a multiset, by summing their hashes under
+/// [`UseIdentityMix`]. The result is the same as that of
+/// [`crate::hash_by_summing_hashes_with`] with [`UseIdentityMix`].
+///
+/// With the `nightly-simd` feature, elements are processed in chunks of 8 with `std::simd`.
+/// Otherwise, they are summed in a plain loop, which the compiler vectorizes where the target
+/// supports it. Either way, large slices are mostly bound by memory bandwidth; the benefit of
+/// `std::simd` is greatest with wide vectors, e.g. with `-C target-cpu=native` on AVX-512.
+///
+pub fn hash_primitive_slice_by_summing<T, H>(slice: &[T], state: &mut H)
+where
+    T: PrimitiveElement,
+    H: Hasher,
+{
+    state.write_u64(sum_mixed(slice));
+}
+
+#[cfg(not(feature = "nightly-simd"))]
+fn sum_mixed<T: PrimitiveElement>(slice: &[T]) -> u64 {
+    sum_mixed_scalar(slice)
+}
+
+#[cfg(feature = "nightly-simd")]
+fn sum_mixed<T: PrimitiveElement>(slice: &[T]) -> u64 {
+    use std::simd::num::SimdUint;
+    use std::simd::u64x8;
+
+    let mut lanes = u64x8::splat(0);
+    let mut chunks = slice.chunks_exact(8);
+    for chunk in &mut chunks {
+        let mut widened = [0; 8];
+        for (lane, value) in widened.iter_mut().zip(chunk) {
+            *lane = value.widen();
+        }
+        let mut z = u64x8::from_array(widened) + u64x8::splat(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> u64x8::splat(30))) * u64x8::splat(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> u64x8::splat(27))) * u64x8::splat(0x94d0_49bb_1331_11eb);
+        lanes += z ^ (z >> u64x8::splat(31));
+    }
+    sum_mixed_scalar(chunks.remainder()).wrapping_add(lanes.reduce_sum())
+}
+
+fn sum_mixed_scalar<T: PrimitiveElement>(slice: &[T]) -> u64 {
+    slice
+        .iter()
+        .fold(0, |sum, value| sum.wrapping_add(splitmix64(value.widen())))
+}
+
+/// The output function of SplitMix64
+pub(crate) fn splitmix64(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+impl Hasher for IdentityMixHasher {
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.state
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for chunk in bytes.chunks(8) {
+            let mut word = [0; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            self.write_u64(u64::from_le_bytes(word));
+        }
+        self.write_u64(bytes.len() as u64);
+    }
+
+    #[inline]
+    fn write_u8(&mut self, i: u8) {
+        self.write_u64(u64::from(i));
+    }
+
+    #[inline]
+    fn write_u16(&mut self, i: u16) {
+        self.write_u64(u64::from(i));
+    }
+
+    #[inline]
+    fn write_u32(&mut self, i: u32) {
+        self.write_u64(u64::from(i));
+    }
+
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        self.state = splitmix64(self.state ^ i);
+    }
+
+    #[inline]
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    #[inline]
+    fn write_i64(&mut self, i: i64) {
+        self.write_u64(i as u64);
+    }
+
+    #[inline]
+    fn write_isize(&mut self, i: isize) {
+        self.write_u64(i as i64 as u64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hash_by_summing_hashes, hash_by_summing_hashes_with};
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::HashSet;
+    use std::hash::Hash;
+
+    fn mixed(set: &HashSet<u64>) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        hash_by_summing_hashes_with::<HashSet<u64>, _, UseIdentityMix>(set, &mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn documented_mix() {
+        // The first outputs of SplitMix64 seeded with 0, since the generator's state advances
+        // by the same constant
+        assert_eq!(0xe220_a839_7b1d_cdaf, splitmix64(0));
+        assert_eq!(0x6e78_9e6a_a1b9_65f4, splitmix64(0x9e37_79b9_7f4a_7c15));
+
+        let mut hasher = IdentityMixHasher::default();
+        7_u32.hash(&mut hasher);
+        assert_eq!(splitmix64(7), hasher.finish());
+        let mut hasher = IdentityMixHasher::default();
+        (-1_i8).hash(&mut hasher);
+        assert_eq!(splitmix64(0xff), hasher.finish());
+    }
+
+    #[test]
+    fn permutation_invariant() {
+        let forward: HashSet<u64> = (0..1000).collect();
+        let backward: HashSet<u64> = (0..1000).rev().collect();
+        assert_eq!(mixed(&forward), mixed(&backward));
+        let expected = (0..1000).fold(0_u64, |sum, value| sum.wrapping_add(splitmix64(value)));
+        let mut hasher = DefaultHasher::new();
+        hasher.write_u64(expected);
+        assert_eq!(hasher.finish(), mixed(&forward));
+
+        let mut fewer = forward.clone();
+        fewer.remove(&0);
+        assert_ne!(mixed(&forward), mixed(&fewer));
+    }
+
+    #[test]
+    fn opt_in_only() {
+        let set: HashSet<u64> = [1, 2, 3].into_iter().collect();
+        let expected = set.iter().fold(0_u64, |sum, value| {
+            let mut hasher = DefaultHasher::new();
+            value.hash(&mut hasher);
+            sum.wrapping_add(hasher.finish())
+        });
+        let mut default = DefaultHasher::new();
+        hash_by_summing_hashes::<HashSet<u64>, _>(&set, &mut default);
+        let mut expected_hasher = DefaultHasher::new();
+        expected_hasher.write_u64(expected);
+        assert_eq!(expected_hasher.finish(), default.finish());
+        assert_ne!(default.finish(), mixed(&set));
+    }
+
+    #[test]
+    fn slices_match_scalar_path() {
+        fn scalar<T: Hash>(values: &Vec<T>) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            hash_by_summing_hashes_with::<Vec<T>, _, UseIdentityMix>(values, &mut hasher);
+            hasher.finish()
+        }
+        fn sliced<T: PrimitiveElement>(values: &[T]) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            hash_primitive_slice_by_summing(values, &mut hasher);
+            hasher.finish()
+        }
+        // Lengths around multiples of the chunk size
+        for len in [0, 1, 7, 8, 9, 16, 100, 1001] {
+            let values: Vec<u64> = (0..len)
+                .map(|n: u64| n.wrapping_mul(0x1357_9bdf_2468_ace1))
+                .collect();
+            assert_eq!(scalar(&values), sliced(&values));
+            let narrow: Vec<u32> = values.iter().map(|value| *value as u32).collect();
+            assert_eq!(scalar(&narrow), sliced(&narrow));
+            let signed: Vec<i32> = narrow.iter().map(|value| *value as i32).collect();
+            assert_eq!(scalar(&signed), sliced(&signed));
+            let bytes: Vec<i8> = narrow.iter().map(|value| *value as i8).collect();
+            assert_eq!(scalar(&bytes), sliced(&bytes));
+            let wide: Vec<isize> = values.iter().map(|value| *value as isize).collect();
+            assert_eq!(scalar(&wide), sliced(&wide));
+        }
+        let mut reversed: Vec<u64> = (0..50).collect();
+        let forward = sliced(&reversed);
+        reversed.reverse();
+        assert_eq!(forward, sliced(&reversed));
+    }
+}
@@ -0,0 +1,398 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Order-independent equality of collections, both exact and by comparing digests
+
+use crate::{sum_of_hashes, summed_hash, UseDefaultHasher};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, BuildHasherDefault, Hash, Hasher};
+use std::ops::{Deref, DerefMut};
+
+///
+/// Makes both equality and hashing order-independent, so that a sequence such as a `Vec` or
+/// `VecDeque` may be used as a multiset. Two wrapped collections are equal if they contain
+/// equal elements with equal multiplicities, in any order. `Deref` and `DerefMut` provide
+/// access to the wrapped type.
+///
+/// Unlike [`crate::SumHashesAnyCollection`], which changes hashing only, this wrapper upholds
+/// the contract between `Hash` and `Eq` for sequences, and is suitable as a key.
+///
+/// ```rust
+/// use hash_that_set::Unordered;
+///
+/// assert_eq!(Unordered::new(vec![1, 2, 2]), Unordered::new(vec![2, 1, 2]));
+/// assert_ne!(Unordered::new(vec![1, 2, 2]), Unordered::new(vec![1, 1, 2]));
+/// ```
+///
+#[derive(Clone, Copy, Debug, Default)]
+#[repr(transparent)]
+pub struct Unordered<C>(C);
+
+impl<C> From<C> for Unordered<C> {
+    /// Creates the wrapper
+    #[inline]
+    fn from(value: C) -> Self {
+        Self(value)
+    }
+}
+
+impl<C> Unordered<C> {
+    /// Creates the wrapper
+    #[inline]
+    pub fn new(collection: C) -> Self {
+        Self(collection)
+    }
+
+    /// Destructures into the inner collection
+    #[inline]
+    pub fn into_inner(self) -> C {
+        self.0
+    }
+}
+
+impl<C> PartialEq for Unordered<C>
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash + Eq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        let equal = same_counts(&self.0, &other.0);
+        #[cfg(feature = "debug-assert")]
+        crate::contract::debug_assert_contract(equal, self, other);
+        equal
+    }
+}
+
+impl<C> Eq for Unordered<C>
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash + Eq,
+{
+}
+
+impl<C> Hash for Unordered<C>
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(summed_hash::<C, UseDefaultHasher>(&self.0))
+    }
+}
+
+impl<C: IntoIterator> IntoIterator for Unordered<C> {
+    type Item = <C as IntoIterator>::Item;
+    type IntoIter = <C as IntoIterator>::IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<C> Deref for Unordered<C> {
+    type Target = C;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<C> DerefMut for Unordered<C> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+///
+/// Determines whether two collections contain equal elements with equal multiplicities, in any
+/// order. The collections may be of different types, such as a `Vec` and a `VecDeque`.
+///
+/// The elements of `a` are counted into a temporary `HashMap`, then the elements of `b` are
+/// removed from it. If the iterators report their exact lengths, collections of different
+/// lengths are rejected without counting.
+///
+pub fn multiset_eq<A, B, T>(a: &A, b: &B) -> bool
+where
+    A: ?Sized,
+    B: ?Sized,
+    for<'a> &'a A: IntoIterator<Item = &'a T>,
+    for<'b> &'b B: IntoIterator<Item = &'b T>,
+    T: Hash + Eq + ?Sized,
+{
+    same_counts(a, b)
+}
+
+///
+/// The outcome of comparing two collections by their digests
+///
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum EqualityResult {
+    /// The collections are certainly not equal
+    DefinitelyDifferent,
+    /// The digests are equal, but the elements were not compared
+    ProbablyEqual,
+    /// The digests are equal, and the elements were confirmed equal as multisets
+    VerifiedEqual,
+}
+
+///
+/// Determines whether two collections would have the same order-independent digest. Both
+/// digests are computed with a new [`DefaultHasher`] per element, never with the collections'
+/// own hashers, so that the result does not depend on how each collection was seeded.
+///
+pub fn hashes_would_equal<A, B, T>(a: &A, b: &B) -> bool
+where
+    A: ?Sized,
+    B: ?Sized,
+    for<'a> &'a A: IntoIterator<Item = &'a T>,
+    for<'b> &'b B: IntoIterator<Item = &'b T>,
+    T: Hash + ?Sized,
+{
+    hashes_would_equal_with(a, b, &BuildHasherDefault::<DefaultHasher>::default())
+}
+
+///
+/// Determines whether two collections would have the same order-independent digest, like
+/// [`hashes_would_equal`], creating each element's hasher from the shared `build_hasher`.
+///
+pub fn hashes_would_equal_with<A, B, T, S>(a: &A, b: &B, build_hasher: &S) -> bool
+where
+    A: ?Sized,
+    B: ?Sized,
+    for<'a> &'a A: IntoIterator<Item = &'a T>,
+    for<'b> &'b B: IntoIterator<Item = &'b T>,
+    T: Hash + ?Sized,
+    S: BuildHasher,
+{
+    let new_hasher = || build_hasher.build_hasher();
+    sum_of_hashes(a, new_hasher) == sum_of_hashes(b, new_hasher)
+}
+
+///
+/// Compares two collections by their digests only, as computed by [`hashes_would_equal`].
+/// The result is never [`EqualityResult::VerifiedEqual`]; use [`verify_equal_by_hash`] if the
+/// elements may be compared.
+///
+pub fn compare_by_hash<A, B, T>(a: &A, b: &B) -> EqualityResult
+where
+    A: ?Sized,
+    B: ?Sized,
+    for<'a> &'a A: IntoIterator<Item = &'a T>,
+    for<'b> &'b B: IntoIterator<Item = &'b T>,
+    T: Hash + ?Sized,
+{
+    if hashes_would_equal(a, b) {
+        EqualityResult::ProbablyEqual
+    } else {
+        EqualityResult::DefinitelyDifferent
+    }
+}
+
+///
+/// Compares two collections by their digests, as computed by [`hashes_would_equal`], then
+/// confirms equal digests with [`multiset_eq`]. Collections whose digests collide, but whose
+/// elements differ, are reported as [`EqualityResult::DefinitelyDifferent`].
+///
+pub fn verify_equal_by_hash<A, B, T>(a: &A, b: &B) -> EqualityResult
+where
+    A: ?Sized,
+    B: ?Sized,
+    for<'a> &'a A: IntoIterator<Item = &'a T>,
+    for<'b> &'b B: IntoIterator<Item = &'b T>,
+    T: Hash + Eq + ?Sized,
+{
+    verify_equal_by_hash_with(a, b, &BuildHasherDefault::<DefaultHasher>::default())
+}
+
+///
+/// Compares two collections like [`verify_equal_by_hash`], creating each element's hasher
+/// from the shared `build_hasher`.
+///
+pub fn verify_equal_by_hash_with<A, B, T, S>(a: &A, b: &B, build_hasher: &S) -> EqualityResult
+where
+    A: ?Sized,
+    B: ?Sized,
+    for<'a> &'a A: IntoIterator<Item = &'a T>,
+    for<'b> &'b B: IntoIterator<Item = &'b T>,
+    T: Hash + Eq + ?Sized,
+    S: BuildHasher,
+{
+    if hashes_would_equal_with(a, b, build_hasher) && multiset_eq(a, b) {
+        EqualityResult::VerifiedEqual
+    } else {
+        EqualityResult::DefinitelyDifferent
+    }
+}
+
+fn same_counts<I, J, T>(a: I, b: J) -> bool
+where
+    I: IntoIterator<Item = T>,
+    J: IntoIterator<Item = T>,
+    T: Hash + Eq,
+{
+    let (a, b) = (a.into_iter(), b.into_iter());
+    if let ((low_a, Some(high_a)), (low_b, Some(high_b))) = (a.size_hint(), b.size_hint()) {
+        if low_a == high_a && low_b == high_b && low_a != low_b {
+            return false;
+        }
+    }
+    // Counts the elements of one side, then removes the elements of the other
+    let mut counts = HashMap::new();
+    let mut remaining = 0_usize;
+    for value in a {
+        *counts.entry(value).or_insert(0_usize) += 1;
+        remaining += 1;
+    }
+    for value in b {
+        match counts.get_mut(&value) {
+            Some(count) if *count > 0 => *count -= 1,
+            _ => return false,
+        }
+        remaining -= 1;
+    }
+    remaining == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::IdentityHasher;
+    use std::collections::hash_map::RandomState;
+    use std::collections::{HashSet, VecDeque};
+
+    fn hash_one<T: Hash>(value: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn permutations_are_equal() {
+        let first = Unordered::new(vec!["a", "b", "b", "c"]);
+        let second = Unordered::new(vec!["b", "c", "a", "b"]);
+        assert_eq!(first, second);
+        assert_eq!(hash_one(&first), hash_one(&second));
+
+        let deque: Unordered<VecDeque<_>> = Unordered::new(second.iter().copied().collect());
+        assert_eq!(Unordered::new(VecDeque::from(first.into_inner())), deque);
+        assert_eq!(Unordered::new(Vec::<u8>::new()), Unordered::default());
+    }
+
+    #[test]
+    fn multiplicities_must_match() {
+        let first = Unordered::new(vec![1, 2, 2, 3]);
+        assert_ne!(first, Unordered::new(vec![1, 1, 2, 3]));
+        assert_ne!(first, Unordered::new(vec![1, 2, 3]));
+        assert_ne!(first, Unordered::new(vec![1, 2, 2, 3, 3]));
+        assert_ne!(Unordered::new(vec![1, 2]), Unordered::new(vec![1, 2, 2]));
+        assert_ne!(Unordered::new(vec![1, 2, 2]), Unordered::new(vec![1, 2]));
+        assert_ne!(Unordered::new(vec![7]), Unordered::new(vec![]));
+    }
+
+    #[test]
+    fn usable_as_key() {
+        let mut map = HashMap::new();
+        map.insert(Unordered::new(vec![3, 1, 1]), "first");
+        assert_eq!(
+            Some("first"),
+            map.insert(Unordered::new(vec![1, 3, 1]), "second")
+        );
+        assert_eq!(None, map.insert(Unordered::new(vec![1, 3, 3]), "third"));
+        assert_eq!(2, map.len());
+        assert_eq!(Some(&"second"), map.get(&Unordered::new(vec![1, 1, 3])));
+    }
+
+    #[test]
+    fn multiset_eq_across_collections() {
+        let values = vec![4, 1, 4, 2];
+        let deque: VecDeque<i32> = [1, 2, 4, 4].into_iter().collect();
+        assert!(multiset_eq(&values, &deque));
+        assert!(multiset_eq(&values[..], &values[..]));
+        assert!(multiset_eq(&Vec::<i32>::new(), &[] as &[i32]));
+
+        assert!(!multiset_eq(&values, &[1, 2, 4]));
+        assert!(!multiset_eq(&[1, 2, 4], &values));
+        assert!(!multiset_eq(&values, &[1, 1, 2, 4]));
+        assert!(!multiset_eq(&values, &[1, 2, 4, 4, 4]));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    proptest::proptest! {
+        #[test]
+        fn multiset_eq_matches_sorting(
+            a in proptest::collection::vec(0_u8..8, 0..12),
+            b in proptest::collection::vec(0_u8..8, 0..12),
+            shuffle in proptest::collection::vec(proptest::num::usize::ANY, 12),
+        ) {
+            let sorted = |values: &[u8]| {
+                let mut values = values.to_vec();
+                values.sort_unstable();
+                values
+            };
+            proptest::prop_assert_eq!(sorted(&a) == sorted(&b), multiset_eq(&a, &b));
+
+            let mut permuted = a.clone();
+            for (index, swap) in (0..permuted.len()).zip(&shuffle) {
+                permuted.swap(index, *swap % a.len());
+            }
+            proptest::prop_assert!(multiset_eq(&a, &permuted));
+        }
+    }
+
+    #[test]
+    fn compare_regardless_of_seeds() {
+        let first: HashSet<i32, RandomState> = (0..20).collect();
+        let second: HashSet<i32, RandomState> = (0..20).rev().collect();
+        let values: Vec<i32> = (0..20).collect();
+        assert!(hashes_would_equal(&first, &second));
+        assert!(hashes_would_equal(&first, &values));
+        assert!(!hashes_would_equal(&first, &values[1..]));
+
+        assert_eq!(
+            EqualityResult::ProbablyEqual,
+            compare_by_hash(&first, &values)
+        );
+        assert_eq!(
+            EqualityResult::VerifiedEqual,
+            verify_equal_by_hash(&first, &second)
+        );
+        assert_eq!(
+            EqualityResult::DefinitelyDifferent,
+            compare_by_hash(&first, &values[1..])
+        );
+        assert_eq!(
+            EqualityResult::DefinitelyDifferent,
+            verify_equal_by_hash(&first, &values[1..])
+        );
+    }
+
+    #[test]
+    fn verification_rejects_sum_collision() {
+        // With identity hashing, 1 + 3 and 2 + 2 sum to the same digest
+        let identity = BuildHasherDefault::<IdentityHasher>::default();
+        let (first, second) = (vec![1, 3], vec![2, 2]);
+        assert!(hashes_would_equal_with(&first, &second, &identity));
+        assert_eq!(
+            EqualityResult::DefinitelyDifferent,
+            verify_equal_by_hash_with(&first, &second, &identity)
+        );
+        assert_eq!(
+            EqualityResult::VerifiedEqual,
+            verify_equal_by_hash_with(&first, &vec![3, 1], &identity)
+        );
+    }
+}
@@ -0,0 +1,145 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! A `BuildHasher` whose hashers are insensitive to the order of writes
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{BuildHasher, BuildHasherDefault, Hasher};
+use std::num::Wrapping;
+
+///
+/// A [`BuildHasher`] whose hashers combine everything written to them order-independently.
+/// Each `write` call is hashed on its own by a fresh hasher from `BH`, and the results are
+/// summed. The produced hash therefore does not depend on the order in which writes occur.
+///
+/// This is useful as the `S` of a `HashMap` or `HashSet` whose keys are themselves sets, but
+/// whose `Hash` implementation feeds elements one at a time, as in
+/// `for element in self { element.hash(state) }`.
+///
+/// # Usage contract
+///
+/// The key type must uphold the following, otherwise the order independence is meaningless:
+///
+/// * Each element must be written with exactly one call to a `write` method. This holds for
+///   integers and other primitives, which this crate's functions also write. It does *not* hold
+///   for strings, slices, or tuples, whose `Hash` implementations perform several writes; their
+///   parts then become separate elements. In such cases, hash each element into a single `u64`
+///   first, for example with a [`DefaultHasher`], and write that.
+/// * The key's `Eq` implementation must also ignore order, so that `Eq` and `Hash` agree.
+/// * Anything else written by the `Hash` implementation, such as a length prefix, becomes an
+///   element in its own right.
+///
+/// Keys whose `Hash` implementation already uses this crate, e.g. [`crate::SumHashes`], write a
+/// single value and are compatible as-is.
+///
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OrderIndependentState<BH = BuildHasherDefault<DefaultHasher>>(BH);
+
+impl<BH> OrderIndependentState<BH> {
+    /// Creates the state, hashing each write using hashers from the given builder
+    #[inline]
+    pub fn new(build_hasher: BH) -> Self {
+        Self(build_hasher)
+    }
+}
+
+impl<BH: BuildHasher + Clone> BuildHasher for OrderIndependentState<BH> {
+    type Hasher = OrderIndependentHasher<BH>;
+
+    fn build_hasher(&self) -> Self::Hasher {
+        OrderIndependentHasher {
+            build_hasher: self.0.clone(),
+            sum: Wrapping::default(),
+        }
+    }
+}
+
+/// The hasher produced by [`OrderIndependentState`]
+#[derive(Clone, Debug)]
+pub struct OrderIndependentHasher<BH> {
+    build_hasher: BH,
+    sum: Wrapping<u64>,
+}
+
+impl<BH: BuildHasher> Hasher for OrderIndependentHasher<BH> {
+    fn finish(&self) -> u64 {
+        self.sum.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        let mut hasher = self.build_hasher.build_hasher();
+        hasher.write(bytes);
+        self.sum += hasher.finish();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::hash::Hash;
+
+    /// A set key which writes each element individually and compares without regard to order
+    #[derive(Debug)]
+    struct MySetKey(Vec<u32>);
+
+    impl Hash for MySetKey {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            for element in &self.0 {
+                state.write_u32(*element);
+            }
+        }
+    }
+
+    impl PartialEq for MySetKey {
+        fn eq(&self, other: &Self) -> bool {
+            let mut this = self.0.clone();
+            let mut other = other.0.clone();
+            this.sort_unstable();
+            other.sort_unstable();
+            this == other
+        }
+    }
+
+    impl Eq for MySetKey {}
+
+    fn hash_key(state: &OrderIndependentState, key: &MySetKey) -> u64 {
+        let mut hasher = state.build_hasher();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn set_keys_hash_regardless_of_order() {
+        let state = OrderIndependentState::default();
+        assert_eq!(
+            hash_key(&state, &MySetKey(vec![1, 2, 3])),
+            hash_key(&state, &MySetKey(vec![3, 1, 2]))
+        );
+        assert_ne!(
+            hash_key(&state, &MySetKey(vec![1, 2, 3])),
+            hash_key(&state, &MySetKey(vec![1, 2, 4]))
+        );
+
+        let mut map: HashMap<MySetKey, &str, OrderIndependentState> = HashMap::default();
+        map.insert(MySetKey(vec![5, 10, 15]), "multiples");
+        map.insert(MySetKey(vec![2, 3, 5, 7]), "primes");
+
+        assert_eq!(Some(&"multiples"), map.get(&MySetKey(vec![15, 5, 10])));
+        assert_eq!(Some(&"primes"), map.get(&MySetKey(vec![7, 5, 3, 2])));
+        assert_eq!(None, map.get(&MySetKey(vec![7, 5, 3])));
+    }
+}
@@ -0,0 +1,202 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Digests which are computed in parts and combined later
+
+use crate::{hashes_of, BuildHasherFromFriend, UseDefaultHasher};
+use std::hash::Hash;
+use std::num::Wrapping;
+
+///
+/// The digest of one shard of a collection, e.g. computed on a separate machine. Summing is
+/// cleanly decomposable, so [`merge_partial_hashes`] reproduces the digest of the whole
+/// collection from the partial digests of its shards.
+///
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct PartialHash {
+    /// The wrapping sum of the element hashes of the shard
+    pub sum: u64,
+    /// The number of elements in the shard
+    pub count: usize,
+}
+
+impl PartialHash {
+    /// Computes the partial hash of a shard, creating a new [`std::collections::hash_map::DefaultHasher`]
+    /// for each element
+    pub fn of<C>(shard: &C) -> Self
+    where
+        for<'c> &'c C: IntoIterator,
+        for<'c> <&'c C as IntoIterator>::Item: Hash,
+    {
+        Self::of_with::<C, UseDefaultHasher>(shard)
+    }
+
+    ///
+    /// Computes the partial hash of a shard, with a means of specifying which kind of hasher is
+    /// created per element via the `BH` parameter. For the merged result to be meaningful, every
+    /// shard must hash its elements the same way; for example, if `BH` takes the hasher from the
+    /// shard, all shards must have identically seeded hashers.
+    ///
+    pub fn of_with<C, BH>(shard: &C) -> Self
+    where
+        for<'c> &'c C: IntoIterator,
+        for<'c> <&'c C as IntoIterator>::Item: Hash,
+        BH: BuildHasherFromFriend<C>,
+    {
+        let mut sum = Wrapping::default();
+        let mut count = 0;
+        for hash in hashes_of(shard, || BH::build_hasher_from(shard)) {
+            sum += hash;
+            count += 1;
+        }
+        Self { sum: sum.0, count }
+    }
+}
+
+///
+/// Merges the partial hashes of every shard of a collection. The result is the digest of the
+/// whole collection, i.e. the value which [`crate::hash_by_summing_hashes_with`] would write
+/// when hashing all the elements together.
+///
+pub fn merge_partial_hashes(partials: &[PartialHash]) -> u64 {
+    partials
+        .iter()
+        .map(|partial| Wrapping(partial.sum))
+        .sum::<Wrapping<u64>>()
+        .0
+}
+
+///
+/// Appends a compact frame of a collection's [`PartialHash`] to `out`: the number of elements,
+/// as an unsigned LEB128 varint, followed by the sum of the element hashes, as 8 little-endian
+/// bytes. Each element is hashed by a new hasher from `BH`. A collection of fewer than 128
+/// elements takes 9 bytes.
+///
+/// Frames can be concatenated, and are read back with [`read_varint_frame`].
+///
+pub fn write_varint_frame<C, BH>(collection: &C, out: &mut Vec<u8>)
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash,
+    BH: BuildHasherFromFriend<C>,
+{
+    let partial = PartialHash::of_with::<C, BH>(collection);
+    let mut count = partial.count as u64;
+    loop {
+        let byte = (count & 0x7f) as u8;
+        count >>= 7;
+        if count == 0 {
+            out.push(byte);
+            break;
+        }
+        out.push(byte | 0x80);
+    }
+    out.extend_from_slice(&partial.sum.to_le_bytes());
+}
+
+///
+/// Reads a frame written by [`write_varint_frame`] from the start of `frame`, returning the
+/// partial hash and the number of bytes read. Returns `None` if the frame is truncated, or if
+/// its count is malformed or does not fit in a `usize`.
+///
+pub fn read_varint_frame(frame: &[u8]) -> Option<(PartialHash, usize)> {
+    let mut count = 0_u64;
+    let mut read = 0;
+    loop {
+        let byte = *frame.get(read)?;
+        let bits = u64::from(byte & 0x7f);
+        // A u64 has 64 bits, so the tenth byte may only hold the highest one, and is the last
+        if read == 9 && byte > 1 {
+            return None;
+        }
+        count |= bits << (7 * read);
+        read += 1;
+        if byte & 0x80 == 0 {
+            break;
+        }
+    }
+    let sum = frame.get(read..read + 8)?;
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(sum);
+    let partial = PartialHash {
+        sum: u64::from_le_bytes(bytes),
+        count: usize::try_from(count).ok()?,
+    };
+    Some((partial, read + 8))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SumHashesAnyCollection;
+
+    #[test]
+    fn merged_shards_equal_whole() {
+        let elements: Vec<u32> = (0..1000).map(|n| n * 7).collect();
+        let whole = SumHashesAnyCollection::<_, UseDefaultHasher>::new(elements.clone());
+
+        for shard_size in [1, 3, 100, 999, 1000] {
+            let partials: Vec<PartialHash> = elements
+                .chunks(shard_size)
+                .map(|shard| PartialHash::of::<Vec<u32>>(&shard.to_vec()))
+                .collect();
+            assert_eq!(
+                elements.len(),
+                partials.iter().map(|partial| partial.count).sum::<usize>()
+            );
+            assert_eq!(whole.raw_unordered_hash(), merge_partial_hashes(&partials));
+        }
+        assert_eq!(0, merge_partial_hashes(&[]));
+    }
+
+    #[test]
+    fn varint_frames_round_trip() {
+        let small: Vec<u32> = (0..5).collect();
+        let large: Vec<u32> = (0..300).collect();
+        let mut frames = Vec::new();
+        write_varint_frame::<Vec<u32>, UseDefaultHasher>(&small, &mut frames);
+        assert_eq!(9, frames.len());
+        assert_eq!(5, frames[0]);
+        write_varint_frame::<Vec<u32>, UseDefaultHasher>(&large, &mut frames);
+        // 300 is 0b10_0101100, written as 0xac 0x02
+        assert_eq!([0xac, 0x02], frames[9..11]);
+
+        let (first, read) = read_varint_frame(&frames).unwrap();
+        assert_eq!(PartialHash::of::<Vec<u32>>(&small), first);
+        assert_eq!(
+            SumHashesAnyCollection::<_>::new(small).raw_unordered_hash(),
+            first.sum
+        );
+        let (second, rest) = read_varint_frame(&frames[read..]).unwrap();
+        assert_eq!(PartialHash::of::<Vec<u32>>(&large), second);
+        assert_eq!(300, second.count);
+        assert_eq!(frames.len(), read + rest);
+    }
+
+    #[test]
+    fn malformed_varint_frames() {
+        let mut frame = Vec::new();
+        write_varint_frame::<Vec<u8>, UseDefaultHasher>(&vec![], &mut frame);
+        assert_eq!(Some((PartialHash::default(), 9)), read_varint_frame(&frame));
+        assert_eq!(None, read_varint_frame(&frame[..8]));
+        assert_eq!(None, read_varint_frame(&[]));
+        assert_eq!(None, read_varint_frame(&[0x80; 20]));
+        let mut overflowing = vec![0xff; 9];
+        overflowing.push(0x02);
+        overflowing.extend_from_slice(&[0; 8]);
+        assert_eq!(None, read_varint_frame(&overflowing));
+    }
+}
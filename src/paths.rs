@@ -0,0 +1,276 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Hashing of paths in a portable form, which is the same on every platform
+
+use crate::{sum_of_hashes, BuildHasherFromFriend, UseDefaultHasher};
+use std::ffi::OsStr;
+use std::hash::{Hash, Hasher};
+use std::path::{Component, Path};
+
+///
+/// Makes a path hash and compare by its portable form, as returned by [`PortablePath::normalized`].
+/// The path may be any type implementing `AsRef<Path>`, such as `PathBuf` or `&Path`.
+///
+/// The portable form is a string, in which:
+/// * components are separated by `/`, including on Windows
+/// * a root is written as a leading `/`, and a Windows prefix such as `C:` is written first,
+///   with any `\` in it written as `/`
+/// * repeated separators, trailing separators, and `.` components other than a leading one are
+///   removed, as by [`Path::components`]
+/// * a `\` within a component is escaped as `\\`
+/// * data which is not valid Unicode is escaped: on Unix, each invalid byte as `\xNN`, and on
+///   Windows, each unpaired surrogate as `\u{NNNN}`, both in lowercase hexadecimal. On other
+///   platforms, invalid data is replaced by U+FFFD.
+///
+/// No other normalization is performed. In particular, `..` components are kept as they are,
+/// letter case is preserved, and symbolic links are not resolved.
+///
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PortablePath<P>(pub P);
+
+impl<P: AsRef<Path>> PortablePath<P> {
+    /// Returns the portable form of the path
+    pub fn normalized(&self) -> String {
+        let mut portable = String::new();
+        let mut separate = false;
+        for component in self.0.as_ref().components() {
+            if separate {
+                portable.push('/');
+            }
+            separate = true;
+            match component {
+                Component::Prefix(prefix) => {
+                    let prefix = prefix.as_os_str().to_string_lossy();
+                    portable.push_str(&prefix.replace('\\', "/"));
+                    separate = false;
+                }
+                Component::RootDir => {
+                    portable.push('/');
+                    separate = false;
+                }
+                Component::CurDir => portable.push('.'),
+                Component::ParentDir => portable.push_str(".."),
+                Component::Normal(name) => escape_into(name, &mut portable),
+            }
+        }
+        portable
+    }
+}
+
+impl<P: AsRef<Path>> PartialEq for PortablePath<P> {
+    fn eq(&self, other: &Self) -> bool {
+        self.normalized() == other.normalized()
+    }
+}
+
+impl<P: AsRef<Path>> Eq for PortablePath<P> {}
+
+impl<P: AsRef<Path>> Hash for PortablePath<P> {
+    /// Hashes the portable form like a `str`
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.normalized().as_str().hash(state)
+    }
+}
+
+fn push_escaped(text: &str, output: &mut String) {
+    for character in text.chars() {
+        if character == '\\' {
+            output.push_str("\\\\");
+        } else {
+            output.push(character);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn escape_into(name: &OsStr, output: &mut String) {
+    use std::fmt::Write;
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut bytes = name.as_bytes();
+    while !bytes.is_empty() {
+        match std::str::from_utf8(bytes) {
+            Ok(valid) => {
+                push_escaped(valid, output);
+                break;
+            }
+            Err(error) => {
+                let (valid, rest) = bytes.split_at(error.valid_up_to());
+                if let Ok(valid) = std::str::from_utf8(valid) {
+                    push_escaped(valid, output);
+                }
+                let invalid = error.error_len().unwrap_or(rest.len());
+                for byte in &rest[..invalid] {
+                    let _ = write!(output, "\\x{:02x}", byte);
+                }
+                bytes = &rest[invalid..];
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+fn escape_into(name: &OsStr, output: &mut String) {
+    use std::fmt::Write;
+    use std::os::windows::ffi::OsStrExt;
+
+    for decoded in char::decode_utf16(name.encode_wide()) {
+        match decoded {
+            Ok(character) => push_escaped(character.encode_utf8(&mut [0; 4]), output),
+            Err(error) => {
+                let _ = write!(output, "\\u{{{:04x}}}", error.unpaired_surrogate());
+            }
+        }
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn escape_into(name: &OsStr, output: &mut String) {
+    push_escaped(&name.to_string_lossy(), output)
+}
+
+///
+/// Implements hashing by summing the hashes of each path's portable form, as described by
+/// [`PortablePath`]. A new [`std::collections::hash_map::DefaultHasher`] is created for each
+/// path. The result is identical to hashing the portable forms as strings.
+///
+/// The portable forms are the same on every platform, but `DefaultHasher` is not guaranteed to
+/// be stable across Rust releases. For digests which are reproducible everywhere, use
+/// [`hash_paths_by_summing_with`] with [`crate::UseStableHasher`], and finish with a
+/// [`crate::StableHasher`].
+///
+pub fn hash_paths_by_summing<C, H>(collection: &C, state: &mut H)
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: AsRef<Path>,
+    H: Hasher,
+{
+    hash_paths_by_summing_with::<C, H, UseDefaultHasher>(collection, state)
+}
+
+///
+/// Implements hashing by summing the hashes of each path's portable form, with a means of
+/// specifying which kind of hasher is created per path via the `BH` parameter.
+///
+pub fn hash_paths_by_summing_with<C, H, BH>(collection: &C, state: &mut H)
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: AsRef<Path>,
+    BH: BuildHasherFromFriend<C>,
+    H: Hasher,
+{
+    let paths = collection.into_iter().map(PortablePath);
+    state.write_u64(sum_of_hashes(paths, || BH::build_hasher_from(collection)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{StableHasher, UseStableHasher};
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::HashSet;
+    use std::path::PathBuf;
+
+    fn normalized(path: &str) -> String {
+        PortablePath(path).normalized()
+    }
+
+    #[test]
+    fn performed_normalizations() {
+        assert_eq!("a/b", normalized("a/b/"));
+        assert_eq!("a/b", normalized("a//b"));
+        assert_eq!("a/b", normalized("a/./b"));
+        assert_eq!("/a/b", normalized("/a/b"));
+        assert_eq!("/", normalized("/"));
+        assert_eq!("./a", normalized("./a"));
+        assert_eq!("", normalized(""));
+        assert_eq!(PortablePath("x/y/"), PortablePath("x/./y"));
+    }
+
+    #[test]
+    fn omitted_normalizations() {
+        assert_eq!("a/../b", normalized("a/../b"));
+        assert_eq!("A/b", normalized("A/b"));
+        assert_ne!(PortablePath("a/../b"), PortablePath("b"));
+        assert_ne!(PortablePath("/a"), PortablePath("a"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn escapes_on_unix() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        assert_eq!("a\\\\b", normalized("a\\b"));
+        let invalid = Path::new(OsStr::from_bytes(b"dir/caf\xe9\xff.txt"));
+        assert_eq!("dir/caf\\xe9\\xff.txt", PortablePath(invalid).normalized());
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn separators_on_windows() {
+        assert_eq!("a/b", normalized("a\\b\\"));
+        assert_eq!("C:/Users", normalized("C:\\Users"));
+    }
+
+    // Digests which must be the same on every platform
+    const FIXTURE_DIGEST: u64 = 0x4282_a220_91d3_4642;
+    const SINGLE_DIGEST: u64 = 0x9c80_9792_10da_c460;
+
+    fn stable_digest<C>(collection: &C) -> u64
+    where
+        for<'c> &'c C: IntoIterator,
+        for<'c> <&'c C as IntoIterator>::Item: AsRef<Path>,
+    {
+        let mut hasher = StableHasher::new();
+        hash_paths_by_summing_with::<C, _, UseStableHasher>(collection, &mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn fixture_digests() {
+        let paths: HashSet<PathBuf> = ["/srv/data/", "/srv/./logs", "relative/../file.txt"]
+            .into_iter()
+            .map(PathBuf::from)
+            .collect();
+        assert_eq!(FIXTURE_DIGEST, stable_digest::<HashSet<PathBuf>>(&paths));
+        assert_eq!(
+            SINGLE_DIGEST,
+            stable_digest::<[&str; 1]>(&["docs/readme.md"])
+        );
+
+        let strings = vec!["/srv/data", "/srv/logs", "relative/../file.txt"];
+        let mut as_strings = StableHasher::new();
+        crate::hash_by_summing_hashes_with::<Vec<&str>, _, UseStableHasher>(
+            &strings,
+            &mut as_strings,
+        );
+        assert_eq!(FIXTURE_DIGEST, as_strings.finish());
+    }
+
+    #[test]
+    fn default_hasher_matches_strings() {
+        let paths = vec![PathBuf::from("/srv/data/"), PathBuf::from("/srv/./logs")];
+        let mut hasher = DefaultHasher::new();
+        hash_paths_by_summing::<Vec<PathBuf>, _>(&paths, &mut hasher);
+
+        let strings = vec!["/srv/logs", "/srv/data"];
+        let mut as_strings = DefaultHasher::new();
+        crate::hash_by_summing_hashes::<Vec<&str>, _>(&strings, &mut as_strings);
+        assert_eq!(as_strings.finish(), hasher.finish());
+    }
+}
@@ -0,0 +1,188 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Element hashes which are computed once and recombined later
+
+use crate::{hash_of, HashCombiner, SumCombiner};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{BuildHasher, BuildHasherDefault, Hash};
+
+///
+/// Elements stored alongside their hashes.
+///
+/// The digest can be recombined without rehashing, for example after filtering the elements,
+/// or under each of several [`HashCombiner`]s. Order of the elements is irrelevant.
+///
+/// The digests of [`Self::recombine_with`] depend on how the elements were hashed. With
+/// [`Self::new`], which uses a new [`DefaultHasher`] per element, recombining with
+/// [`crate::SumCombiner`] gives the digest of [`crate::hash_by_summing_hashes`].
+///
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PrecomputedHashes<T> {
+    elements: Vec<(T, u64)>,
+}
+
+impl<T: Hash> PrecomputedHashes<T> {
+    /// Hashes each element with a new [`DefaultHasher`]
+    pub fn new<I>(values: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+    {
+        Self::new_with(values, &BuildHasherDefault::<DefaultHasher>::default())
+    }
+
+    /// Hashes each element with a new hasher from `build_hasher`
+    pub fn new_with<I, S>(values: I, build_hasher: &S) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        S: BuildHasher,
+    {
+        let elements = values
+            .into_iter()
+            .map(|value| {
+                let hash = hash_of(&value, build_hasher.build_hasher());
+                (value, hash)
+            })
+            .collect();
+        Self { elements }
+    }
+}
+
+impl<T> PrecomputedHashes<T> {
+    /// Combines the stored hashes with the given combiner
+    pub fn recombine_with<Comb: HashCombiner>(&self) -> u64 {
+        Comb::combine(self.hashes())
+    }
+
+    /// Retains only the elements for which the predicate holds, along with their hashes
+    pub fn retain<F>(&mut self, mut predicate: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.elements.retain(|(value, _)| predicate(value));
+    }
+
+    /// Iterates over the elements and their hashes
+    pub fn iter(&self) -> impl Iterator<Item = (&T, u64)> {
+        self.elements.iter().map(|(value, hash)| (value, *hash))
+    }
+
+    /// Iterates over the stored hashes
+    pub fn hashes(&self) -> impl Iterator<Item = u64> + '_ {
+        self.elements.iter().map(|(_, hash)| *hash)
+    }
+
+    /// The number of elements
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    /// Whether there are no elements
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    /// Discards the hashes, returning the elements
+    pub fn into_elements(self) -> Vec<T> {
+        self.elements.into_iter().map(|(value, _)| value).collect()
+    }
+}
+
+impl<T: Hash> FromIterator<T> for PrecomputedHashes<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::new(iter)
+    }
+}
+
+///
+/// Combines precomputed element hashes into the digest of their collection, i.e. their wrapping
+/// sum. If each hash was computed by a new [`DefaultHasher`], this is the value which
+/// [`crate::hash_by_summing_hashes`] writes. Implementations in other languages produce
+/// compatible digests by summing their element hashes the same way, modulo `2^64`.
+///
+pub fn combine_precomputed_hashes(hashes: &[u64]) -> u64 {
+    SumCombiner::combine(hashes.iter().copied())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dual_combine, SortedCombiner, SumCombiner, SumHashesAnyCollection, UseDefaultHasher,
+        XorCombiner,
+    };
+    use std::hash::Hasher;
+
+    fn fresh_hashes(values: &[&str]) -> Vec<u64> {
+        values
+            .iter()
+            .map(|value| {
+                let mut hasher = DefaultHasher::new();
+                value.hash(&mut hasher);
+                hasher.finish()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn recombining_matches_hashing_from_scratch() {
+        let values = vec!["north", "east", "south", "west"];
+        let precomputed: PrecomputedHashes<&str> = values.iter().copied().collect();
+        let (sum, xor) = dual_combine::<Vec<&str>, UseDefaultHasher>(&values);
+
+        assert_eq!(sum, precomputed.recombine_with::<SumCombiner>());
+        assert_eq!(
+            SumHashesAnyCollection::<_>::new(values.clone()).raw_unordered_hash(),
+            precomputed.recombine_with::<SumCombiner>()
+        );
+        assert_eq!(xor, precomputed.recombine_with::<XorCombiner>());
+        assert_eq!(
+            SortedCombiner::combine(fresh_hashes(&values)),
+            precomputed.recombine_with::<SortedCombiner>()
+        );
+    }
+
+    #[test]
+    fn recombining_after_filtering() {
+        let mut precomputed = PrecomputedHashes::new(vec!["kept", "dropped", "also kept"]);
+        precomputed.retain(|value| value.contains("kept"));
+        let filtered = vec!["also kept", "kept"];
+
+        assert_eq!(2, precomputed.len());
+        assert_eq!(
+            SumCombiner::combine(fresh_hashes(&filtered)),
+            precomputed.recombine_with::<SumCombiner>()
+        );
+        assert_eq!(
+            SortedCombiner::combine(fresh_hashes(&filtered)),
+            precomputed.recombine_with::<SortedCombiner>()
+        );
+        assert_eq!(vec!["kept", "also kept"], precomputed.into_elements());
+    }
+
+    #[test]
+    fn combined_hashes_are_the_digest() {
+        let values = vec!["alpha", "beta", "gamma"];
+        let precomputed = PrecomputedHashes::new(values.iter().copied());
+        let hashes: Vec<u64> = precomputed.hashes().collect();
+        assert_eq!(
+            SumHashesAnyCollection::<_, UseDefaultHasher>::new(values).raw_unordered_hash(),
+            combine_precomputed_hashes(&hashes)
+        );
+        assert_eq!(0, combine_precomputed_hashes(&[]));
+        assert_eq!(1, combine_precomputed_hashes(&[u64::MAX, 2]));
+    }
+}
@@ -0,0 +1,238 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Elements which carry their own precomputed hash
+
+use crate::{hash_of, BuildHasherFromFriend, UseDefaultHasher};
+use std::collections::hash_map::DefaultHasher;
+use std::fmt::{self, Debug, Formatter};
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::ops::Deref;
+
+///
+/// An element stored alongside its hash, which is computed once, at construction, by a new
+/// hasher from `BH`. Hashing writes only the stored hash, so a collection of expensive
+/// elements, such as long strings, can be digested repeatedly without rehashing them. With the
+/// [`UsePrehashed`] strategy, the stored hashes are summed directly, and the digest equals that
+/// of the raw elements under `BH`.
+///
+/// Equality compares the stored hashes first, then the elements. `Deref` and `AsRef` give
+/// access to the element. `Borrow<T>` is deliberately not implemented: the hash of a
+/// `Prehashed<T>` is not that of the `T`, so looking up a `T` in a `HashSet<Prehashed<T>>`
+/// would not find it.
+///
+pub struct Prehashed<T, BH = UseDefaultHasher> {
+    hash: u64,
+    value: T,
+    strategy: PhantomData<BH>,
+}
+
+impl<T: Hash> Prehashed<T> {
+    /// Hashes the element with a new [`DefaultHasher`]
+    pub fn new(value: T) -> Self {
+        Self::with_strategy(value)
+    }
+}
+
+impl<T: Hash, BH: BuildHasherFromFriend<T>> Prehashed<T, BH> {
+    /// Hashes the element with a new hasher from `BH`
+    pub fn with_strategy(value: T) -> Self {
+        Self {
+            hash: hash_of(&value, BH::build_hasher_from(&value)),
+            value,
+            strategy: PhantomData,
+        }
+    }
+}
+
+impl<T, BH> Prehashed<T, BH> {
+    /// The stored hash of the element
+    #[inline]
+    pub fn stored_hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Destructures into the element
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+// The traits are implemented manually, because derived implementations would require the
+// same traits of the strategy
+
+impl<T: Clone, BH> Clone for Prehashed<T, BH> {
+    fn clone(&self) -> Self {
+        Self {
+            hash: self.hash,
+            value: self.value.clone(),
+            strategy: PhantomData,
+        }
+    }
+}
+
+impl<T: Debug, BH> Debug for Prehashed<T, BH> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Prehashed")
+            .field("hash", &self.hash)
+            .field("value", &self.value)
+            .finish()
+    }
+}
+
+impl<T: PartialEq, BH> PartialEq for Prehashed<T, BH> {
+    fn eq(&self, other: &Self) -> bool {
+        self.hash == other.hash && self.value == other.value
+    }
+}
+
+impl<T: Eq, BH> Eq for Prehashed<T, BH> {}
+
+impl<T, BH> Hash for Prehashed<T, BH> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash);
+    }
+}
+
+impl<T, BH> Deref for Prehashed<T, BH> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<T, BH> AsRef<T> for Prehashed<T, BH> {
+    fn as_ref(&self) -> &T {
+        &self.value
+    }
+}
+
+///
+/// Implementation of [`BuildHasherFromFriend`] which takes the hash of each [`Prehashed`]
+/// element as it is, without running a hash function. The digest of a collection of
+/// `Prehashed<T, BH>` is then the sum of the stored hashes.
+///
+/// The hasher passes through the first `u64` written to it. Anything else written, such as
+/// the value in a `(Prehashed<K>, V)` map entry, is hashed by a [`DefaultHasher`] and mixed in.
+///
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct UsePrehashed(());
+
+impl<F> BuildHasherFromFriend<F> for UsePrehashed {
+    type Hasher = PrehashedHasher;
+
+    fn build_hasher_from<'f>(_: &'f F) -> Self::Hasher
+    where
+        Self::Hasher: 'f,
+    {
+        PrehashedHasher::default()
+    }
+}
+
+/// The hasher of [`UsePrehashed`]
+#[derive(Clone, Debug, Default)]
+pub struct PrehashedHasher {
+    passed: Option<u64>,
+    rest: Option<DefaultHasher>,
+}
+
+impl PrehashedHasher {
+    fn rest(&mut self) -> &mut DefaultHasher {
+        self.rest.get_or_insert_with(DefaultHasher::new)
+    }
+}
+
+impl Hasher for PrehashedHasher {
+    fn finish(&self) -> u64 {
+        let passed = self.passed.unwrap_or(0);
+        match &self.rest {
+            Some(rest) => passed ^ rest.finish(),
+            None => passed,
+        }
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.rest().write(bytes);
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        if self.passed.is_none() && self.rest.is_none() {
+            self.passed = Some(i);
+        } else {
+            self.rest().write_u64(i);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hash_by_summing_hashes_with, UseStableHasher};
+    use std::collections::HashSet;
+
+    fn digest<C, BH>(collection: &C) -> u64
+    where
+        for<'c> &'c C: IntoIterator,
+        for<'c> <&'c C as IntoIterator>::Item: Hash,
+        BH: BuildHasherFromFriend<C>,
+    {
+        let mut hasher = DefaultHasher::new();
+        hash_by_summing_hashes_with::<C, _, BH>(collection, &mut hasher);
+        hasher.finish()
+    }
+
+    fn long_strings() -> HashSet<String> {
+        (0..20).map(|index| format!("{:x>2000}", index)).collect()
+    }
+
+    #[test]
+    fn digest_matches_raw_elements() {
+        let raw = long_strings();
+        let prehashed: HashSet<Prehashed<String>> =
+            raw.iter().cloned().map(Prehashed::new).collect();
+        assert_eq!(
+            digest::<HashSet<String>, UseDefaultHasher>(&raw),
+            digest::<HashSet<Prehashed<String>>, UsePrehashed>(&prehashed)
+        );
+
+        let stable: Vec<Prehashed<String, UseStableHasher>> =
+            raw.iter().cloned().map(Prehashed::with_strategy).collect();
+        assert_eq!(
+            digest::<HashSet<String>, UseStableHasher>(&raw),
+            digest::<Vec<Prehashed<String, UseStableHasher>>, UsePrehashed>(&stable)
+        );
+    }
+
+    #[test]
+    fn equality_and_access() {
+        let first = Prehashed::new(String::from("value"));
+        let second = Prehashed::new(String::from("value"));
+        assert_eq!(first, second);
+        assert_ne!(first, Prehashed::new(String::from("other")));
+        assert_eq!(5, first.len());
+        assert_eq!("value", first.clone().into_inner());
+
+        // Anything written besides the stored hash is still hashed
+        let entries = |value: u8| vec![(Prehashed::new("key"), value)];
+        assert_ne!(
+            digest::<Vec<(Prehashed<&str>, u8)>, UsePrehashed>(&entries(1)),
+            digest::<Vec<(Prehashed<&str>, u8)>, UsePrehashed>(&entries(2))
+        );
+    }
+}
@@ -0,0 +1,229 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Compatibility with the hashes of Python's `frozenset`
+
+use std::hash::Hasher;
+
+/// The modulus of Python's hashes of numbers on 64-bit builds, `2**61 - 1`
+const MODULUS: u64 = (1 << 61) - 1;
+
+///
+/// Types whose hash in Python can be computed in Rust, i.e. the value of Python's `hash` for
+/// the equivalent Python object. For integers, this is the reduction modulo `2**61 - 1` which
+/// CPython uses on 64-bit platforms, keeping the sign, except that `-1` becomes `-2`. A `bool`
+/// hashes like the integers `0` and `1`, as in Python.
+///
+/// Python's hashes of `str` and `bytes` are randomized per process, unless `PYTHONHASHSEED` is
+/// set, and are not implemented. For other objects, compute the hash in Python and wrap it in
+/// [`PythonHashValue`].
+///
+pub trait PythonHash {
+    /// The value of `hash(x)` in Python, for the equivalent object `x`
+    fn python_hash(&self) -> i64;
+}
+
+/// A hash computed by Python, such as the result of `hash(x)` for an arbitrary object `x`
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct PythonHashValue(pub i64);
+
+impl PythonHash for PythonHashValue {
+    fn python_hash(&self) -> i64 {
+        self.0
+    }
+}
+
+impl<T: PythonHash + ?Sized> PythonHash for &T {
+    fn python_hash(&self) -> i64 {
+        (**self).python_hash()
+    }
+}
+
+/// Computes the hash of a Python `int` from its sign and magnitude
+fn hash_int(negative: bool, magnitude: u128) -> i64 {
+    // The result is less than 2**61, so it fits
+    let reduced = (magnitude % u128::from(MODULUS)) as i64;
+    if !negative {
+        reduced
+    } else if reduced == 1 {
+        -2
+    } else {
+        -reduced
+    }
+}
+
+macro_rules! python_hash_unsigned {
+    ($($int:ty),*) => {
+        $(
+            impl PythonHash for $int {
+                fn python_hash(&self) -> i64 {
+                    hash_int(false, *self as u128)
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! python_hash_signed {
+    ($($int:ty),*) => {
+        $(
+            impl PythonHash for $int {
+                fn python_hash(&self) -> i64 {
+                    hash_int(*self < 0, self.unsigned_abs() as u128)
+                }
+            }
+        )*
+    };
+}
+
+python_hash_unsigned!(u8, u16, u32, u64, u128, usize);
+python_hash_signed!(i8, i16, i32, i64, i128, isize);
+
+impl PythonHash for bool {
+    fn python_hash(&self) -> i64 {
+        i64::from(*self)
+    }
+}
+
+///
+/// Computes the hash which Python gives a `frozenset` of the elements, on 64-bit builds of
+/// CPython 3.8 or later. The elements must be distinct, as in a set; note that in Python, `1`
+/// and `True` are the same element. The result can itself be wrapped in a [`PythonHashValue`]
+/// to hash nested frozensets.
+///
+/// # Algorithm
+///
+/// This reproduces `frozenset_hash` in CPython's `Objects/setobject.c`, with wrapping 64-bit
+/// arithmetic throughout:
+///
+/// 1. Each element hash `h` is shuffled as `((h ^ 89869747) ^ (h << 16)) * 3644798167`, and the
+///    shuffled hashes are combined by XOR.
+/// 2. The number of elements plus one, multiplied by `1927868237`, is XORed in.
+/// 3. The result `x` is dispersed as `x ^= (x >> 11) ^ (x >> 25)`, using logical shifts, then
+///    `x = x * 69069 + 907133923`.
+/// 4. If `x` is `-1`, which Python reserves for errors, it becomes `590923713`.
+///
+/// CPython also iterates over the empty and deleted slots of its hash table, but cancels their
+/// contributions, so they do not affect the result.
+///
+pub fn python_frozenset_digest<C>(collection: &C) -> i64
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: PythonHash,
+{
+    fn shuffle_bits(hash: u64) -> u64 {
+        ((hash ^ 89_869_747) ^ (hash << 16)).wrapping_mul(3_644_798_167)
+    }
+    let mut hash = 0_u64;
+    let mut count = 0_u64;
+    for value in collection {
+        hash ^= shuffle_bits(value.python_hash() as u64);
+        count += 1;
+    }
+    hash ^= count.wrapping_add(1).wrapping_mul(1_927_868_237);
+    hash ^= (hash >> 11) ^ (hash >> 25);
+    hash = hash.wrapping_mul(69_069).wrapping_add(907_133_923);
+    if hash == u64::MAX {
+        hash = 590_923_713;
+    }
+    hash as i64
+}
+
+///
+/// Implements hashing by writing the hash which Python gives a `frozenset` of the elements, as
+/// computed by [`python_frozenset_digest`], to `state` as an `i64`.
+///
+pub fn python_frozenset_hash<C, H>(collection: &C, state: &mut H)
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: PythonHash,
+    H: Hasher,
+{
+    state.write_i64(python_frozenset_digest(collection));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{BTreeSet, HashSet};
+
+    // Expected values are from `hash(frozenset(...))` in CPython 3.11 on x86_64
+
+    #[test]
+    fn integer_elements() {
+        assert_eq!(
+            133_146_708_735_736,
+            python_frozenset_digest::<Vec<i64>>(&vec![])
+        );
+        let set: HashSet<i32> = [1, 2, 3].into_iter().collect();
+        assert_eq!(
+            -272_375_401_224_217_160,
+            python_frozenset_digest::<HashSet<i32>>(&set)
+        );
+        assert_eq!(
+            8_868_930_259_606_097_796,
+            python_frozenset_digest::<Vec<i8>>(&vec![1, 0, -1])
+        );
+        assert_eq!(
+            4_411_996_150_312_757_951,
+            python_frozenset_digest::<BTreeSet<u8>>(&(0..100).collect())
+        );
+    }
+
+    #[test]
+    fn integer_edge_cases() {
+        assert_eq!(-2, (-1_i64).python_hash());
+        assert_eq!(0, (-(MODULUS as i64)).python_hash());
+        assert_eq!(-4, i64::MIN.python_hash());
+        // {2**61 - 1, 2**61, 2**63 - 1, -2**63}
+        let extremes = vec![MODULUS as i64, 1 << 61, i64::MAX, i64::MIN];
+        assert_eq!(
+            -2_584_664_141_258_355_493,
+            python_frozenset_digest::<Vec<i64>>(&extremes)
+        );
+        let wide = vec![10_u128.pow(30), 7];
+        assert_eq!(
+            -8_321_617_171_612_316_631,
+            python_frozenset_digest::<Vec<u128>>(&wide)
+        );
+        // hash(-1) == hash(-2) in Python
+        assert_eq!(
+            python_frozenset_digest::<Vec<i32>>(&vec![-2]),
+            python_frozenset_digest::<Vec<i32>>(&vec![-1])
+        );
+        assert_eq!(
+            6_776_983_852_052_281_967,
+            python_frozenset_digest::<Vec<i32>>(&vec![-1])
+        );
+        assert_eq!(
+            -558_064_481_276_695_278,
+            python_frozenset_digest::<Vec<bool>>(&vec![true])
+        );
+    }
+
+    #[test]
+    fn nested_frozensets() {
+        // {frozenset({1, 2}), frozenset()}
+        let inner = [
+            PythonHashValue(python_frozenset_digest::<Vec<u64>>(&vec![1, 2])),
+            PythonHashValue(python_frozenset_digest::<Vec<u64>>(&vec![])),
+        ];
+        assert_eq!(
+            6_574_431_580_149_215_965,
+            python_frozenset_digest::<[PythonHashValue; 2]>(&inner)
+        );
+    }
+}
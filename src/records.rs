@@ -0,0 +1,196 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Hashing of record sets, with fields in a canonical order
+
+use crate::{hash_of, StableHasher};
+use std::hash::{Hash, Hasher};
+use std::num::Wrapping;
+
+///
+/// Describes the fields of a record type, for [`hash_records`]. A record's hash depends on the
+/// names and values of its fields, but not on the order in which the schema writes them, nor on
+/// the order in which the record type declares them.
+///
+pub trait RecordSchema<R: ?Sized> {
+    /// Writes each field of the record, by name, to `fields`
+    fn write_fields<'s>(&'s self, record: &R, fields: &mut RecordFields<'s>);
+}
+
+///
+/// The fields of one record, as written by a [`RecordSchema`]
+///
+#[derive(Debug)]
+pub struct RecordFields<'s> {
+    fields: Vec<(&'s str, u64)>,
+}
+
+impl<'s> RecordFields<'s> {
+    /// Adds a field, which is hashed by a new [`StableHasher`]
+    pub fn field<T: Hash + ?Sized>(&mut self, name: &'s str, value: &T) {
+        self.fields
+            .push((name, hash_of(value, StableHasher::new())));
+    }
+}
+
+///
+/// Hashes a set of records, combining the records order-independently, by summing their
+/// hashes. Each record is hashed from the fields written by `schema`, in the canonical order of
+/// their names.
+///
+/// The hash of a record is computed by a [`StableHasher`]. Each field's value is first hashed
+/// by its own `StableHasher`. The fields are then sorted by name, as bytes, and their names and
+/// value hashes are written in that order, as a `str` and a `u64`. Since the `StableHasher` is
+/// frozen and portable, implementations in other languages can reproduce the digest, given the
+/// same encoding of field values.
+///
+pub fn hash_records<C, R, H, S>(collection: &C, state: &mut H, schema: S)
+where
+    for<'c> &'c C: IntoIterator<Item = &'c R>,
+    R: ?Sized,
+    H: Hasher,
+    S: RecordSchema<R>,
+{
+    let mut fields = RecordFields { fields: Vec::new() };
+    let mut sum = Wrapping(0);
+    for record in collection {
+        fields.fields.clear();
+        schema.write_fields(record, &mut fields);
+        fields.fields.sort_unstable();
+
+        let mut hasher = StableHasher::new();
+        for (name, value_hash) in &fields.fields {
+            name.hash(&mut hasher);
+            hasher.write_u64(*value_hash);
+        }
+        sum += hasher.finish();
+    }
+    state.write_u64(sum.0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::HashSet;
+
+    #[derive(Eq, Hash, PartialEq)]
+    struct Person {
+        name: &'static str,
+        age: u32,
+    }
+
+    struct Reordered {
+        age: u32,
+        name: &'static str,
+    }
+
+    struct PersonSchema;
+
+    impl RecordSchema<Person> for PersonSchema {
+        fn write_fields<'s>(&'s self, record: &Person, fields: &mut RecordFields<'s>) {
+            fields.field("name", record.name);
+            fields.field("age", &record.age);
+        }
+    }
+
+    struct ReorderedSchema;
+
+    impl RecordSchema<Reordered> for ReorderedSchema {
+        fn write_fields<'s>(&'s self, record: &Reordered, fields: &mut RecordFields<'s>) {
+            fields.field("age", &record.age);
+            fields.field("name", record.name);
+        }
+    }
+
+    /// Names its fields at runtime
+    struct DynamicSchema(Vec<String>);
+
+    impl RecordSchema<[u32; 2]> for DynamicSchema {
+        fn write_fields<'s>(&'s self, record: &[u32; 2], fields: &mut RecordFields<'s>) {
+            for (name, value) in self.0.iter().zip(record) {
+                fields.field(name, value);
+            }
+        }
+    }
+
+    fn digest<C, R, S>(collection: &C, schema: S) -> u64
+    where
+        for<'c> &'c C: IntoIterator<Item = &'c R>,
+        R: ?Sized,
+        S: RecordSchema<R>,
+    {
+        let mut hasher = DefaultHasher::new();
+        hash_records(collection, &mut hasher, schema);
+        hasher.finish()
+    }
+
+    #[test]
+    fn field_order_is_canonical() {
+        let people: HashSet<Person> = [("ana", 31), ("bo", 45), ("cy", 7)]
+            .into_iter()
+            .map(|(name, age)| Person { name, age })
+            .collect();
+        let reordered: Vec<Reordered> = [("cy", 7), ("ana", 31), ("bo", 45)]
+            .into_iter()
+            .map(|(name, age)| Reordered { age, name })
+            .collect();
+        assert_eq!(
+            digest(&people, PersonSchema),
+            digest(&reordered, ReorderedSchema)
+        );
+
+        let older: Vec<Reordered> = [("cy", 8), ("ana", 31), ("bo", 45)]
+            .into_iter()
+            .map(|(name, age)| Reordered { age, name })
+            .collect();
+        assert_ne!(
+            digest(&people, PersonSchema),
+            digest(&older, ReorderedSchema)
+        );
+    }
+
+    #[test]
+    fn field_names_contribute() {
+        let records = vec![[1, 2], [3, 4]];
+        let schema = |names: [&str; 2]| DynamicSchema(names.map(str::to_owned).to_vec());
+        let base = digest(&records, schema(["x", "y"]));
+        assert_eq!(base, digest(&[[3, 4], [1, 2]], schema(["x", "y"])));
+        assert_ne!(base, digest(&records, schema(["y", "x"])));
+        assert_ne!(base, digest(&records, schema(["x", "z"])));
+    }
+
+    #[test]
+    fn record_hash_is_reproducible() {
+        let people = vec![Person {
+            name: "ana",
+            age: 31,
+        }];
+        let mut expected = StableHasher::new();
+        let mut age = StableHasher::new();
+        31_u32.hash(&mut age);
+        let mut name = StableHasher::new();
+        "ana".hash(&mut name);
+        "age".hash(&mut expected);
+        expected.write_u64(age.finish());
+        "name".hash(&mut expected);
+        expected.write_u64(name.finish());
+
+        let mut hasher = DefaultHasher::new();
+        hasher.write_u64(expected.finish());
+        assert_eq!(hasher.finish(), digest(&people, PersonSchema));
+    }
+}
@@ -0,0 +1,165 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Rehashing with new seeds when element hashes collide
+
+use crate::{hash_of, Seed};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::num::Wrapping;
+
+///
+/// Hashes a collection by summing the hashes of each element, retrying with a new seed if two
+/// unequal elements have the same hash. Returns the digest together with the seed used.
+///
+/// Each element is hashed by a new [`DefaultHasher`], into which the low 64 bits of the seed,
+/// [`Seed::as_u64`], are first written. The first attempt uses a seed of `0`, and every retry
+/// increments it, up to `max_retries` retries.
+/// If every attempt collides, the digest of the final attempt is returned. Equal elements, as
+/// may occur in a multiset, are not treated as collisions.
+///
+/// Digests are only comparable if they were computed with the same seed. To recompute a stored
+/// digest, pass its seed as the first seed of [`hash_robust_with`], with a `max_retries` of `0`
+/// and hashers created the same way.
+///
+pub fn hash_robust<C>(collection: &C, max_retries: usize) -> (u64, Seed)
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash + Eq,
+{
+    hash_robust_with(collection, 0, max_retries, |seed| {
+        let mut hasher = DefaultHasher::new();
+        hasher.write_u64(seed.as_u64());
+        hasher
+    })
+}
+
+///
+/// Hashes a collection like [`hash_robust`], starting from `first_seed` and creating each
+/// element's hasher with `new_hasher`, which is given the seed of the current attempt. Retries
+/// increment all 128 bits of the seed, wrapping around.
+///
+pub fn hash_robust_with<C, F, S>(
+    collection: &C,
+    first_seed: impl Into<Seed>,
+    max_retries: usize,
+    mut new_hasher: F,
+) -> (u64, Seed)
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash + Eq,
+    F: FnMut(Seed) -> S,
+    S: Hasher,
+{
+    let mut seed = first_seed.into();
+    let mut retries = 0;
+    let mut hashed = Vec::new();
+    loop {
+        hashed.clear();
+        hashed.extend(
+            collection
+                .into_iter()
+                .map(|value| (hash_of(&value, new_hasher(seed)), value)),
+        );
+        let digest = hashed
+            .iter()
+            .map(|(hash, _)| Wrapping(*hash))
+            .sum::<Wrapping<u64>>()
+            .0;
+
+        hashed.sort_unstable_by_key(|(hash, _)| *hash);
+        // Within a run of equal hashes, unequal elements are always adjacent somewhere
+        let collides = hashed
+            .windows(2)
+            .any(|pair| pair[0].0 == pair[1].0 && pair[0].1 != pair[1].1);
+        if !collides || retries == max_retries {
+            return (digest, seed);
+        }
+        seed = Seed::from_u128(seed.as_u128().wrapping_add(1));
+        retries += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::MaskedHasher;
+    use std::collections::HashSet;
+
+    fn masked(seed: Seed) -> MaskedHasher<0b1> {
+        let mut hasher = MaskedHasher::default();
+        hasher.write_u64(seed.as_u64());
+        hasher
+    }
+
+    fn hash_masked(value: u32, seed: Seed) -> u64 {
+        let mut hasher = masked(seed);
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn collision_triggers_retry() {
+        // Find a pair of elements which collide with the first seed
+        let other = (1..)
+            .find(|n| hash_masked(0, Seed::default()) == hash_masked(*n, Seed::default()))
+            .unwrap();
+        let set: HashSet<u32> = [0, other].into_iter().collect();
+
+        let (digest, seed) = hash_robust_with::<HashSet<u32>, _, _>(&set, 0, 64, masked);
+        assert_ne!(Seed::default(), seed);
+        assert_ne!(hash_masked(0, seed), hash_masked(other, seed));
+        assert_eq!(hash_masked(0, seed) + hash_masked(other, seed), digest);
+
+        let retried = hash_robust_with::<HashSet<u32>, _, _>(&set, seed, 0, masked);
+        assert_eq!((digest, seed), retried);
+    }
+
+    #[test]
+    fn exhausted_retries_return_last_attempt() {
+        // Three distinct elements always collide in two buckets
+        let set: HashSet<u32> = [1, 2, 3].into_iter().collect();
+        let (digest, seed) = hash_robust_with::<HashSet<u32>, _, _>(&set, 10, 5, masked);
+        assert_eq!(Seed::from(15), seed);
+        let expected: u64 = [1, 2, 3]
+            .iter()
+            .map(|n| hash_masked(*n, Seed::from(15)))
+            .sum();
+        assert_eq!(expected, digest);
+    }
+
+    #[test]
+    fn derived_first_seed() {
+        let set: HashSet<u32> = [4, 5, 6].into_iter().collect();
+        let first = Seed::derive("robust");
+        let (digest, seed) = hash_robust_with::<HashSet<u32>, _, _>(&set, first, 0, masked);
+        assert_eq!(first, seed);
+        let expected: u64 = [4, 5, 6].iter().map(|n| hash_masked(*n, first)).sum();
+        assert_eq!(expected, digest);
+    }
+
+    #[test]
+    fn duplicates_are_not_collisions() {
+        let values = vec!["same", "same", "same"];
+        let (digest, seed) = hash_robust::<Vec<&str>>(&values, 3);
+        assert_eq!(Seed::from(0), seed);
+
+        let mut hasher = DefaultHasher::new();
+        hasher.write_u64(0);
+        "same".hash(&mut hasher);
+        assert_eq!(hasher.finish().wrapping_mul(3), digest);
+    }
+}
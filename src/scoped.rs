@@ -0,0 +1,130 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Multi-threaded hashing with scoped threads from the standard library
+
+use crate::trace::DigestSpan;
+use crate::{hash_by_summing_hashes, hashes_of, UseDefaultHasher};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::num::Wrapping;
+use std::thread;
+
+/// Collections smaller than this are hashed on the calling thread
+const PARALLEL_THRESHOLD: usize = 4096;
+
+///
+/// Implements hashing by summing the hashes of each element, like [`hash_by_summing_hashes`],
+/// with the element hashes computed on up to `num_threads` threads. A new [`DefaultHasher`] is
+/// created for each element, and the partial sums are reduced by wrapping addition, so the
+/// result is identical to [`hash_by_summing_hashes`]'s.
+///
+/// The elements are collected, then split into one contiguous chunk per thread, using
+/// [`std::thread::scope`]. This needs no dependency beyond the standard library; with rayon,
+/// prefer its thread pool. Collections whose iterators report fewer than 4096 elements in
+/// their size hints, and a `num_threads` of 0 or 1, are hashed on the calling thread. The
+/// number of threads is capped by the number of elements and by
+/// [`std::thread::available_parallelism`].
+///
+/// A panic in the `Hash` implementation of an element is propagated to the caller.
+///
+pub fn hash_by_summing_parallel_scoped<C, H>(collection: &C, state: &mut H, num_threads: usize)
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash + Sync,
+    H: Hasher,
+{
+    let (lower_bound, _) = collection.into_iter().size_hint();
+    if num_threads <= 1 || lower_bound < PARALLEL_THRESHOLD {
+        return hash_by_summing_hashes::<C, H>(collection, state);
+    }
+    let elements: Vec<_> = collection.into_iter().collect();
+    let mut span = DigestSpan::enter::<UseDefaultHasher>("summed_parallel_scoped", elements.len());
+    span.add_elements(elements.len());
+    let available = thread::available_parallelism().map_or(1, |threads| threads.get());
+    let threads = num_threads.min(available).min(elements.len()).max(1);
+    let chunk_size = elements.len() / threads + usize::from(elements.len() % threads != 0);
+    let sum = thread::scope(|scope| {
+        let workers: Vec<_> = elements
+            .chunks(chunk_size.max(1))
+            .map(|chunk| {
+                scope.spawn(move || {
+                    hashes_of(chunk, DefaultHasher::new)
+                        .map(Wrapping)
+                        .sum::<Wrapping<u64>>()
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .map(|worker| match worker.join() {
+                Ok(sum) => sum,
+                Err(panic) => std::panic::resume_unwind(panic),
+            })
+            .sum::<Wrapping<u64>>()
+    });
+    state.write_u64(span.finish(sum.0));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn sequential<C>(collection: &C) -> u64
+    where
+        for<'c> &'c C: IntoIterator,
+        for<'c> <&'c C as IntoIterator>::Item: Hash,
+    {
+        let mut hasher = DefaultHasher::new();
+        hash_by_summing_hashes::<C, _>(collection, &mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn matches_sequential_for_any_thread_count() {
+        let set: HashSet<String> = (0..50_000).map(|n| format!("element {}", n)).collect();
+        let expected = sequential::<HashSet<String>>(&set);
+        for num_threads in [0, 1, 2, 8] {
+            let mut hasher = DefaultHasher::new();
+            hash_by_summing_parallel_scoped::<HashSet<String>, _>(&set, &mut hasher, num_threads);
+            assert_eq!(expected, hasher.finish(), "{} threads", num_threads);
+        }
+
+        // More threads than cores, or than elements, still covers every element once
+        let values: Vec<u64> = (0..PARALLEL_THRESHOLD as u64 + 3).collect();
+        for num_threads in [64, usize::MAX] {
+            let mut hasher = DefaultHasher::new();
+            hash_by_summing_parallel_scoped::<Vec<u64>, _>(&values, &mut hasher, num_threads);
+            assert_eq!(sequential::<Vec<u64>>(&values), hasher.finish());
+        }
+    }
+
+    #[test]
+    fn small_collections_are_sequential() {
+        let small = vec!["a", "b", "c"];
+        for num_threads in [1, 2, 8] {
+            let mut hasher = DefaultHasher::new();
+            hash_by_summing_parallel_scoped::<Vec<&str>, _>(&small, &mut hasher, num_threads);
+            assert_eq!(sequential::<Vec<&str>>(&small), hasher.finish());
+        }
+        let mut hasher = DefaultHasher::new();
+        hash_by_summing_parallel_scoped::<Vec<u8>, _>(&vec![], &mut hasher, 8);
+        let mut expected = DefaultHasher::new();
+        expected.write_u64(0);
+        assert_eq!(expected.finish(), hasher.finish());
+    }
+}
@@ -0,0 +1,125 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Seeds for the seeded functions, including seeds derived from names of domains
+
+use crate::StableHasher;
+use std::hash::{Hash, Hasher};
+
+///
+/// A seed for the seeded functions, such as [`crate::lsh_bucket`], which accept any
+/// `Into<Seed>`, including a raw `u64`. Rather than every team choosing a number such as `42`,
+/// derive seeds from names with [`Seed::derive`], so that unrelated uses do not collide.
+///
+/// A seed has 128 bits. The seeded functions use the low 64 bits, [`Seed::as_u64`]; the full
+/// value, [`Seed::as_u128`], is suitable for keying a hasher with a 128-bit key, such as
+/// SipHash. A seed made from a `u64` has that value as its low 64 bits, and zero as its high
+/// bits, so a function given `Seed::from(seed)` behaves as it would given `seed`.
+///
+/// ```rust
+/// use hash_that_set::{fuzzy_hash, Seed};
+///
+/// let seed = Seed::derive("inventory-sync");
+/// let digest = fuzzy_hash::<Vec<u32>>(&vec![1, 2, 3], 0, seed);
+/// ```
+///
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Seed(u128);
+
+impl Seed {
+    ///
+    /// Derives a seed from the name of a domain. The derivation is frozen: a name gives the same
+    /// seed on every platform and in every release.
+    ///
+    /// Each half of the seed is computed by a [`StableHasher`], to which the salt
+    /// `"hash-that-set seed derivation"` is written as a `str`, then the index of the half, `0`
+    /// for the low bits and `1` for the high bits, as a `u8`, then the domain as a `str`.
+    ///
+    pub fn derive(domain: &str) -> Self {
+        let half = |index: u8| {
+            let mut hasher = StableHasher::new();
+            "hash-that-set seed derivation".hash(&mut hasher);
+            hasher.write_u8(index);
+            domain.hash(&mut hasher);
+            hasher.finish()
+        };
+        Self((u128::from(half(1)) << 64) | u128::from(half(0)))
+    }
+
+    /// Creates a seed from all 128 bits
+    pub const fn from_u128(value: u128) -> Self {
+        Self(value)
+    }
+
+    /// The low 64 bits, which the seeded functions use
+    pub const fn as_u64(&self) -> u64 {
+        self.0 as u64
+    }
+
+    /// All 128 bits
+    pub const fn as_u128(&self) -> u128 {
+        self.0
+    }
+}
+
+impl From<u64> for Seed {
+    /// Creates a seed whose low 64 bits are the value
+    fn from(value: u64) -> Self {
+        Self(u128::from(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fuzzy_hash, multi_seed_hashes, UseDefaultHasher};
+
+    #[test]
+    fn derived_seeds_are_frozen() {
+        assert_eq!(
+            0x95ad_78ce_5f91_5917_9a6f_9f25_5bd4_cfdf,
+            Seed::derive("").as_u128()
+        );
+        assert_eq!(
+            0xcbcb_63ee_0812_afb3_d644_2aa8_0119_a8e2,
+            Seed::derive("inventory").as_u128()
+        );
+        assert_eq!(
+            0x39dd_9ae9_1cc8_bc05_1020_e9bd_4b71_4275,
+            Seed::derive("search").as_u128()
+        );
+    }
+
+    #[test]
+    fn domains_give_different_digests() {
+        let values: Vec<u32> = (0..100).collect();
+        let digest = |seed: Seed| fuzzy_hash::<Vec<u32>>(&values, 2, seed);
+        assert_ne!(
+            digest(Seed::derive("inventory")),
+            digest(Seed::derive("search"))
+        );
+        assert_eq!(
+            digest(Seed::derive("inventory")),
+            digest(Seed::derive("inventory"))
+        );
+
+        let raw = multi_seed_hashes::<Vec<u32>, UseDefaultHasher>(&values, &[42_u64]);
+        let wrapped = multi_seed_hashes::<Vec<u32>, UseDefaultHasher>(&values, &[Seed::from(42)]);
+        assert_eq!(raw, wrapped);
+        assert_eq!(42, Seed::from(42).as_u64());
+        assert_eq!(0, Seed::from(u64::MAX).as_u128() >> 64);
+    }
+}
@@ -0,0 +1,540 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Sketches and partial fingerprints of collections, from their element hashes
+
+use crate::mix::splitmix64;
+use crate::{hash_of, hashes_of, BuildHasherFromFriend, Seed, UseDefaultHasher};
+use std::collections::hash_map::DefaultHasher;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::num::Wrapping;
+
+/// Collects the hash of each element, using a new hasher from `BH` per element
+fn element_hashes<C, BH>(collection: &C) -> Vec<u64>
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash,
+    BH: BuildHasherFromFriend<C>,
+{
+    hashes_of(collection, || BH::build_hasher_from(collection)).collect()
+}
+
+///
+/// Computes a partial fingerprint of a collection from at most `n` of its elements. Elements
+/// are chosen by lowest element hash, so the subset is deterministic and independent of
+/// iteration order; the chosen hashes are then summed.
+///
+/// When `n` is at least the size of the collection, the result is the same as the value
+/// written by [`crate::hash_by_summing_hashes_with`].
+///
+pub fn hash_prefix_unordered<C, BH>(collection: &C, n: usize) -> u64
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash,
+    BH: BuildHasherFromFriend<C>,
+{
+    let mut hashes = element_hashes::<C, BH>(collection);
+    if n < hashes.len() {
+        hashes.select_nth_unstable(n);
+        hashes.truncate(n);
+    }
+    hashes.into_iter().map(Wrapping).sum::<Wrapping<u64>>().0
+}
+
+///
+/// Computes locality-sensitive bucket keys of a collection, by MinHash with banding, for
+/// detecting near-duplicate sets. Collections which differ in only a few elements share at
+/// least one bucket key with high probability, whereas dissimilar collections rarely do.
+/// Elements are hashed with a new [`DefaultHasher`] each; duplicates have no effect.
+///
+/// There are `bands * rows` MinHash functions, keyed by `seed`. Each band of `rows` minimum
+/// hashes is hashed into one bucket key, so the result has `bands` keys. Bucket keys are only
+/// meaningful when compared at the same position, between collections sketched with the same
+/// parameters. With Jaccard similarity `s`, two collections share a key with probability
+/// `1 - (1 - s^rows)^bands`: more rows make the test stricter, and more bands make it looser.
+/// A `rows` of zero is treated as one, since a band without rows would put every collection
+/// in the same bucket.
+///
+/// The cost is `bands * rows` passes over the element hashes.
+///
+pub fn lsh_bucket<C>(collection: &C, bands: usize, rows: usize, seed: impl Into<Seed>) -> Vec<u64>
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash,
+{
+    let seed = seed.into().as_u64();
+    let rows = rows.max(1);
+    let hashes = element_hashes::<C, UseDefaultHasher>(collection);
+    let mut function = 0_u64;
+    (0..bands)
+        .map(|band| {
+            let mut bucket = DefaultHasher::new();
+            bucket.write_usize(band);
+            for _ in 0..rows {
+                let key = splitmix64(seed ^ splitmix64(function));
+                function = function.wrapping_add(1);
+                let min_hash = hashes
+                    .iter()
+                    .map(|hash| splitmix64(hash ^ key))
+                    .min()
+                    .unwrap_or(u64::MAX);
+                bucket.write_u64(min_hash);
+            }
+            bucket.finish()
+        })
+        .collect()
+}
+
+///
+/// Computes a deliberately fuzzy digest of a collection, which is, with high probability, the
+/// same for collections which differ in at most `tolerance` elements, counting each element
+/// present in only one of them, for caching results of
+/// near-duplicate inputs. Elements are hashed with a new [`DefaultHasher`] each, and keyed by
+/// `seed` as in [`lsh_bucket`].
+///
+/// This is MinHash by threshold sampling: each element is sampled if its keyed hash is among
+/// the lowest `1 / (1 + 4 * tolerance)` of the hash space, and the keyed hashes of the sampled
+/// elements are summed. Two collections hash equally if none of the elements in which they
+/// differ is sampled. Each element is sampled independently, so the result does not depend on
+/// the size of the collections:
+///
+/// * Within `tolerance` differences, the digests are equal with probability at least about
+///   `e^(-1/4)`, or 78%, and always if the collections are equal
+/// * With `d` differences, they are equal with probability about `e^(-d / (4 * tolerance))`,
+///   e.g. 8% for ten times the tolerance
+///
+/// **The result is probabilistic**: any two collections may collide, and near duplicates may
+/// not. For the digest to discriminate at all, a collection needs many more than
+/// `1 + 4 * tolerance` elements, since about that many elements contribute one sample. A
+/// `tolerance` of zero samples every element, giving an exact, order-independent digest.
+///
+pub fn fuzzy_hash<C>(collection: &C, tolerance: usize, seed: impl Into<Seed>) -> u64
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash,
+{
+    let rate = (tolerance as u64).saturating_mul(4).saturating_add(1);
+    let threshold = u64::MAX / rate;
+    let key = splitmix64(seed.into().as_u64());
+    element_hashes::<C, UseDefaultHasher>(collection)
+        .into_iter()
+        .map(|hash| splitmix64(hash ^ key))
+        .filter(|keyed| *keyed <= threshold)
+        .map(Wrapping)
+        .sum::<Wrapping<u64>>()
+        .0
+}
+
+///
+/// Computes an order-independent digest of a collection under each of the `seeds`, in a single
+/// pass. Each element is hashed once, by a new hasher from `BH`; its hash `h` is then mixed
+/// with every seed. The digest under `seed` is the wrapping sum of
+/// `splitmix64(h ^ splitmix64(seed))` over the elements, where `splitmix64` is the finalizer
+/// described by [`crate::UseIdentityMix`].
+///
+/// This costs one element hash and one mix per element and seed, which is much cheaper than
+/// hashing the collection once per seed, e.g. to fill the rows of a sketch. Distinct seeds give
+/// independent-looking digests, but they are all derived from the same element hashes, so
+/// collections whose element hashes collide under `BH` collide under every seed.
+///
+pub fn multi_seed_hashes<C, BH>(collection: &C, seeds: &[impl Into<Seed> + Copy]) -> Vec<u64>
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash,
+    BH: BuildHasherFromFriend<C>,
+{
+    let keys: Vec<u64> = seeds
+        .iter()
+        .map(|seed| splitmix64((*seed).into().as_u64()))
+        .collect();
+    let mut sums = vec![Wrapping(0_u64); seeds.len()];
+    for hash in hashes_of(collection, || BH::build_hasher_from(collection)) {
+        for (sum, key) in sums.iter_mut().zip(&keys) {
+            *sum += splitmix64(hash ^ key);
+        }
+    }
+    sums.into_iter().map(|sum| sum.0).collect()
+}
+
+///
+/// Computes the digest of a collection, as [`crate::hash_by_summing_hashes`] does, while
+/// building a Count-Min sketch of its elements in the same pass. The sketch answers
+/// approximate frequency queries with [`CountMinSketch::estimate`].
+///
+/// The sketch has `depth` rows of `width` counters, with row positions keyed by `seed`. An
+/// estimate is never less than the true frequency. It exceeds it by at most
+/// [`CountMinSketch::error_bound`], i.e. `e / width` times the number of elements, except with
+/// probability `e^-depth`. A `width` or `depth` of zero is treated as one.
+///
+/// The counters are allocated before hashing. If `width * depth` counters cannot be
+/// allocated, a [`SketchTooLarge`] error is returned, and the collection is not iterated.
+///
+pub fn countmin_fingerprint<C>(
+    collection: &C,
+    width: usize,
+    depth: usize,
+    seed: impl Into<Seed>,
+) -> Result<CountMinSketch, SketchTooLarge>
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash,
+{
+    let mut sketch = CountMinSketch {
+        digest: 0,
+        total: 0,
+        width: width.max(1),
+        depth: depth.max(1),
+        seed: seed.into().as_u64(),
+        counters: Vec::new(),
+    };
+    let too_large = SketchTooLarge {
+        width: sketch.width,
+        depth: sketch.depth,
+    };
+    let counters = sketch.width.checked_mul(sketch.depth).ok_or(too_large)?;
+    sketch
+        .counters
+        .try_reserve_exact(counters)
+        .map_err(|_| too_large)?;
+    sketch.counters.resize(counters, 0);
+    let mut digest = Wrapping::default();
+    for hash in hashes_of(collection, DefaultHasher::new) {
+        digest += hash;
+        sketch.total += 1;
+        for row in 0..sketch.depth {
+            let index = sketch.index(row, hash);
+            sketch.counters[index] += 1;
+        }
+    }
+    sketch.digest = digest.0;
+    Ok(sketch)
+}
+
+/// The error when the counters of a Count-Min sketch cannot be allocated
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SketchTooLarge {
+    width: usize,
+    depth: usize,
+}
+
+impl SketchTooLarge {
+    /// The requested number of counters per row, after treating zero as one
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// The requested number of rows, after treating zero as one
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+}
+
+impl Display for SketchTooLarge {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cannot allocate a Count-Min sketch of {} rows of {} counters",
+            self.depth, self.width
+        )
+    }
+}
+
+impl Error for SketchTooLarge {}
+
+/// A Count-Min sketch of a collection's elements, along with its exact digest
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CountMinSketch {
+    digest: u64,
+    total: u64,
+    width: usize,
+    depth: usize,
+    seed: u64,
+    counters: Vec<u64>,
+}
+
+impl CountMinSketch {
+    /// The position in the counters of an element hash, in the given row
+    fn index(&self, row: usize, hash: u64) -> usize {
+        let key = splitmix64(self.seed ^ splitmix64(row as u64));
+        let column = splitmix64(hash ^ key) % self.width as u64;
+        row * self.width + column as usize
+    }
+
+    /// The exact digest of the collection, as written by [`crate::hash_by_summing_hashes`]
+    pub fn hash(&self) -> u64 {
+        self.digest
+    }
+
+    /// The number of elements in the collection
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Estimates how many times the value occurs in the collection. The estimate is never
+    /// less than the true frequency.
+    pub fn estimate<T: Hash + ?Sized>(&self, value: &T) -> u64 {
+        let hash = hash_of(value, DefaultHasher::new());
+        (0..self.depth)
+            .map(|row| self.counters[self.index(row, hash)])
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// The amount by which an estimate may exceed the true frequency, except with probability
+    /// `e^-depth`: `e / width` times the number of elements, rounded up
+    pub fn error_bound(&self) -> u64 {
+        (std::f64::consts::E * self.total as f64 / self.width as f64).ceil() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SumHashesAnyCollection, UseDefaultHasher};
+    use std::collections::HashSet;
+
+    #[test]
+    fn prefix_is_deterministic_and_order_independent() {
+        let elements: Vec<u32> = (0..100).collect();
+        let reversed: Vec<u32> = elements.iter().rev().copied().collect();
+        let set: HashSet<u32> = elements.iter().copied().collect();
+
+        for n in [0, 1, 10, 99] {
+            let prefix = hash_prefix_unordered::<Vec<u32>, UseDefaultHasher>(&elements, n);
+            assert_eq!(
+                prefix,
+                hash_prefix_unordered::<Vec<u32>, UseDefaultHasher>(&elements, n)
+            );
+            assert_eq!(
+                prefix,
+                hash_prefix_unordered::<Vec<u32>, UseDefaultHasher>(&reversed, n)
+            );
+            assert_eq!(
+                prefix,
+                hash_prefix_unordered::<HashSet<u32>, UseDefaultHasher>(&set, n)
+            );
+        }
+        assert_eq!(
+            0,
+            hash_prefix_unordered::<Vec<u32>, UseDefaultHasher>(&elements, 0)
+        );
+    }
+
+    #[test]
+    fn prefix_of_everything_is_full_hash() {
+        let elements: Vec<u32> = (0..100).collect();
+        let full = SumHashesAnyCollection::<_, UseDefaultHasher>::new(elements.clone());
+        for n in [100, 101, usize::MAX] {
+            assert_eq!(
+                full.raw_unordered_hash(),
+                hash_prefix_unordered::<Vec<u32>, UseDefaultHasher>(&elements, n)
+            );
+        }
+        assert_ne!(
+            full.raw_unordered_hash(),
+            hash_prefix_unordered::<Vec<u32>, UseDefaultHasher>(&elements, 99)
+        );
+    }
+
+    fn shares_bucket(a: &HashSet<u32>, b: &HashSet<u32>, seed: u64) -> bool {
+        let a = lsh_bucket::<HashSet<u32>>(a, 20, 5, seed);
+        let b = lsh_bucket::<HashSet<u32>>(b, 20, 5, seed);
+        a.iter().zip(&b).any(|(a, b)| a == b)
+    }
+
+    #[test]
+    fn near_duplicates_share_buckets() {
+        let original: HashSet<u32> = (0..200).collect();
+        let mut near = original.clone();
+        near.remove(&7);
+        near.remove(&150);
+        near.insert(1000);
+
+        assert_eq!(20, lsh_bucket::<HashSet<u32>>(&original, 20, 5, 0).len());
+        let reordered: Vec<u32> = (0..200).rev().collect();
+        assert_eq!(
+            lsh_bucket::<HashSet<u32>>(&original, 20, 5, 0),
+            lsh_bucket::<Vec<u32>>(&reordered, 20, 5, 0)
+        );
+        for seed in 0..20 {
+            assert!(shares_bucket(&original, &near, seed));
+        }
+    }
+
+    #[test]
+    fn zero_rows_are_one_row() {
+        let evens: HashSet<u32> = (0..200).map(|n| n * 2).collect();
+        let odds: HashSet<u32> = (0..200).map(|n| n * 2 + 1).collect();
+        assert_eq!(
+            lsh_bucket::<HashSet<u32>>(&evens, 8, 1, 3),
+            lsh_bucket::<HashSet<u32>>(&evens, 8, 0, 3)
+        );
+        assert_ne!(
+            lsh_bucket::<HashSet<u32>>(&evens, 8, 0, 3),
+            lsh_bucket::<HashSet<u32>>(&odds, 8, 0, 3)
+        );
+    }
+
+    #[test]
+    fn different_sets_rarely_share_buckets() {
+        let evens: HashSet<u32> = (0..200).map(|n| n * 2).collect();
+        // Jaccard similarity of about 0.11 with the evens
+        let mixed: HashSet<u32> = (0..200)
+            .map(|n| if n < 40 { n * 2 } else { n * 2 + 1 })
+            .collect();
+        let odds: HashSet<u32> = (0..200).map(|n| n * 2 + 1).collect();
+        let seeds = 0..50_u64;
+        let sharing = seeds
+            .clone()
+            .filter(|seed| shares_bucket(&evens, &mixed, *seed))
+            .count();
+        assert!(sharing <= 2, "{} of 50 seeds share a bucket", sharing);
+        assert!(seeds
+            .into_iter()
+            .all(|seed| !shares_bucket(&evens, &odds, seed)));
+    }
+
+    #[test]
+    fn frequencies_within_error_bound() {
+        // Value n occurs n % 10 + 1 times
+        let values: Vec<u32> = (0..500_u32)
+            .flat_map(|n| std::iter::repeat(n).take(n as usize % 10 + 1))
+            .collect();
+        let sketch = countmin_fingerprint::<Vec<u32>>(&values, 272, 5, 7).unwrap();
+        assert_eq!(values.len() as u64, sketch.total());
+        // e / 272 of 2750 elements, rounded up
+        assert_eq!(28, sketch.error_bound());
+        for n in 0..500_u32 {
+            let estimate = sketch.estimate(&n);
+            let truth = u64::from(n % 10 + 1);
+            assert!(estimate >= truth);
+            assert!(
+                estimate <= truth + sketch.error_bound(),
+                "{} for {}",
+                estimate,
+                n
+            );
+        }
+        assert!(sketch.estimate(&1_000_000_u32) <= sketch.error_bound());
+    }
+
+    #[test]
+    fn countmin_hash_is_order_independent() {
+        let values: Vec<u32> = (0..100).collect();
+        let reversed: Vec<u32> = values.iter().rev().copied().collect();
+        let forward = countmin_fingerprint::<Vec<u32>>(&values, 64, 4, 0).unwrap();
+        let backward = countmin_fingerprint::<Vec<u32>>(&reversed, 64, 4, 0).unwrap();
+        assert_eq!(forward, backward);
+        assert_eq!(
+            SumHashesAnyCollection::<_, UseDefaultHasher>::new(values).raw_unordered_hash(),
+            forward.hash()
+        );
+        // Degenerate dimensions count everything together
+        let single = countmin_fingerprint::<Vec<u32>>(&reversed, 0, 0, 0).unwrap();
+        assert_eq!(100, single.estimate(&5_u32));
+    }
+
+    #[test]
+    fn oversized_sketches_are_errors() {
+        let values: Vec<u32> = (0..10).collect();
+        let error = countmin_fingerprint::<Vec<u32>>(&values, usize::MAX / 2, 4, 0).unwrap_err();
+        assert_eq!((usize::MAX / 2, 4), (error.width(), error.depth()));
+        assert!(error.to_string().contains("4 rows"), "{}", error);
+        // The product fits in a usize, but not in memory
+        assert!(countmin_fingerprint::<Vec<u32>>(&values, usize::MAX / 16, 1, 0).is_err());
+    }
+
+    /// Hashes the collection separately for one seed
+    fn seeded_digest(values: &[&str], seed: u64) -> u64 {
+        let key = splitmix64(seed);
+        values
+            .iter()
+            .map(|value| {
+                let mut hasher = DefaultHasher::new();
+                value.hash(&mut hasher);
+                Wrapping(splitmix64(hasher.finish() ^ key))
+            })
+            .sum::<Wrapping<u64>>()
+            .0
+    }
+
+    #[test]
+    fn multi_seed_matches_each_seed() {
+        let values = vec!["north", "east", "south", "west", "east"];
+        let reordered = vec!["east", "west", "east", "north", "south"];
+        let seeds = [0, 1, 2, u64::MAX, 0x5eed];
+        let hashes = multi_seed_hashes::<Vec<&str>, UseDefaultHasher>(&values, &seeds);
+        assert_eq!(seeds.len(), hashes.len());
+        for (seed, hash) in seeds.iter().zip(&hashes) {
+            assert_eq!(seeded_digest(&values, *seed), *hash);
+        }
+        assert_eq!(
+            hashes,
+            multi_seed_hashes::<Vec<&str>, UseDefaultHasher>(&reordered, &seeds)
+        );
+        let distinct: HashSet<u64> = hashes.iter().copied().collect();
+        assert_eq!(seeds.len(), distinct.len());
+
+        assert!(multi_seed_hashes::<Vec<&str>, UseDefaultHasher>(&values, &[0_u64; 0]).is_empty());
+        assert_eq!(
+            vec![0, 0],
+            multi_seed_hashes::<Vec<&str>, UseDefaultHasher>(&vec![], &[3, 4])
+        );
+    }
+
+    #[test]
+    fn fuzzy_hash_tolerates_few_differences() {
+        let base: Vec<u32> = (0..2000).collect();
+        let with_changes = |changes: u32| -> Vec<u32> {
+            let mut changed = base.clone();
+            for change in 0..changes {
+                changed[change as usize * 7] = 10_000 + change;
+            }
+            changed
+        };
+        // Each replaced element is two differences: one removed, one added
+        let near = with_changes(1);
+        let far = with_changes(10);
+
+        let (mut near_hits, mut far_hits) = (0, 0);
+        for seed in 0..200 {
+            let digest = fuzzy_hash::<Vec<u32>>(&base, 2, seed);
+            near_hits += usize::from(digest == fuzzy_hash::<Vec<u32>>(&near, 2, seed));
+            far_hits += usize::from(digest == fuzzy_hash::<Vec<u32>>(&far, 2, seed));
+        }
+        assert!(near_hits > 120, "{} near hits", near_hits);
+        assert!(far_hits < 40, "{} far hits", far_hits);
+        assert!(near_hits > far_hits);
+    }
+
+    #[test]
+    fn fuzzy_hash_without_tolerance_is_exact() {
+        let values: Vec<u32> = (0..100).collect();
+        let reversed: Vec<u32> = values.iter().rev().copied().collect();
+        let mut changed = values.clone();
+        changed[50] = 1000;
+        for seed in [0, 1, u64::MAX] {
+            let digest = fuzzy_hash::<Vec<u32>>(&values, 0, seed);
+            assert_eq!(digest, fuzzy_hash::<Vec<u32>>(&reversed, 0, seed));
+            assert_ne!(digest, fuzzy_hash::<Vec<u32>>(&changed, 0, seed));
+        }
+        assert_eq!(0, fuzzy_hash::<Vec<u32>>(&vec![], 3, 0));
+        // The maximum tolerance does not overflow
+        fuzzy_hash::<Vec<u32>>(&values, usize::MAX, 0);
+    }
+}
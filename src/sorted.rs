@@ -0,0 +1,227 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! A sorted set which maintains its digest as it is modified
+
+use crate::hash_of;
+use std::borrow::Borrow;
+use std::collections::btree_set::{self, BTreeSet};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::iter::FromIterator;
+use std::num::Wrapping;
+
+///
+/// A [`BTreeSet`] which keeps the sum of its element hashes up to date on every insertion and
+/// removal. Hashing takes constant time, and iteration is in sorted order. Each element is
+/// hashed with a new [`DefaultHasher`], so the `Hash` implementation writes the same value as
+/// [`crate::hash_by_summing_hashes`] does for the same elements.
+///
+/// Elements must not be mutated while in the set, e.g. through interior mutability, as this
+/// would invalidate both the order and the digest.
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SortedSumHashes<T> {
+    set: BTreeSet<T>,
+    sum: Wrapping<u64>,
+}
+
+fn element_hash<T: Hash + ?Sized>(value: &T) -> Wrapping<u64> {
+    Wrapping(hash_of(value, DefaultHasher::new()))
+}
+
+impl<T> SortedSumHashes<T> {
+    /// Creates an empty set
+    pub fn new() -> Self {
+        Self {
+            set: BTreeSet::new(),
+            sum: Wrapping(0),
+        }
+    }
+
+    /// The digest, i.e. the wrapping sum of the element hashes
+    pub fn digest(&self) -> u64 {
+        self.sum.0
+    }
+
+    /// Iterates over the elements in ascending order
+    pub fn iter(&self) -> btree_set::Iter<'_, T> {
+        self.set.iter()
+    }
+
+    /// The number of elements
+    pub fn len(&self) -> usize {
+        self.set.len()
+    }
+
+    /// Whether there are no elements
+    pub fn is_empty(&self) -> bool {
+        self.set.is_empty()
+    }
+
+    /// Takes the set of elements
+    pub fn into_inner(self) -> BTreeSet<T> {
+        self.set
+    }
+}
+
+impl<T: Ord + Hash> SortedSumHashes<T> {
+    /// Adds an element, returning whether it was newly inserted
+    pub fn insert(&mut self, value: T) -> bool {
+        let hash = element_hash(&value);
+        let inserted = self.set.insert(value);
+        if inserted {
+            self.sum += hash;
+        }
+        inserted
+    }
+
+    /// Removes an element, returning whether it was present
+    pub fn remove<Q>(&mut self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        match self.set.take(value) {
+            Some(removed) => {
+                self.sum -= element_hash(&removed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Whether the set contains an element
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.set.contains(value)
+    }
+
+    /// The smallest element, if any
+    pub fn first(&self) -> Option<&T> {
+        self.set.iter().next()
+    }
+
+    /// The largest element, if any
+    pub fn last(&self) -> Option<&T> {
+        self.set.iter().next_back()
+    }
+}
+
+impl<T> Default for SortedSumHashes<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord + Hash> From<BTreeSet<T>> for SortedSumHashes<T> {
+    fn from(set: BTreeSet<T>) -> Self {
+        let sum = set.iter().map(element_hash).sum();
+        Self { set, sum }
+    }
+}
+
+impl<T> Hash for SortedSumHashes<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.sum.0);
+    }
+}
+
+impl<T: Ord + Hash> FromIterator<T> for SortedSumHashes<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::from(BTreeSet::from_iter(iter))
+    }
+}
+
+impl<T: Ord + Hash> Extend<T> for SortedSumHashes<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+impl<'s, T> IntoIterator for &'s SortedSumHashes<T> {
+    type Item = &'s T;
+    type IntoIter = btree_set::Iter<'s, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.set.iter()
+    }
+}
+
+impl<T> IntoIterator for SortedSumHashes<T> {
+    type Item = T;
+    type IntoIter = btree_set::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.set.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hash_by_summing_hashes, SumHashesAnyCollection};
+    use std::collections::HashSet;
+
+    fn hash_one<T: Hash>(value: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn incremental_digest_matches_recompute() {
+        let mut sorted = SortedSumHashes::new();
+        let mut reference = HashSet::new();
+        for n in 0..500_u32 {
+            let value = n.wrapping_mul(2_654_435_761) % 97;
+            if n % 4 == 0 {
+                assert_eq!(reference.remove(&value), sorted.remove(&value));
+            } else {
+                assert_eq!(reference.insert(value), sorted.insert(value));
+            }
+            assert_eq!(
+                SumHashesAnyCollection::<_>::new(reference.clone()).raw_unordered_hash(),
+                sorted.digest()
+            );
+        }
+        let mut expected = DefaultHasher::new();
+        hash_by_summing_hashes::<HashSet<u32>, _>(&reference, &mut expected);
+        assert_eq!(expected.finish(), hash_one(&sorted));
+        assert_eq!(sorted, reference.into_iter().collect());
+    }
+
+    #[test]
+    fn iteration_is_sorted() {
+        let mut sorted: SortedSumHashes<&str> = ["pear", "apple", "fig"].into_iter().collect();
+        sorted.extend(["banana", "apple"]);
+        assert!(sorted.remove("fig"));
+        assert!(!sorted.contains("fig"));
+        assert_eq!(
+            vec!["apple", "banana", "pear"],
+            sorted.iter().copied().collect::<Vec<_>>()
+        );
+        assert_eq!(Some(&"apple"), sorted.first());
+        assert_eq!(Some(&"pear"), sorted.last());
+        assert_eq!(3, sorted.len());
+        assert_eq!(0, SortedSumHashes::<u8>::new().digest());
+    }
+}
@@ -0,0 +1,299 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Versioned algorithms, whose results are frozen across releases and platforms
+
+use crate::{summed_hash, BuildHasherFromFriend};
+use std::hash::{Hash, Hasher};
+
+///
+/// A versioned algorithm for computing order-independent digests. Each version's exact output
+/// is frozen: for the same elements, it is the same in every release of this library, and on
+/// every platform for [portable element types](#portable-element-types). A change in behavior
+/// is only ever introduced as a new version. Use [`versioned_digest`] or [`hash_versioned`] for
+/// digests which are persisted.
+///
+/// The frozen output assumes the `Hash` implementations of the elements do not change. Those of
+/// the standard library's primitive types, strings, tuples and references have been stable in
+/// practice but are outside this library's control.
+///
+/// The unversioned functions, such as [`crate::hash_by_summing_hashes`], use the standard
+/// library's [`std::collections::hash_map::DefaultHasher`], whose algorithm is explicitly
+/// unspecified. They currently produce the same digests as [`Algorithm::V1Sum64`] on 64-bit
+/// little-endian platforms, but this is not guaranteed, so they are not suitable for persisting.
+///
+/// Digests which are compared across platforms, such as between wasm32 in a browser and x86_64
+/// on a server, must also use a versioned algorithm. The `DefaultHasher` writes integers in
+/// native byte order, and writes `usize` values with the platform's width, including the
+/// lengths which the `Hash` implementations of slices, `Vec`s and other collections write
+/// before their contents. Under a versioned algorithm, these are the same everywhere.
+///
+/// # Portable element types
+///
+/// A versioned digest is the same on every platform when the elements are integers, including
+/// `usize` and `isize`, `bool`, `char`, `str` and `String`, and tuples, `Option`s, references
+/// and derived `Hash` implementations composed of these. The collection being digested may be
+/// any collection of such elements, including maps.
+///
+/// Elements which themselves contain slices, arrays or `Vec`s of integers are not portable.
+/// The standard library hashes these as the raw memory of the whole slice, in native byte order
+/// and width, which no hasher can correct. Nor are paths, `OsStr`s, or pointers portable.
+///
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum Algorithm {
+    ///
+    /// The wrapping sum of the hashes of each element, where each element is hashed by a new
+    /// [`StableHasher`]: SipHash-1-3 with zero keys, with integers written in little-endian
+    /// order and `usize` and `isize` widened to 64 bits.
+    ///
+    V1Sum64,
+}
+
+impl Algorithm {
+    /// The most recent version
+    pub const LATEST: Algorithm = Algorithm::V1Sum64;
+}
+
+///
+/// Computes the digest of a collection with a versioned algorithm. The result is frozen for the
+/// given version, as described by [`Algorithm`].
+///
+pub fn versioned_digest<C>(collection: &C, algorithm: Algorithm) -> u64
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash,
+{
+    match algorithm {
+        Algorithm::V1Sum64 => summed_hash::<C, UseStableHasher>(collection),
+    }
+}
+
+///
+/// Hashes a collection with a versioned algorithm, by writing its [`versioned_digest`] to
+/// `state` as a `u64`.
+///
+pub fn hash_versioned<C, H>(collection: &C, algorithm: Algorithm, state: &mut H)
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash,
+    H: Hasher,
+{
+    state.write_u64(versioned_digest(collection, algorithm));
+}
+
+///
+/// Creates a new [`StableHasher`] per element
+///
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct UseStableHasher(());
+
+impl<F> BuildHasherFromFriend<F> for UseStableHasher {
+    type Hasher = StableHasher;
+
+    fn build_hasher_from<'f>(_: &'f F) -> Self::Hasher
+    where
+        Self::Hasher: 'f,
+    {
+        StableHasher::new()
+    }
+}
+
+///
+/// SipHash-1-3 with zero keys, whose output does not depend on the platform. Integers are
+/// written in little-endian byte order, and `usize` and `isize` are widened to 64 bits. This is
+/// the hasher used by [`Algorithm::V1Sum64`].
+///
+#[derive(Clone, Debug)]
+pub struct StableHasher {
+    state: [u64; 4],
+    tail: u64,
+    tail_len: usize,
+    length: usize,
+}
+
+impl StableHasher {
+    /// Creates the hasher
+    pub fn new() -> Self {
+        Self {
+            state: [
+                0x736f_6d65_7073_6575,
+                0x646f_7261_6e64_6f6d,
+                0x6c79_6765_6e65_7261,
+                0x7465_6462_7974_6573,
+            ],
+            tail: 0,
+            tail_len: 0,
+            length: 0,
+        }
+    }
+
+    fn round(state: &mut [u64; 4]) {
+        let [v0, v1, v2, v3] = state;
+        *v0 = v0.wrapping_add(*v1);
+        *v1 = v1.rotate_left(13) ^ *v0;
+        *v0 = v0.rotate_left(32);
+        *v2 = v2.wrapping_add(*v3);
+        *v3 = v3.rotate_left(16) ^ *v2;
+        *v0 = v0.wrapping_add(*v3);
+        *v3 = v3.rotate_left(21) ^ *v0;
+        *v2 = v2.wrapping_add(*v1);
+        *v1 = v1.rotate_left(17) ^ *v2;
+        *v2 = v2.rotate_left(32);
+    }
+
+    fn compress(&mut self, block: u64) {
+        self.state[3] ^= block;
+        Self::round(&mut self.state);
+        self.state[0] ^= block;
+    }
+}
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+macro_rules! write_le {
+    ($($method:ident: $int:ty),*) => {
+        $(
+            fn $method(&mut self, value: $int) {
+                self.write(&value.to_le_bytes())
+            }
+        )*
+    };
+}
+
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        let mut state = self.state;
+        let block = ((self.length as u64 & 0xff) << 56) | self.tail;
+        state[3] ^= block;
+        Self::round(&mut state);
+        state[0] ^= block;
+        state[2] ^= 0xff;
+        for _ in 0..3 {
+            Self::round(&mut state);
+        }
+        state[0] ^ state[1] ^ state[2] ^ state[3]
+    }
+
+    fn write(&mut self, mut bytes: &[u8]) {
+        self.length = self.length.wrapping_add(bytes.len());
+        // Completes a partial block left over from the previous write
+        while self.tail_len > 0 && !bytes.is_empty() {
+            self.tail |= u64::from(bytes[0]) << (8 * self.tail_len);
+            self.tail_len += 1;
+            bytes = &bytes[1..];
+            if self.tail_len == 8 {
+                let block = self.tail;
+                self.compress(block);
+                self.tail = 0;
+                self.tail_len = 0;
+            }
+        }
+        let mut blocks = bytes.chunks_exact(8);
+        for block in &mut blocks {
+            let mut buffer = [0; 8];
+            buffer.copy_from_slice(block);
+            self.compress(u64::from_le_bytes(buffer));
+        }
+        for byte in blocks.remainder() {
+            self.tail |= u64::from(*byte) << (8 * self.tail_len);
+            self.tail_len += 1;
+        }
+    }
+
+    write_le!(
+        write_u16: u16, write_u32: u32, write_u64: u64, write_u128: u128,
+        write_i16: i16, write_i32: i32, write_i64: i64, write_i128: i128
+    );
+
+    fn write_u8(&mut self, value: u8) {
+        self.write(&[value])
+    }
+
+    fn write_i8(&mut self, value: i8) {
+        self.write_u8(value as u8)
+    }
+
+    fn write_usize(&mut self, value: usize) {
+        self.write_u64(value as u64)
+    }
+
+    fn write_isize(&mut self, value: isize) {
+        self.write_i64(value as i64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::hash_map::DefaultHasher;
+
+    fn stable<T: Hash + ?Sized>(value: &T) -> u64 {
+        let mut hasher = StableHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    // On these platforms, the standard library's hasher is currently the same algorithm
+    #[cfg(all(target_endian = "little", target_pointer_width = "64"))]
+    #[test]
+    fn matches_default_hasher_here() {
+        fn default<T: Hash + ?Sized>(value: &T) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            value.hash(&mut hasher);
+            hasher.finish()
+        }
+        let long: String = (0..100).map(|n| char::from(b'a' + n % 26)).collect();
+        assert_eq!(default(""), stable(""));
+        assert_eq!(default(&long), stable(&long));
+        assert_eq!(
+            default(&(1_u8, -2_i16, 3_u32, 4_usize)),
+            stable(&(1_u8, -2_i16, 3_u32, 4_usize))
+        );
+        assert_eq!(default(&[u128::MAX; 3]), stable(&[u128::MAX; 3]));
+        assert_eq!(
+            default(&vec!["split", "across", "writes"]),
+            stable(&vec!["split", "across", "writes"])
+        );
+    }
+
+    #[test]
+    fn split_writes_match_whole_writes() {
+        let bytes: Vec<u8> = (0..=40).collect();
+        let mut whole = StableHasher::new();
+        whole.write(&bytes);
+        for split in 0..bytes.len() {
+            let mut parts = StableHasher::new();
+            parts.write(&bytes[..split]);
+            parts.write(&bytes[split..]);
+            assert_eq!(whole.finish(), parts.finish());
+        }
+    }
+
+    #[test]
+    fn versioned_digest_is_written() {
+        let values = vec![1_u64, 2, 3];
+        let mut hasher = StableHasher::new();
+        hash_versioned::<Vec<u64>, _>(&values, Algorithm::LATEST, &mut hasher);
+        let mut expected = StableHasher::new();
+        expected.write_u64(versioned_digest::<Vec<u64>>(&values, Algorithm::V1Sum64));
+        assert_eq!(expected.finish(), hasher.finish());
+    }
+}
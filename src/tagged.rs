@@ -0,0 +1,198 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Domain separation, so that digests of different kinds of collections do not collide
+
+use crate::{sum_of_hashes, BuildHasherFromFriend, UseDefaultHasher};
+use std::any::type_name;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+///
+/// A tag which is mixed into the hash of every element before the element itself. Collections
+/// whose elements write the same bytes, such as a `HashSet<u32>` and a `HashSet<i32>`, or a set
+/// of newtype ids and a set of raw integers, have identical digests; hashing them under
+/// different tags makes their digests differ.
+///
+/// A tag is written to each element hasher as a single `u64`. A tag made from a string is
+/// the 64-bit FNV-1a hash of its bytes, hence the same on every platform and in every release.
+///
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct DomainTag(u64);
+
+impl DomainTag {
+    /// Creates a tag from a string, such as the name of a table
+    pub const fn new(name: &'static str) -> Self {
+        Self(fnv1a(name.as_bytes()))
+    }
+
+    /// Creates a tag from a number
+    pub const fn from_u64(value: u64) -> Self {
+        Self(value)
+    }
+
+    ///
+    /// Creates a tag from the name of a type, as given by [`std::any::type_name`]. That name is
+    /// not guaranteed to be the same between compiler versions, so neither are the digests:
+    /// use [`DomainTag::new`] for digests which are persisted.
+    ///
+    pub fn of_type<T: ?Sized>() -> Self {
+        Self(fnv1a(type_name::<T>().as_bytes()))
+    }
+
+    /// The value written to each element hasher
+    pub const fn value(&self) -> u64 {
+        self.0
+    }
+}
+
+/// The 64-bit FNV-1a hash
+const fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325_u64;
+    let mut index = 0;
+    while index < bytes.len() {
+        hash ^= bytes[index] as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+        index += 1;
+    }
+    hash
+}
+
+///
+/// Defines a [`DomainTag`] at the type level, for use with [`UseTaggedHasher`]
+///
+/// ```rust
+/// use hash_that_set::{Domain, DomainTag};
+///
+/// #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+/// struct Accounts;
+///
+/// impl Domain for Accounts {
+///     const TAG: DomainTag = DomainTag::new("accounts");
+/// }
+/// ```
+///
+pub trait Domain {
+    /// The tag mixed into every element hash
+    const TAG: DomainTag;
+}
+
+///
+/// Implementation of [`BuildHasherFromFriend`] which creates hashers from `BH`, then writes the
+/// tag of the domain `D` to each before the element is hashed
+///
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct UseTaggedHasher<D: Domain, BH = UseDefaultHasher>(PhantomData<(D, BH)>);
+
+impl<F, D: Domain, BH: BuildHasherFromFriend<F>> BuildHasherFromFriend<F>
+    for UseTaggedHasher<D, BH>
+{
+    type Hasher = BH::Hasher;
+
+    fn build_hasher_from<'f>(friend: &'f F) -> Self::Hasher
+    where
+        Self::Hasher: 'f,
+    {
+        let mut hasher = BH::build_hasher_from(friend);
+        hasher.write_u64(D::TAG.value());
+        hasher
+    }
+}
+
+///
+/// Implements hashing by summing the hashes of each element, mixing `tag` into each element
+/// hash beforehand. A new [`DefaultHasher`] is created for each element. The result is the
+/// same as that of [`crate::hash_by_summing_hashes_with`] with a [`UseTaggedHasher`] whose
+/// domain has the same tag.
+///
+pub fn hash_by_summing_tagged<C, H>(tag: DomainTag, collection: &C, state: &mut H)
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash,
+    H: Hasher,
+{
+    let new_hasher = || {
+        let mut hasher = DefaultHasher::new();
+        hasher.write_u64(tag.value());
+        hasher
+    };
+    state.write_u64(sum_of_hashes(collection, new_hasher));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hash_by_summing_hashes, hash_by_summing_hashes_with, SumHashesAnyCollection};
+    use std::collections::HashSet;
+
+    #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+    struct Users;
+
+    impl Domain for Users {
+        const TAG: DomainTag = DomainTag::new("users");
+    }
+
+    fn tagged<C>(tag: DomainTag, collection: &C) -> u64
+    where
+        for<'c> &'c C: IntoIterator,
+        for<'c> <&'c C as IntoIterator>::Item: Hash,
+    {
+        let mut hasher = DefaultHasher::new();
+        hash_by_summing_tagged(tag, collection, &mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn tags_separate_identical_content() {
+        let unsigned: HashSet<u32> = [1, 2].into_iter().collect();
+        let signed: HashSet<i32> = [1, 2].into_iter().collect();
+        let mut untagged = (DefaultHasher::new(), DefaultHasher::new());
+        hash_by_summing_hashes::<HashSet<u32>, _>(&unsigned, &mut untagged.0);
+        hash_by_summing_hashes::<HashSet<i32>, _>(&signed, &mut untagged.1);
+        assert_eq!(untagged.0.finish(), untagged.1.finish());
+
+        assert_ne!(
+            tagged::<HashSet<u32>>(DomainTag::of_type::<u32>(), &unsigned),
+            tagged::<HashSet<i32>>(DomainTag::of_type::<i32>(), &signed)
+        );
+        assert_ne!(
+            tagged::<HashSet<u32>>(DomainTag::new("orders"), &unsigned),
+            tagged::<HashSet<i32>>(DomainTag::new("users"), &signed)
+        );
+    }
+
+    #[test]
+    fn same_tag_reproduces_digest() {
+        let ids: HashSet<u32> = [7, 8, 9].into_iter().collect();
+        let reordered = vec![9_u32, 7, 8];
+        let tag = DomainTag::new("users");
+        assert_eq!(0x4228_8471_628c_3333, tag.value());
+        assert_eq!(
+            tagged::<HashSet<u32>>(tag, &ids),
+            tagged::<Vec<u32>>(tag, &reordered)
+        );
+
+        let mut hasher = DefaultHasher::new();
+        hash_by_summing_hashes_with::<HashSet<u32>, _, UseTaggedHasher<Users>>(&ids, &mut hasher);
+        assert_eq!(tagged::<HashSet<u32>>(tag, &ids), hasher.finish());
+        assert_eq!(
+            SumHashesAnyCollection::<_, UseTaggedHasher<Users>>::new(reordered)
+                .raw_unordered_hash(),
+            SumHashesAnyCollection::<_, UseTaggedHasher<Users>>::new(ids).raw_unordered_hash()
+        );
+    }
+}
@@ -0,0 +1,94 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Deliberately weak hashing strategies, used to construct collisions in tests
+
+// Which of these are used depends on the enabled features
+#![allow(dead_code)]
+
+use crate::BuildHasherFromFriend;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hasher;
+
+/// A default hasher whose result is truncated by a bit mask, making collisions likely
+#[derive(Default)]
+pub struct MaskedHasher<const MASK: u64>(DefaultHasher);
+
+impl<const MASK: u64> Hasher for MaskedHasher<MASK> {
+    fn finish(&self) -> u64 {
+        self.0.finish() & MASK
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.write(bytes)
+    }
+}
+
+/// Creates [`MaskedHasher`]s
+pub struct UseMaskedHasher<const MASK: u64>;
+
+impl<F, const MASK: u64> BuildHasherFromFriend<F> for UseMaskedHasher<MASK> {
+    type Hasher = MaskedHasher<MASK>;
+
+    fn build_hasher_from<'f>(_: &'f F) -> Self::Hasher
+    where
+        Self::Hasher: 'f,
+    {
+        MaskedHasher::default()
+    }
+}
+
+/// A hasher whose result is the integer written to it, making collisions easy to construct
+#[derive(Default)]
+pub struct IdentityHasher(u64);
+
+impl Hasher for IdentityHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 << 8) | u64::from(*byte);
+        }
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        self.0 = u64::from(value);
+    }
+
+    fn write_i32(&mut self, value: i32) {
+        self.0 = value as u64;
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.0 = value;
+    }
+}
+
+/// Creates [`IdentityHasher`]s
+pub struct UseIdentityHasher;
+
+impl<F> BuildHasherFromFriend<F> for UseIdentityHasher {
+    type Hasher = IdentityHasher;
+
+    fn build_hasher_from<'f>(_: &'f F) -> Self::Hasher
+    where
+        Self::Hasher: 'f,
+    {
+        IdentityHasher::default()
+    }
+}
@@ -0,0 +1,180 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Tracing of expensive digest computations. Without the `tracing` feature, [`DigestSpan`] is
+//! empty and all of its methods compile to nothing.
+//!
+//! Only computations over at least [`TRACE_THRESHOLD`] elements are traced, so that hashing
+//! small collections, for instance as the keys of a map, does not emit a span per call.
+
+/// The number of elements, as reported by the size hint of the collection's iterator, from
+/// which digest computations are traced
+#[cfg_attr(not(feature = "tracing"), allow(dead_code))]
+pub(crate) const TRACE_THRESHOLD: usize = 1000;
+
+///
+/// A `digest` span at the debug level, covering one computation of a digest. It records the
+/// `operation`, the `strategy` by type name, and the number of `elements` hashed, and ends with
+/// a `digest computed` event. A subscriber which times spans, such as tracing-subscriber's `fmt`
+/// with `FmtSpan::CLOSE`, reports how long the computation took.
+///
+pub(crate) struct DigestSpan {
+    #[cfg(feature = "tracing")]
+    span: Option<tracing::span::EnteredSpan>,
+    #[cfg(feature = "tracing")]
+    elements: usize,
+}
+
+impl DigestSpan {
+    /// Enters a span for the given operation, hashing elements with the strategy `BH`, if at
+    /// least [`TRACE_THRESHOLD`] elements are `expected`
+    #[inline(always)]
+    #[cfg_attr(not(feature = "tracing"), allow(clippy::extra_unused_type_parameters))]
+    pub(crate) fn enter<BH: ?Sized>(operation: &'static str, expected: usize) -> Self {
+        #[cfg(not(feature = "tracing"))]
+        let _ = (operation, expected);
+        Self {
+            #[cfg(feature = "tracing")]
+            span: (expected >= TRACE_THRESHOLD).then(|| {
+                tracing::debug_span!(
+                    "digest",
+                    operation,
+                    strategy = std::any::type_name::<BH>(),
+                    elements = tracing::field::Empty,
+                )
+                .entered()
+            }),
+            #[cfg(feature = "tracing")]
+            elements: 0,
+        }
+    }
+
+    /// Counts elements which were hashed
+    #[inline(always)]
+    pub(crate) fn add_elements(&mut self, count: usize) {
+        #[cfg(feature = "tracing")]
+        {
+            self.elements += count;
+        }
+        #[cfg(not(feature = "tracing"))]
+        let _ = count;
+    }
+
+    /// Records the element count and exits the span, passing through the digest
+    #[inline(always)]
+    pub(crate) fn finish(self, digest: u64) -> u64 {
+        #[cfg(feature = "tracing")]
+        if let Some(span) = self.span {
+            span.record("elements", self.elements);
+            tracing::debug!(elements = self.elements, "digest computed");
+        }
+        digest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        hash_by_summing_hashes, hash_by_summing_parallel_scoped, hash_by_xoring_hashes,
+        precompute_contributions, UseDefaultHasher,
+    };
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::HashMap;
+    use std::io;
+    use std::sync::{Arc, Mutex};
+    use tracing_subscriber::fmt::format::FmtSpan;
+
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Runs `action` under a subscriber which logs every span and event, returning the log
+    fn capture(action: impl FnOnce()) -> String {
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::TRACE)
+            .with_span_events(FmtSpan::CLOSE)
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+        tracing::subscriber::with_default(subscriber, action);
+        let log = captured.0.lock().unwrap().clone();
+        String::from_utf8(log).unwrap()
+    }
+
+    fn hash_large_collections() {
+        let set: Vec<u32> = (0..100_000).collect();
+        hash_by_summing_hashes::<Vec<u32>, _>(&set, &mut DefaultHasher::new());
+        hash_by_xoring_hashes::<Vec<u32>, _>(&set, &mut DefaultHasher::new());
+        hash_by_summing_parallel_scoped::<Vec<u32>, _>(&set, &mut DefaultHasher::new(), 2);
+        let map: HashMap<u32, u32> = (0..1_000).map(|n| (n, n * 2)).collect();
+        precompute_contributions::<HashMap<u32, u32>, UseDefaultHasher, _, _>(&map);
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn spans_record_their_fields() {
+        let log = capture(hash_large_collections);
+        assert!(log.contains("operation=\"summed_hash\""), "{}", log);
+        assert!(log.contains("operation=\"xored_hash\""), "{}", log);
+        assert!(
+            log.contains("operation=\"summed_parallel_scoped\""),
+            "{}",
+            log
+        );
+        assert!(log.contains("elements=100000"), "{}", log);
+        assert!(
+            log.contains("operation=\"precompute_contributions\""),
+            "{}",
+            log
+        );
+        assert!(log.contains("elements=1000"), "{}", log);
+        assert!(
+            log.contains("strategy=\"hash_that_set::UseDefaultHasher\""),
+            "{}",
+            log
+        );
+        assert!(log.contains("digest computed"), "{}", log);
+        assert!(log.contains("time.busy"), "{}", log);
+    }
+
+    #[test]
+    fn small_collections_are_not_traced() {
+        let log = capture(|| {
+            let small: Vec<u32> = (0..super::TRACE_THRESHOLD as u32 - 1).collect();
+            hash_by_summing_hashes::<Vec<u32>, _>(&small, &mut DefaultHasher::new());
+            hash_by_xoring_hashes::<Vec<u32>, _>(&small, &mut DefaultHasher::new());
+        });
+        assert_eq!("", log);
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    #[test]
+    fn nothing_is_emitted_without_the_feature() {
+        assert_eq!("", capture(hash_large_collections));
+    }
+}
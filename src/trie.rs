@@ -0,0 +1,117 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Integration for tries and other prefix maps, which reconstruct their keys on demand
+
+use crate::sum_of_hashes;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+///
+/// A collection whose keys may be enumerated, such as a trie or prefix map. Tries typically
+/// do not store their keys whole, so each key is produced by value as it is iterated.
+///
+/// To hash the same as an equivalent `HashSet` of keys, every key must hash the same as the
+/// corresponding element of the set; for example, a trie of strings should visit `String`s or
+/// `&str`s.
+///
+/// The iterator is boxed, since generic associated types are unavailable at the crate's
+/// minimum supported Rust version.
+///
+pub trait TrieKeys {
+    /// The type of each key
+    type Key: Hash;
+
+    /// Iterates over the keys, in any order
+    fn keys(&self) -> Box<dyn Iterator<Item = Self::Key> + '_>;
+}
+
+///
+/// Hashes the key set of a trie by summing the hashes of each key. A new [`DefaultHasher`] is
+/// created for each key, so the result is identical to hashing an equivalent `HashSet` of keys
+/// with [`crate::hash_by_summing_hashes`].
+///
+pub fn hash_trie_keys<T, H>(trie: &T, state: &mut H)
+where
+    T: TrieKeys + ?Sized,
+    H: Hasher,
+{
+    state.write_u64(sum_of_hashes(trie.keys(), DefaultHasher::new));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash_by_summing_hashes;
+    use std::collections::{BTreeMap, HashSet};
+
+    #[derive(Default)]
+    struct Node {
+        terminal: bool,
+        children: BTreeMap<char, Node>,
+    }
+
+    impl Node {
+        fn insert(&mut self, key: &str) {
+            let node = key.chars().fold(self, |node, letter| {
+                node.children.entry(letter).or_default()
+            });
+            node.terminal = true;
+        }
+
+        fn collect_keys(&self, prefix: &mut String, keys: &mut Vec<String>) {
+            if self.terminal {
+                keys.push(prefix.clone());
+            }
+            for (letter, child) in &self.children {
+                prefix.push(*letter);
+                child.collect_keys(prefix, keys);
+                prefix.pop();
+            }
+        }
+    }
+
+    impl TrieKeys for Node {
+        type Key = String;
+
+        fn keys(&self) -> Box<dyn Iterator<Item = String> + '_> {
+            let mut keys = Vec::new();
+            self.collect_keys(&mut String::new(), &mut keys);
+            Box::new(keys.into_iter())
+        }
+    }
+
+    #[test]
+    fn trie_hashes_like_key_set() {
+        let keys = ["", "tea", "ten", "to", "in", "inn", "t"];
+        let mut trie = Node::default();
+        for key in keys {
+            trie.insert(key);
+        }
+        let set: HashSet<&str> = keys.into_iter().collect();
+
+        let (mut trie_hasher, mut set_hasher) = (DefaultHasher::new(), DefaultHasher::new());
+        hash_trie_keys(&trie, &mut trie_hasher);
+        hash_by_summing_hashes::<HashSet<&str>, _>(&set, &mut set_hasher);
+        assert_eq!(set_hasher.finish(), trie_hasher.finish());
+        assert_eq!(keys.len(), trie.keys().count());
+
+        trie.insert("tent");
+        let mut larger_hasher = DefaultHasher::new();
+        hash_trie_keys(&trie, &mut larger_hasher);
+        assert_ne!(set_hasher.finish(), larger_hasher.finish());
+    }
+}
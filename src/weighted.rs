@@ -0,0 +1,130 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Hashing with elements weighted by their recency
+
+use crate::hash_of;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::num::Wrapping;
+
+///
+/// Hashes a collection by summing the hashes of each element, with each hash scaled by an
+/// exponential decay factor according to the element's age. This suits fingerprints of
+/// time-series data, such as a sliding cache, where newer elements should influence the hash
+/// more than older ones. A new [`DefaultHasher`] is created for each element.
+///
+/// `ts` extracts the timestamp of an element, and its age is `now - ts`, or zero for timestamps
+/// in the future. The weight of an element is `exp(-decay * age)`, clamped to between zero and
+/// one, so `decay` is per unit of the timestamps. Each hash is multiplied by its weight in
+/// fixed point, with 32 fractional bits.
+///
+/// **The result is lossy and approximate**. Scaling discards the low bits of each hash, and
+/// elements old enough to have a weight of zero do not contribute at all. Since the weights
+/// are computed with floating-point arithmetic, which may differ slightly between platforms,
+/// results should not be compared across machines.
+///
+pub fn hash_time_weighted<C, H, F>(collection: &C, state: &mut H, now: u64, decay: f64, ts: F)
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash,
+    F: for<'c> Fn(&<&'c C as IntoIterator>::Item) -> u64,
+    H: Hasher,
+{
+    const ONE: f64 = (1_u64 << 32) as f64;
+
+    let mut sum = Wrapping::default();
+    for value in collection {
+        let age = now.saturating_sub(ts(&value)) as f64;
+        // A NaN weight becomes zero when cast
+        let weight = (-decay * age).exp().clamp(0.0, 1.0);
+        let hash = hash_of(&value, DefaultHasher::new());
+        let scaled = (u128::from(hash) * (weight * ONE) as u128) >> 32;
+        sum += scaled as u64;
+    }
+    state.write_u64(sum.0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash_by_summing_hashes;
+
+    /// A cache entry, which hashes by name only
+    struct Entry {
+        name: &'static str,
+        timestamp: u64,
+    }
+
+    impl Hash for Entry {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            self.name.hash(state)
+        }
+    }
+
+    fn weighted(entries: &Vec<Entry>, now: u64, decay: f64) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        hash_time_weighted::<Vec<Entry>, _, _>(
+            entries,
+            &mut hasher,
+            now,
+            decay,
+            |entry: &&Entry| entry.timestamp,
+        );
+        hasher.finish()
+    }
+
+    fn entries(timestamps: [u64; 3]) -> Vec<Entry> {
+        ["a", "b", "c"]
+            .into_iter()
+            .zip(timestamps)
+            .map(|(name, timestamp)| Entry { name, timestamp })
+            .collect()
+    }
+
+    #[test]
+    fn recency_changes_the_hash() {
+        let recent_heavy = entries([100, 99, 10]);
+        let old_heavy = entries([100, 10, 9]);
+        assert_ne!(
+            weighted(&recent_heavy, 100, 0.1),
+            weighted(&old_heavy, 100, 0.1)
+        );
+
+        // Without decay, every element has full weight, which is exactly the plain sum
+        let mut unweighted = DefaultHasher::new();
+        hash_by_summing_hashes::<Vec<Entry>, _>(&recent_heavy, &mut unweighted);
+        assert_eq!(
+            weighted(&recent_heavy, 100, 0.0),
+            weighted(&old_heavy, 100, 0.0)
+        );
+        assert_eq!(unweighted.finish(), weighted(&recent_heavy, 100, 0.0));
+    }
+
+    #[test]
+    fn extreme_parameters_do_not_panic() {
+        let entries = entries([0, u64::MAX, 5]);
+        for decay in [f64::NAN, f64::INFINITY, -1.0, 1e300] {
+            weighted(&entries, u64::MAX, decay);
+            weighted(&entries, 0, decay);
+        }
+        // Elements with zero weight do not contribute
+        let mut nothing = DefaultHasher::new();
+        nothing.write_u64(0);
+        let old = self::entries([0, 1, 5]);
+        assert_eq!(nothing.finish(), weighted(&old, 1000, 1e300));
+    }
+}
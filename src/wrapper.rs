@@ -0,0 +1,331 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! The surface shared by the wrappers, whichever way they combine element hashes
+
+///
+/// Implements the traits and inherent methods of a pair of wrappers, one using the collection's
+/// provided hasher and the other a `BuildHasherFromFriend` strategy, given the function which
+/// computes their digest. The wrappers must be tuple structs of the collection, and for the
+/// latter, a `PhantomData` of the strategy.
+///
+macro_rules! wrapper_impls {
+    ($provided:ident, $any:ident, $digest:ident) => {
+        impl<C: $crate::ProvidesHasher> From<C> for $provided<C> {
+            /// Creates the wrapper
+            #[inline]
+            fn from(value: C) -> Self {
+                Self(value)
+            }
+        }
+
+        impl<C: $crate::ProvidesHasher> $provided<C> {
+            /// Creates the wrapper
+            #[inline]
+            pub fn new(value: C) -> Self {
+                Self::from(value)
+            }
+
+            /// Destructures into the inner collection
+            #[inline]
+            pub fn into_inner(self) -> C {
+                self.0
+            }
+        }
+
+        impl<C: $crate::NewCollection> $provided<C> {
+            /// Creates the wrapper around an empty collection, which will use the given hasher
+            #[inline]
+            pub fn with_hasher(hasher: C::Hasher) -> Self {
+                Self::from(C::with_hasher(hasher))
+            }
+
+            /// Creates the wrapper around an empty collection with at least the specified
+            /// capacity, which will use the given hasher
+            #[inline]
+            pub fn with_capacity_and_hasher(capacity: usize, hasher: C::Hasher) -> Self {
+                Self::from(C::with_capacity_and_hasher(capacity, hasher))
+            }
+
+            /// Creates the wrapper around an empty collection with at least the specified
+            /// capacity
+            #[inline]
+            pub fn with_capacity(capacity: usize) -> Self
+            where
+                C::Hasher: Default,
+            {
+                Self::with_capacity_and_hasher(capacity, Default::default())
+            }
+        }
+
+        impl<C: $crate::ProvidesHasher> $provided<C>
+        where
+            for<'c> &'c C: IntoIterator,
+            for<'c> <&'c C as IntoIterator>::Item: ::std::hash::Hash,
+        {
+            ///
+            /// Returns the canonical digest of the collection under the provided hasher. This is
+            /// exactly the value which the `Hash` implementation writes to the outer hasher, and
+            /// is suitable for storing directly without going through another hasher.
+            ///
+            #[inline]
+            pub fn raw_unordered_hash(&self) -> u64 {
+                $digest::<C, $crate::UseProvidedHasher<C>>(&self.0)
+            }
+
+            /// Computes the digest of the collection using hashers from `BH`, rather than the
+            /// provided hasher
+            #[inline]
+            pub fn digest_with<BH: $crate::BuildHasherFromFriend<C>>(&self) -> u64 {
+                $digest::<C, BH>(&self.0)
+            }
+        }
+
+        // The hasher parameter is a marker, so these traits are implemented by hand; deriving
+        // them would require the same traits of the marker, and not only of the collection
+        impl<C: Clone, H> Clone for $any<C, H> {
+            fn clone(&self) -> Self {
+                Self::new(self.0.clone())
+            }
+        }
+
+        impl<C: ::std::fmt::Debug, H> ::std::fmt::Debug for $any<C, H> {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                f.debug_tuple(stringify!($any)).field(&self.0).finish()
+            }
+        }
+
+        impl<C: Default, H> Default for $any<C, H> {
+            fn default() -> Self {
+                Self::new(C::default())
+            }
+        }
+
+        #[cfg(not(feature = "debug-assert"))]
+        impl<C: PartialEq, H> PartialEq for $any<C, H> {
+            fn eq(&self, other: &Self) -> bool {
+                self.0 == other.0
+            }
+        }
+
+        #[cfg(not(feature = "debug-assert"))]
+        impl<C: Eq, H> Eq for $any<C, H> {}
+
+        // With debug-assert, equality requires what hashing requires, so that it can check
+        // both agree
+
+        #[cfg(feature = "debug-assert")]
+        impl<C: PartialEq, BH> PartialEq for $any<C, BH>
+        where
+            for<'c> &'c C: IntoIterator,
+            for<'c> <&'c C as IntoIterator>::Item: ::std::hash::Hash,
+            BH: $crate::BuildHasherFromFriend<C>,
+        {
+            fn eq(&self, other: &Self) -> bool {
+                let equal = self.0 == other.0;
+                $crate::contract::debug_assert_contract(equal, self, other);
+                equal
+            }
+        }
+
+        #[cfg(feature = "debug-assert")]
+        impl<C: Eq, BH> Eq for $any<C, BH>
+        where
+            for<'c> &'c C: IntoIterator,
+            for<'c> <&'c C as IntoIterator>::Item: ::std::hash::Hash,
+            BH: $crate::BuildHasherFromFriend<C>,
+        {
+        }
+
+        impl<C, H> From<C> for $any<C, H> {
+            /// Creates the wrapper
+            #[inline]
+            fn from(value: C) -> Self {
+                Self(value, ::std::marker::PhantomData)
+            }
+        }
+
+        impl<C, H> $any<C, H> {
+            /// Creates the wrapper
+            #[inline]
+            pub fn new(value: C) -> Self {
+                Self::from(value)
+            }
+
+            /// Destructures into the inner collection
+            #[inline]
+            pub fn into_inner(self) -> C {
+                self.0
+            }
+        }
+
+        impl<C: $crate::NewCollection, H> $any<C, H> {
+            /// Creates the wrapper around an empty collection, which will use the given hasher
+            #[inline]
+            pub fn with_hasher(hasher: C::Hasher) -> Self {
+                Self::from(C::with_hasher(hasher))
+            }
+
+            /// Creates the wrapper around an empty collection with at least the specified
+            /// capacity, which will use the given hasher
+            #[inline]
+            pub fn with_capacity_and_hasher(capacity: usize, hasher: C::Hasher) -> Self {
+                Self::from(C::with_capacity_and_hasher(capacity, hasher))
+            }
+
+            /// Creates the wrapper around an empty collection with at least the specified
+            /// capacity
+            #[inline]
+            pub fn with_capacity(capacity: usize) -> Self
+            where
+                C::Hasher: Default,
+            {
+                Self::with_capacity_and_hasher(capacity, Default::default())
+            }
+        }
+
+        impl<C, H> $any<C, H>
+        where
+            for<'c> &'c C: IntoIterator,
+            for<'c> <&'c C as IntoIterator>::Item: ::std::hash::Hash,
+        {
+            /// Computes the digest of the collection using hashers from `BH`, rather than the
+            /// configured hasher
+            #[inline]
+            pub fn digest_with<BH: $crate::BuildHasherFromFriend<C>>(&self) -> u64 {
+                $digest::<C, BH>(&self.0)
+            }
+        }
+
+        impl<C, BH> $any<C, BH>
+        where
+            for<'c> &'c C: IntoIterator,
+            for<'c> <&'c C as IntoIterator>::Item: ::std::hash::Hash,
+            BH: $crate::BuildHasherFromFriend<C>,
+        {
+            ///
+            /// Returns the canonical digest of the collection under the configured hasher
+            /// `BH`. This is exactly the value which the `Hash` implementation writes to the
+            /// outer hasher, and is suitable for storing directly without going through another
+            /// hasher.
+            ///
+            #[inline]
+            pub fn raw_unordered_hash(&self) -> u64 {
+                $digest::<C, BH>(&self.0)
+            }
+        }
+
+        #[cfg(not(feature = "debug-assert"))]
+        impl<C: $crate::ProvidesHasher + PartialEq> PartialEq for $provided<C> {
+            fn eq(&self, other: &Self) -> bool {
+                self.0 == other.0
+            }
+        }
+
+        #[cfg(not(feature = "debug-assert"))]
+        impl<C: $crate::ProvidesHasher + Eq> Eq for $provided<C> {}
+
+        #[cfg(feature = "debug-assert")]
+        impl<C: $crate::ProvidesHasher + PartialEq> PartialEq for $provided<C>
+        where
+            for<'c> &'c C: IntoIterator,
+            for<'c> <&'c C as IntoIterator>::Item: ::std::hash::Hash,
+        {
+            fn eq(&self, other: &Self) -> bool {
+                let equal = self.0 == other.0;
+                $crate::contract::debug_assert_contract(equal, self, other);
+                equal
+            }
+        }
+
+        #[cfg(feature = "debug-assert")]
+        impl<C: $crate::ProvidesHasher + Eq> Eq for $provided<C>
+        where
+            for<'c> &'c C: IntoIterator,
+            for<'c> <&'c C as IntoIterator>::Item: ::std::hash::Hash,
+        {
+        }
+
+        impl<C: $crate::ProvidesHasher> ::std::hash::Hash for $provided<C>
+        where
+            for<'c> &'c C: IntoIterator,
+            for<'c> <&'c C as IntoIterator>::Item: ::std::hash::Hash,
+        {
+            fn hash<H: ::std::hash::Hasher>(&self, state: &mut H) {
+                state.write_u64(self.raw_unordered_hash())
+            }
+        }
+
+        impl<C, BH> ::std::hash::Hash for $any<C, BH>
+        where
+            for<'c> &'c C: IntoIterator,
+            for<'c> <&'c C as IntoIterator>::Item: ::std::hash::Hash,
+            BH: $crate::BuildHasherFromFriend<C>,
+        {
+            fn hash<H: ::std::hash::Hasher>(&self, state: &mut H) {
+                state.write_u64(self.raw_unordered_hash())
+            }
+        }
+
+        impl<C: $crate::ProvidesHasher + IntoIterator> IntoIterator for $provided<C> {
+            type Item = <C as IntoIterator>::Item;
+            type IntoIter = <C as IntoIterator>::IntoIter;
+
+            fn into_iter(self) -> Self::IntoIter {
+                self.0.into_iter()
+            }
+        }
+
+        impl<C: IntoIterator, H> IntoIterator for $any<C, H> {
+            type Item = <C as IntoIterator>::Item;
+            type IntoIter = <C as IntoIterator>::IntoIter;
+
+            fn into_iter(self) -> Self::IntoIter {
+                self.0.into_iter()
+            }
+        }
+
+        impl<C: $crate::ProvidesHasher> ::std::ops::Deref for $provided<C> {
+            type Target = C;
+
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+
+        impl<C: $crate::ProvidesHasher> ::std::ops::DerefMut for $provided<C> {
+            fn deref_mut(&mut self) -> &mut Self::Target {
+                &mut self.0
+            }
+        }
+
+        impl<C, H> ::std::ops::Deref for $any<C, H> {
+            type Target = C;
+
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+
+        impl<C, H> ::std::ops::DerefMut for $any<C, H> {
+            fn deref_mut(&mut self) -> &mut Self::Target {
+                &mut self.0
+            }
+        }
+    };
+}
+
+pub(crate) use wrapper_impls;
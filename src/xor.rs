@@ -0,0 +1,138 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Wrappers which hash collections by XORing element hashes, rather than summing them
+
+use crate::{wrapper::wrapper_impls, xored_hash, ProvidesHasher, UseDefaultHasher};
+use std::marker::PhantomData;
+
+///
+/// Like [`crate::SumHashes`], but combines the hashes of elements by XOR, as
+/// [`crate::hash_by_xoring_hashes_with`] does. Elements are hashed using the wrapped
+/// collection's own hasher, subject to the same requirement of identically seeded hashers.
+///
+/// An element which appears twice cancels out, so a multiset with an element appearing twice
+/// hashes as if the element did not appear at all. This wrapper is only suitable for
+/// collections without duplicates, such as sets and maps.
+///
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(
+    feature = "bytemuck",
+    derive(bytemuck::TransparentWrapper),
+    transparent(C)
+)]
+#[repr(transparent)]
+pub struct XorHashes<C: ProvidesHasher>(pub(crate) C);
+
+///
+/// Like [`crate::SumHashesAnyCollection`], but combines the hashes of elements by XOR, as
+/// [`crate::hash_by_xoring_hashes_with`] does. Always usable with any collection, via the
+/// default hasher.
+///
+/// An element which appears twice cancels out, so a multiset with an element appearing twice
+/// hashes as if the element did not appear at all. **Do not use this wrapper with an ordered
+/// collection**, nor with one which may contain duplicates.
+///
+#[cfg_attr(
+    feature = "bytemuck",
+    derive(bytemuck::TransparentWrapper),
+    transparent(C)
+)]
+#[repr(transparent)]
+pub struct XorHashesAnyCollection<C, H = UseDefaultHasher>(pub(crate) C, PhantomData<H>);
+
+wrapper_impls!(XorHashes, XorHashesAnyCollection, xored_hash);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SumHashesAnyCollection;
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::{HashMap, HashSet};
+    use std::hash::{BuildHasherDefault, Hash, Hasher};
+
+    fn hash_one<T: Hash>(value: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn xoring_is_independent_of_order_but_differs_from_summing() {
+        let entries = vec![(4, "four"), (1, "one"), (-3, "minus three"), (20, "twenty")];
+        let mut shuffled = entries.clone();
+        shuffled.swap(0, 3);
+        shuffled.swap(1, 2);
+        let map: HashMap<i8, &str> = entries.iter().cloned().collect();
+        let set: HashSet<(i8, &str)> = entries.iter().cloned().collect();
+
+        let digest = XorHashesAnyCollection::<_, UseDefaultHasher>::new(entries.clone());
+        let digest = hash_one(&digest);
+        assert_eq!(
+            digest,
+            hash_one(&XorHashesAnyCollection::<_, UseDefaultHasher>::new(
+                shuffled
+            ))
+        );
+        assert_eq!(
+            digest,
+            hash_one(&XorHashesAnyCollection::<_, UseDefaultHasher>::new(map))
+        );
+        assert_eq!(
+            digest,
+            hash_one(&XorHashesAnyCollection::<_, UseDefaultHasher>::new(set))
+        );
+        assert_ne!(
+            digest,
+            hash_one(&SumHashesAnyCollection::<_, UseDefaultHasher>::new(entries))
+        );
+    }
+
+    #[test]
+    fn duplicates_cancel_out() {
+        let once = XorHashesAnyCollection::<Vec<u32>>::new(vec![1]);
+        let thrice = XorHashesAnyCollection::<Vec<u32>>::new(vec![1, 2, 2]);
+        let twice = XorHashesAnyCollection::<Vec<u32>>::new(vec![2, 2]);
+        assert_eq!(once.raw_unordered_hash(), thrice.raw_unordered_hash());
+        assert_eq!(0, twice.raw_unordered_hash());
+    }
+
+    #[test]
+    fn provided_hasher_and_incremental_maintenance() {
+        type Deterministic = BuildHasherDefault<DefaultHasher>;
+        let mut set: XorHashes<HashSet<u32, Deterministic>> = XorHashes::default();
+        set.extend([1, 2, 3]);
+        let before = set.raw_unordered_hash();
+
+        // Adding then removing an element's hash restores the digest, as XOR is its own inverse
+        set.insert(4);
+        assert_eq!(before ^ hash_one(&4_u32), set.raw_unordered_hash());
+        set.remove(&4);
+        assert_eq!(before, set.raw_unordered_hash());
+
+        let mut other: XorHashes<HashSet<u32, Deterministic>> = XorHashes::with_capacity(3);
+        other.extend([3, 2, 1]);
+        assert_eq!(set, other);
+        assert_eq!(hash_one(&set), hash_one(&other));
+        assert_eq!(
+            set.raw_unordered_hash(),
+            set.digest_with::<UseDefaultHasher>()
+        );
+        let mut elements: Vec<u32> = other.into_iter().collect();
+        elements.sort_unstable();
+        assert_eq!(vec![1, 2, 3], elements);
+    }
+}
@@ -0,0 +1,89 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! The allocator-parameterized variants, using a bump allocator
+
+#![cfg(feature = "nightly-allocator-api")]
+#![feature(allocator_api)]
+
+use hash_that_set::{
+    combine_sorted_in, reconciliation_digest, reconciliation_digest_in, HashCombiner,
+    SortedCombiner, UseDefaultHasher,
+};
+use std::alloc::{AllocError, Allocator, Layout};
+use std::cell::{Cell, UnsafeCell};
+use std::collections::HashSet;
+use std::ptr::NonNull;
+
+/// Hands out memory from a fixed arena, and never frees it
+struct Bump {
+    arena: Box<[UnsafeCell<u8>]>,
+    offset: Cell<usize>,
+    allocations: Cell<usize>,
+}
+
+impl Bump {
+    fn new(capacity: usize) -> Self {
+        Self {
+            arena: (0..capacity).map(|_| UnsafeCell::new(0)).collect(),
+            offset: Cell::new(0),
+            allocations: Cell::new(0),
+        }
+    }
+}
+
+unsafe impl Allocator for &Bump {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        let base = self.arena.as_ptr() as usize;
+        let start = (base + self.offset.get() + layout.align() - 1) & !(layout.align() - 1);
+        let end = start + layout.size();
+        if end > base + self.arena.len() {
+            return Err(AllocError);
+        }
+        self.offset.set(end - base);
+        self.allocations.set(self.allocations.get() + 1);
+        // The cells of the arena may be written through a shared reference
+        let ptr = (self.arena.as_ptr() as *mut u8).wrapping_add(start - base);
+        NonNull::new(std::ptr::slice_from_raw_parts_mut(ptr, layout.size())).ok_or(AllocError)
+    }
+
+    unsafe fn deallocate(&self, _: NonNull<u8>, _: Layout) {}
+}
+
+#[test]
+fn sorted_combination_matches_default_allocator() {
+    let bump = Bump::new(1 << 16);
+    for len in [0_u64, 1, 31, 32, 33, 500] {
+        let hashes = (0..len).map(|n| n.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+        assert_eq!(
+            SortedCombiner::combine(hashes.clone()),
+            combine_sorted_in(hashes, &bump)
+        );
+    }
+    assert!(bump.allocations.get() > 0);
+}
+
+#[test]
+fn reconciliation_digest_matches_default_allocator() {
+    let bump = Bump::new(1 << 16);
+    let set: HashSet<u32> = (0..1000).map(|n| n * 3).collect();
+    let digest = reconciliation_digest_in::<HashSet<u32>, UseDefaultHasher, _>(&set, &bump);
+    assert_eq!(
+        reconciliation_digest::<HashSet<u32>, UseDefaultHasher>(&set),
+        &digest[..]
+    );
+    assert_eq!(1, bump.allocations.get());
+}
@@ -0,0 +1,77 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Collision behavior of the combiners over generated workloads
+
+mod support;
+
+use hash_that_set::{HashCombiner, SortedCombiner, SumCombiner, XorCombiner};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use support::workloads::Workload;
+
+fn distinct_digests<Comb: HashCombiner>(inputs: &[Vec<u64>]) -> usize {
+    inputs
+        .iter()
+        .map(|input| {
+            Comb::combine(input.iter().map(|value| {
+                let mut hasher = DefaultHasher::new();
+                value.hash(&mut hasher);
+                hasher.finish()
+            }))
+        })
+        .collect::<HashSet<u64>>()
+        .len()
+}
+
+#[test]
+fn workloads_are_distinct_and_reproducible() {
+    for workload in [
+        Workload::Random,
+        Workload::NearLinear,
+        Workload::DuplicateHeavy,
+    ] {
+        let inputs = workload.generate(500, 8, 3);
+        assert_eq!(inputs, workload.generate(500, 8, 3));
+        let distinct: HashSet<&Vec<u64>> = inputs.iter().collect();
+        assert_eq!(inputs.len(), distinct.len());
+        assert!(inputs.iter().all(|input| input.len() == 8));
+    }
+}
+
+#[test]
+fn sum_and_sorted_do_not_collide() {
+    for workload in [
+        Workload::Random,
+        Workload::NearLinear,
+        Workload::DuplicateHeavy,
+    ] {
+        let inputs = workload.generate(2000, 8, 7);
+        assert_eq!(inputs.len(), distinct_digests::<SumCombiner>(&inputs));
+        assert_eq!(inputs.len(), distinct_digests::<SortedCombiner>(&inputs));
+    }
+}
+
+#[test]
+fn xor_collides_on_duplicates() {
+    let sets = Workload::Random.generate(2000, 8, 7);
+    assert_eq!(sets.len(), distinct_digests::<XorCombiner>(&sets));
+
+    // Only the parity of each of the 8 values' multiplicities is left
+    let multisets = Workload::DuplicateHeavy.generate(2000, 8, 7);
+    assert!(distinct_digests::<XorCombiner>(&multisets) <= 1 << 8);
+}
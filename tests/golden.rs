@@ -0,0 +1,76 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Checks the golden-vector corpus in `tests/golden/`
+//!
+//! The committed digests must match: a mismatch means a digest changed, which breaks
+//! compatibility with persisted digests and with reimplementations in other languages. Restore
+//! the previous behavior rather than regenerating the corpus. To add cases or digests, edit
+//! the `generate_golden` example and run `cargo run --example generate_golden --features xxh3`.
+
+mod support;
+
+use serde::de::DeserializeOwned;
+use std::hash::Hash;
+use support::golden::{digests, Corpus, FEATURE_DIGESTS};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::wasm_bindgen_test as test;
+
+fn check<T: Hash + DeserializeOwned>(json: &str) {
+    let corpus: Corpus<T> = serde_json::from_str(json).expect("the corpus is valid");
+    assert!(!corpus.cases.is_empty());
+    for case in corpus.cases {
+        let computed = digests(&case.elements);
+        for (name, expected) in &case.digests {
+            match computed.get(name) {
+                Some(digest) => assert_eq!(
+                    expected, digest,
+                    "{} digest of {} case {}",
+                    name, corpus.element_type, case.name
+                ),
+                None => assert!(
+                    FEATURE_DIGESTS.contains(&name.as_str()),
+                    "unknown digest {}",
+                    name
+                ),
+            }
+        }
+        for name in computed.keys() {
+            assert!(
+                case.digests.contains_key(name),
+                "{} digest of {} case {} is not in the corpus",
+                name,
+                corpus.element_type,
+                case.name
+            );
+        }
+    }
+}
+
+#[test]
+fn ints() {
+    check::<u64>(include_str!("golden/ints.json"));
+}
+
+#[test]
+fn strings() {
+    check::<String>(include_str!("golden/strings.json"));
+}
+
+#[test]
+fn pairs() {
+    check::<(u32, String)>(include_str!("golden/pairs.json"));
+}
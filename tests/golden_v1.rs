@@ -0,0 +1,117 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Golden vectors for `Algorithm::V1Sum64`.
+//!
+//! These values are frozen. If any of them changes, the algorithm has changed, which breaks
+//! every persisted digest: restore the previous behavior rather than updating the vectors. New
+//! behavior belongs in a new `Algorithm` variant with its own golden file.
+//!
+//! The same vectors must pass on every platform. On wasm32, run them with
+//! `cargo test --target wasm32-unknown-unknown --test golden_v1`, which requires
+//! `wasm-bindgen-test-runner` from wasm-bindgen-cli and Node.js.
+
+use hash_that_set::{hash_versioned, versioned_digest, Algorithm, StableHasher};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::Hasher;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::wasm_bindgen_test as test;
+
+const V1: Algorithm = Algorithm::V1Sum64;
+
+#[test]
+fn integers() {
+    assert_eq!(0, versioned_digest::<Vec<u64>>(&vec![], V1));
+    assert_eq!(
+        0xc0fe_eac8_cc39_82e9,
+        versioned_digest::<Vec<u64>>(&vec![1, 2, 3], V1)
+    );
+    assert_eq!(
+        0xc0fe_eac8_cc39_82e9,
+        versioned_digest::<Vec<u64>>(&vec![3, 1, 2], V1)
+    );
+    assert_eq!(
+        0xee27_eb9d_b11a_b3fa,
+        versioned_digest::<Vec<i8>>(&vec![-1, 0, i8::MIN], V1)
+    );
+    assert_eq!(
+        0xf656_7329_8cb8_b23d,
+        versioned_digest::<Vec<u32>>(&vec![7, 0xdead_beef], V1)
+    );
+    // usize is widened to 64 bits, so this is the same on 32-bit platforms
+    assert_eq!(
+        0xdebd_8cf0_9503_5366,
+        versioned_digest::<Vec<usize>>(&vec![0, 1, 1 << 31], V1)
+    );
+}
+
+#[test]
+fn strings() {
+    let set: HashSet<&str> = ["apple", "banana", "cherry"].into_iter().collect();
+    assert_eq!(
+        0xfb8f_5c6b_3e31_fb9e,
+        versioned_digest::<HashSet<&str>>(&set, V1)
+    );
+    assert_eq!(
+        0x3040_6ea5_23c5_3def,
+        versioned_digest::<Vec<&str>>(&vec![""], V1)
+    );
+}
+
+#[test]
+fn maps_and_compound_elements() {
+    let map: HashMap<String, u16> = [(String::from("one"), 1), (String::from("two"), 2)]
+        .into_iter()
+        .collect();
+    assert_eq!(
+        0x5a4e_ac0a_5bb1_2135,
+        versioned_digest::<HashMap<String, u16>>(&map, V1)
+    );
+
+    let tuples = vec![(1_u8, 'x', true), (2, 'y', false)];
+    assert_eq!(
+        0x004f_27d7_a9ee_8c16,
+        versioned_digest::<Vec<(u8, char, bool)>>(&tuples, V1)
+    );
+
+    let tree: BTreeMap<i64, Option<u128>> =
+        [(-5, None), (5, Some(u128::MAX))].into_iter().collect();
+    assert_eq!(
+        0xc1b9_31c7_74e3_e9d0,
+        versioned_digest::<BTreeMap<i64, Option<u128>>>(&tree, V1)
+    );
+}
+
+// The standard library hashes a slice of integers as its raw memory, in native byte order and
+// width, so such elements are only portable between targets of the same endianness
+#[cfg(target_endian = "little")]
+#[test]
+fn integer_slices_on_little_endian() {
+    let nested = vec![vec![1_u64, 2], vec![], vec![3]];
+    assert_eq!(
+        0xac5d_b011_a0fd_260a,
+        versioned_digest::<Vec<Vec<u64>>>(&nested, V1)
+    );
+}
+
+#[test]
+fn written_digest() {
+    let mut hasher = StableHasher::new();
+    hash_versioned::<Vec<u64>, _>(&vec![1, 2, 3], V1, &mut hasher);
+    let mut expected = StableHasher::new();
+    expected.write_u64(0xc0fe_eac8_cc39_82e9);
+    assert_eq!(expected.finish(), hasher.finish());
+}
@@ -0,0 +1,87 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Tests keyed off the target's endianness and pointer width.
+//!
+//! These are regular tests, meant for running on each kind of target, such as big-endian s390x
+//! or powerpc and 32-bit ARM, under `cross`, QEMU, or Miri with `--target`. The versioned
+//! algorithm must give the same digests on every target for portable element types, whereas
+//! the unversioned functions are expected to differ on targets other than 64-bit little-endian
+//! ones.
+
+use hash_that_set::{hash_by_summing_hashes, versioned_digest, Algorithm};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hasher;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::wasm_bindgen_test as test;
+
+const V1: Algorithm = Algorithm::V1Sum64;
+
+#[test]
+fn portable_elements() {
+    let integers = vec![(1_usize, -2_isize, 3_u16), (1 << 31, -(1 << 30), u16::MAX)];
+    assert_eq!(
+        0x51b8_a25b_bd08_085f,
+        versioned_digest::<Vec<(usize, isize, u16)>>(&integers, V1)
+    );
+    let options = vec![None, Some(7_usize), Some(0)];
+    assert_eq!(
+        0xdc10_da15_f932_1588,
+        versioned_digest::<Vec<Option<usize>>>(&options, V1)
+    );
+    let strings = vec![String::from("portable"), String::new()];
+    assert_eq!(
+        0x2d60_34a9_e48d_69cf,
+        versioned_digest::<Vec<String>>(&strings, V1)
+    );
+}
+
+#[test]
+fn integer_slices_follow_the_target() {
+    // Hashed as raw memory by the standard library, so not portable
+    let nested = vec![vec![1_u16, 2, 3], vec![]];
+    let digest = versioned_digest::<Vec<Vec<u16>>>(&nested, V1);
+    if cfg!(target_endian = "little") {
+        assert_eq!(0x9d8f_0cad_8a8f_ba8a, digest);
+    } else {
+        assert_ne!(0x9d8f_0cad_8a8f_ba8a, digest);
+    }
+}
+
+/// Returns what the unversioned function writes for a collection of `usize`s, and what it would
+/// write if it agreed with the versioned algorithm
+fn unversioned_and_versioned() -> (u64, u64) {
+    let values = vec![1_usize, 2, 1 << 31];
+    let mut unversioned = DefaultHasher::new();
+    hash_by_summing_hashes::<Vec<usize>, _>(&values, &mut unversioned);
+    let mut versioned = DefaultHasher::new();
+    versioned.write_u64(versioned_digest::<Vec<usize>>(&values, V1));
+    (unversioned.finish(), versioned.finish())
+}
+
+#[cfg(all(target_endian = "little", target_pointer_width = "64"))]
+#[test]
+fn unversioned_agrees_on_64_bit_little_endian() {
+    let (unversioned, versioned) = unversioned_and_versioned();
+    assert_eq!(versioned, unversioned);
+}
+
+#[cfg(not(all(target_endian = "little", target_pointer_width = "64")))]
+#[test]
+fn unversioned_differs_elsewhere() {
+    let (unversioned, versioned) = unversioned_and_versioned();
+    assert_ne!(versioned, unversioned);
+}
@@ -0,0 +1,203 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Property tests of permutation and rebuild invariance, over every strategy and combiner
+//!
+//! To cover a new strategy or combiner, add one line to [`for_each_strategy`] or
+//! [`for_each_combiner`].
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use hash_that_set::{
+    hash_by_summing_hashes, hash_by_summing_hashes_with, precompute_contributions,
+    BuildHasherFromFriend, Domain, DomainTag, HashCombiner, PrecomputedHashes, SortedCombiner,
+    SortedSumHashes, SumCombiner, SumHashes, SumHashesAnyCollection, Unordered, UseDefaultHasher,
+    UseIdentityMix, UsePrehashed, UseProvidedHasher, UseStableHasher, UseTaggedHasher, XorCombiner,
+};
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::test_runner::TestCaseError;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{BuildHasherDefault, Hash, Hasher};
+
+type Fixed = BuildHasherDefault<DefaultHasher>;
+
+struct Properties;
+
+impl Domain for Properties {
+    const TAG: DomainTag = DomainTag::new("properties");
+}
+
+/// Calls `$property::<BH>(name, args..)?` for every built-in per-element strategy
+macro_rules! for_each_strategy {
+    ($property:ident($($arg:expr),*)) => {{
+        $property::<UseDefaultHasher>("default", $($arg),*)?;
+        $property::<UseStableHasher>("stable", $($arg),*)?;
+        $property::<UseIdentityMix>("identity_mix", $($arg),*)?;
+        $property::<UsePrehashed>("prehashed", $($arg),*)?;
+        $property::<UseTaggedHasher<Properties>>("tagged", $($arg),*)?;
+        #[cfg(feature = "xxh3")]
+        $property::<hash_that_set::UseXxh3>("xxh3", $($arg),*)?;
+    }};
+}
+
+/// Calls `$property::<Comb>(name, args..)?` for every built-in combiner
+macro_rules! for_each_combiner {
+    ($property:ident($($arg:expr),*)) => {{
+        $property::<SumCombiner>("sum", $($arg),*)?;
+        $property::<XorCombiner>("xor", $($arg),*)?;
+        $property::<SortedCombiner>("sorted", $($arg),*)?;
+    }};
+}
+
+fn digest<C, BH>(collection: &C) -> u64
+where
+    for<'c> &'c C: IntoIterator,
+    for<'c> <&'c C as IntoIterator>::Item: Hash,
+    BH: BuildHasherFromFriend<C>,
+{
+    let mut hasher = DefaultHasher::new();
+    hash_by_summing_hashes_with::<C, _, BH>(collection, &mut hasher);
+    hasher.finish()
+}
+
+fn hash_one<T: Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// A vector of values, and a permutation of it
+fn permuted_values() -> impl Strategy<Value = (Vec<u16>, Vec<u16>)> {
+    vec(any::<u16>(), 0..48)
+        .prop_flat_map(|values| (Just(values.clone()), Just(values).prop_shuffle()))
+}
+
+fn rebuilds_agree<BH>(name: &str, values: &[u16], permuted: &[u16]) -> Result<(), TestCaseError>
+where
+    BH: BuildHasherFromFriend<Vec<u16>>
+        + BuildHasherFromFriend<HashSet<u16>>
+        + BuildHasherFromFriend<HashMap<u16, u16>>,
+{
+    let (values, permuted) = (values.to_vec(), permuted.to_vec());
+    prop_assert_eq!(
+        digest::<Vec<u16>, BH>(&values),
+        digest::<Vec<u16>, BH>(&permuted),
+        "multiset under {}",
+        name
+    );
+
+    let set: HashSet<u16> = values.iter().copied().collect();
+    let rebuilt: HashSet<u16> = permuted.iter().copied().collect();
+    prop_assert_eq!(
+        digest::<HashSet<u16>, BH>(&set),
+        digest::<HashSet<u16>, BH>(&rebuilt),
+        "set under {}",
+        name
+    );
+
+    let map: HashMap<u16, u16> = values.iter().map(|value| (*value, value / 3)).collect();
+    let rebuilt: HashMap<u16, u16> = permuted.iter().map(|value| (*value, value / 3)).collect();
+    prop_assert_eq!(
+        digest::<HashMap<u16, u16>, BH>(&map),
+        digest::<HashMap<u16, u16>, BH>(&rebuilt),
+        "map under {}",
+        name
+    );
+    Ok(())
+}
+
+fn wrapper_agrees<BH>(name: &str, values: &[u16]) -> Result<(), TestCaseError>
+where
+    BH: BuildHasherFromFriend<Vec<u16>>,
+{
+    let values = values.to_vec();
+    let expected = digest::<Vec<u16>, BH>(&values);
+    let wrapped = SumHashesAnyCollection::<Vec<u16>, BH>::new(values);
+    prop_assert_eq!(expected, hash_one(&wrapped), "wrapper under {}", name);
+    Ok(())
+}
+
+fn combiner_ignores_order<Comb: HashCombiner>(
+    name: &str,
+    values: &[u16],
+    permuted: &[u16],
+) -> Result<(), TestCaseError> {
+    let precomputed: PrecomputedHashes<u16> = values.iter().copied().collect();
+    let reordered: PrecomputedHashes<u16> = permuted.iter().copied().collect();
+    prop_assert_eq!(
+        precomputed.recombine_with::<Comb>(),
+        reordered.recombine_with::<Comb>(),
+        "combiner {}",
+        name
+    );
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn permutations_and_rebuilds_hash_equally((values, permuted) in permuted_values()) {
+        for_each_strategy!(rebuilds_agree(&values, &permuted));
+        for_each_combiner!(combiner_ignores_order(&values, &permuted));
+
+        let fixed: HashSet<u16, Fixed> = values.iter().copied().collect();
+        let rebuilt: HashSet<u16, Fixed> = permuted.iter().copied().collect();
+        prop_assert_eq!(
+            digest::<HashSet<u16, Fixed>, UseProvidedHasher<HashSet<u16, Fixed>>>(&fixed),
+            digest::<HashSet<u16, Fixed>, UseProvidedHasher<HashSet<u16, Fixed>>>(&rebuilt)
+        );
+    }
+
+    #[test]
+    fn insert_then_remove_restores_digest(values in vec(any::<u16>(), 0..48), extra in any::<u16>()) {
+        let mut wrapped = SumHashes::new(values.iter().copied().collect::<HashSet<u16, Fixed>>());
+        let mut sorted: SortedSumHashes<u16> = values.iter().copied().collect();
+        let map: HashMap<u16, u16> = values.iter().map(|value| (*value, 0)).collect();
+        let mut index = precompute_contributions::<HashMap<u16, u16>, UseDefaultHasher, _, _>(&map);
+        let before = (hash_one(&wrapped), sorted.digest(), index.digest());
+
+        let inserted = wrapped.insert(extra);
+        prop_assert_eq!(inserted, sorted.insert(extra));
+        let previous = index.update(extra, 0x1234);
+        if inserted {
+            wrapped.remove(&extra);
+            sorted.remove(&extra);
+            index.remove(&extra);
+        } else if let Some(previous) = previous {
+            index.update(extra, previous);
+        }
+        prop_assert_eq!(before, (hash_one(&wrapped), sorted.digest(), index.digest()));
+    }
+
+    #[test]
+    fn wrappers_agree_with_free_functions(values in vec(any::<u16>(), 0..48)) {
+        for_each_strategy!(wrapper_agrees(&values));
+
+        let set: HashSet<u16, Fixed> = values.iter().copied().collect();
+        let expected = digest::<HashSet<u16, Fixed>, UseProvidedHasher<HashSet<u16, Fixed>>>(&set);
+        prop_assert_eq!(expected, hash_one(&SumHashes::new(set.clone())));
+
+        let mut free = DefaultHasher::new();
+        hash_by_summing_hashes::<HashSet<u16, Fixed>, _>(&set, &mut free);
+        let sorted: SortedSumHashes<u16> = set.iter().copied().collect();
+        prop_assert_eq!(free.finish(), hash_one(&sorted));
+
+        let mut free = DefaultHasher::new();
+        hash_by_summing_hashes::<Vec<u16>, _>(&values, &mut free);
+        prop_assert_eq!(free.finish(), hash_one(&Unordered::new(values.clone())));
+    }
+}
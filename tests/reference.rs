@@ -0,0 +1,180 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Differential tests of every digest-producing function against the naive reference in
+//! `support::reference`, on randomized inputs
+
+#![cfg(not(target_arch = "wasm32"))]
+
+mod support;
+
+use hash_that_set::{
+    delta_hash, dual_combine, hash_as_u64, hash_btreemap_keys_as_set, hash_by_summing_hashes,
+    hash_by_summing_parallel_scoped, hash_checked, hash_distribution, hash_iter_ref,
+    hash_prefix_unordered, hash_segmented, hash_time_weighted, hash_try_convert,
+    merge_partial_hashes, multiset_eq, precompute_contributions, reconciliation_digest,
+    PartialHash, PrecomputedHashes, SortedSumHashes, SumCombiner, SumHashesAnyCollection,
+    UseDefaultHasher,
+};
+use proptest::collection::vec;
+use proptest::prelude::*;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use support::reference;
+
+fn written<F: FnOnce(&mut DefaultHasher)>(write: F) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    write(&mut hasher);
+    hasher.finish()
+}
+
+fn hash_one<T: Hash>(value: &T) -> u64 {
+    written(|hasher| value.hash(hasher))
+}
+
+/// Values drawn from a small range, so that duplicates are common
+fn values() -> impl Strategy<Value = Vec<u16>> {
+    vec(0_u16..512, 0..64)
+}
+
+proptest! {
+    #[test]
+    fn summed_digests(values in values()) {
+        let expected = reference::digest(&values);
+        let raw = reference::raw_digest(&values);
+
+        prop_assert_eq!(expected, written(|h| hash_by_summing_hashes::<Vec<u16>, _>(&values, h)));
+        prop_assert_eq!(expected, written(|h| hash_iter_ref(&values, h)));
+        prop_assert_eq!(
+            reference::digest(values.iter().map(|value| u64::from(*value))),
+            written(|h| hash_as_u64::<Vec<u16>, _, _>(&values, h))
+        );
+        prop_assert_eq!(expected, written(|h| {
+            hash_by_summing_parallel_scoped::<Vec<u16>, _>(&values, h, 4)
+        }));
+
+        let wrapped = SumHashesAnyCollection::<Vec<u16>>::new(values.clone());
+        prop_assert_eq!(raw, wrapped.raw_unordered_hash());
+        prop_assert_eq!(expected, hash_one(&wrapped));
+
+        prop_assert_eq!(raw, PartialHash::of::<Vec<u16>>(&values).sum);
+        prop_assert_eq!(raw, dual_combine::<Vec<u16>, UseDefaultHasher>(&values).0);
+        prop_assert_eq!(raw, delta_hash::<Vec<u16>>(&values, 0, 0).hash());
+        prop_assert_eq!(Ok(raw), hash_checked::<Vec<u16>, UseDefaultHasher, _>(&values, |_| true));
+        prop_assert_eq!(raw, values.iter().copied().collect::<PrecomputedHashes<u16>>()
+            .recombine_with::<SumCombiner>());
+        prop_assert_eq!(
+            reference::raw_digest(values.iter().collect::<HashSet<_>>()),
+            values.iter().copied().collect::<SortedSumHashes<u16>>().digest()
+        );
+    }
+
+    #[test]
+    fn converted_and_weighted_digests(values in values()) {
+        let expected = reference::digest(values.iter().map(|value| u32::from(*value) * 3));
+        let converted = written(|h| {
+            hash_try_convert::<Vec<u16>, _, _, (), _>(&values, h, |value| Ok(u32::from(**value) * 3))
+                .unwrap()
+        });
+        prop_assert_eq!(expected, converted);
+
+        // With no decay, every weight is exactly one
+        let weighted = written(|h| hash_time_weighted::<Vec<u16>, _, _>(&values, h, 0, 0.0, |_| 0));
+        prop_assert_eq!(reference::digest(&values), weighted);
+    }
+
+    #[test]
+    fn shard_digests_merge(values in values(), split in any::<prop::sample::Index>()) {
+        let split = split.index(values.len() + 1);
+        let (left, right) = values.split_at(split);
+        let partials = [
+            PartialHash::of::<Vec<u16>>(&left.to_vec()),
+            PartialHash::of::<Vec<u16>>(&right.to_vec()),
+        ];
+        prop_assert_eq!(reference::raw_digest(&values), merge_partial_hashes(&partials));
+    }
+
+    #[test]
+    fn filtered_and_prefix_digests(values in values(), n in 0_usize..80) {
+        // Pairs with a count of zero are filtered out
+        let pairs: Vec<(u16, u64)> = values
+            .iter()
+            .map(|value| (*value, u64::from(*value % 3 != 0)))
+            .collect();
+        let expected = reference::filtered_digest(
+            values.iter().map(|value| (*value, 1_u64)),
+            |(value, _)| *value % 3 != 0,
+        );
+        let distribution = written(|h| {
+            hash_distribution::<Vec<(u16, u64)>, _, _, _, _>(&pairs, h, |pair| pair.0, |pair| pair.1)
+        });
+        prop_assert_eq!(expected, distribution);
+
+        let lowest = reference::sorted_hashes(&values);
+        let prefix = hash_prefix_unordered::<Vec<u16>, UseDefaultHasher>(&values, n);
+        prop_assert_eq!(reference::sum(&lowest[..n.min(lowest.len())]), prefix);
+
+        let mut deduplicated = lowest;
+        deduplicated.dedup();
+        prop_assert_eq!(deduplicated, reconciliation_digest::<Vec<u16>, UseDefaultHasher>(&values));
+    }
+
+    #[test]
+    fn keys_only_and_map_digests(entries in vec((0_u16..512, any::<u8>()), 0..64)) {
+        let tree: BTreeMap<u16, u8> = entries.iter().copied().collect();
+        prop_assert_eq!(
+            reference::keys_digest(&tree),
+            written(|h| hash_btreemap_keys_as_set(&tree, h))
+        );
+
+        let map: HashMap<u16, u8> = entries.iter().copied().collect();
+        let index = precompute_contributions::<HashMap<u16, u8>, UseDefaultHasher, _, _>(&map);
+        prop_assert_eq!(reference::raw_digest(&map), index.digest());
+    }
+
+    #[test]
+    fn nested_digests(outer in vec(values(), 0..8)) {
+        prop_assert_eq!(
+            reference::digest(outer.iter().flatten()),
+            written(|h| hash_segmented::<Vec<Vec<u16>>, _>(&outer, h))
+        );
+
+        let nested: Vec<SumHashesAnyCollection<Vec<u16>>> = outer
+            .iter()
+            .cloned()
+            .map(SumHashesAnyCollection::new)
+            .collect();
+        prop_assert_eq!(
+            reference::nested_digest(&outer),
+            written(|h| hash_by_summing_hashes::<Vec<SumHashesAnyCollection<Vec<u16>>>, _>(&nested, h))
+        );
+    }
+
+    #[test]
+    fn multiset_equality(a in values(), b in values(), shuffle_seed in any::<u64>()) {
+        prop_assert_eq!(reference::multiset_eq(&a, &b), multiset_eq(&a, &b));
+
+        let mut permuted = a.clone();
+        let mut state = shuffle_seed;
+        for index in (1..permuted.len()).rev() {
+            state = state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+            permuted.swap(index, (state >> 33) as usize % (index + 1));
+        }
+        prop_assert!(reference::multiset_eq(&a, &permuted));
+        prop_assert!(multiset_eq(&a, &permuted));
+    }
+}
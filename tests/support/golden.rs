@@ -0,0 +1,97 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! The golden-vector corpus in `tests/golden/`, and the digests it records
+//!
+//! Both `tests/golden.rs` and the `generate_golden` example compute digests with [`digests`],
+//! so that the corpus can be regenerated deliberately after adding a case or a digest.
+
+#![allow(dead_code)]
+
+use hash_that_set::{
+    Domain, DomainTag, PartialHash, PrecomputedHashes, StableHasher, SumCombiner, UseIdentityMix,
+    UseStableHasher, UseTaggedHasher, XorCombiner,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::hash::{BuildHasherDefault, Hash};
+
+/// One fixture file: cases of collections of a single element type
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Corpus<T> {
+    pub element_type: String,
+    pub cases: Vec<Case<T>>,
+}
+
+/// A collection, and its digests keyed by the names used in [`digests`]
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Case<T> {
+    pub name: String,
+    pub elements: Vec<T>,
+    pub digests: BTreeMap<String, String>,
+}
+
+/// The domain of the `tagged_stable` digest, whose tag is the FNV-1a hash of "golden"
+pub struct Golden;
+
+impl Domain for Golden {
+    const TAG: DomainTag = DomainTag::new("golden");
+}
+
+/// Digests which only exist with a feature enabled, and may be absent when checking
+pub const FEATURE_DIGESTS: [&str; 1] = ["xxh3"];
+
+fn hex(digest: u64) -> String {
+    format!("{:#018x}", digest)
+}
+
+///
+/// Computes the recorded digests of a collection. Every digest is portable: each element is hashed by a
+/// hasher whose output is the same on every platform, and the raw 64-bit result is recorded,
+/// so that reimplementations need not reproduce a `Hasher` to write it to.
+///
+/// The sorted combiner is not recorded, because it hashes the sorted element hashes with
+/// `DefaultHasher`, which writes them in native byte order.
+///
+pub fn digests<T: Hash>(elements: &[T]) -> BTreeMap<String, String> {
+    let elements = elements.iter().collect::<Vec<&T>>();
+    let stable =
+        PrecomputedHashes::new_with(&elements, &BuildHasherDefault::<StableHasher>::default());
+    let mut digests = BTreeMap::new();
+    let mut record = |name: &str, digest: u64| {
+        digests.insert(name.to_owned(), hex(digest));
+    };
+    record(
+        "v1_sum64",
+        PartialHash::of_with::<Vec<&T>, UseStableHasher>(&elements).sum,
+    );
+    record(
+        "identity_mix",
+        PartialHash::of_with::<Vec<&T>, UseIdentityMix>(&elements).sum,
+    );
+    record(
+        "tagged_stable",
+        PartialHash::of_with::<Vec<&T>, UseTaggedHasher<Golden, UseStableHasher>>(&elements).sum,
+    );
+    #[cfg(feature = "xxh3")]
+    record(
+        "xxh3",
+        PartialHash::of_with::<Vec<&T>, hash_that_set::UseXxh3>(&elements).sum,
+    );
+    record("sum", stable.recombine_with::<SumCombiner>());
+    record("xor", stable.recombine_with::<XorCombiner>());
+    digests
+}
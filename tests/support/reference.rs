@@ -0,0 +1,120 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! A naive reference implementation of the specification of the digests
+//!
+//! Every digest of this crate which sums element hashes is specified here, as plainly as
+//! possible, and checked against by `tests/reference.rs`. New digest-producing functions must
+//! add their mode here, and a check there.
+
+#![allow(dead_code)]
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// The hash of one element, by a new [`DefaultHasher`]
+pub fn element_hash<T: Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// The element hashes, sorted, which identifies the collection as a multiset of hashes
+pub fn sorted_hashes<I>(values: I) -> Vec<u64>
+where
+    I: IntoIterator,
+    I::Item: Hash,
+{
+    let mut hashes: Vec<u64> = values
+        .into_iter()
+        .map(|value| element_hash(&value))
+        .collect();
+    hashes.sort_unstable();
+    hashes
+}
+
+/// The sum of hashes, computed exactly, then truncated to 64 bits
+pub fn sum(hashes: &[u64]) -> u64 {
+    let exact: u128 = hashes.iter().map(|hash| u128::from(*hash)).sum();
+    exact as u64
+}
+
+/// The raw digest: the sum of the element hashes
+pub fn raw_digest<I>(values: I) -> u64
+where
+    I: IntoIterator,
+    I::Item: Hash,
+{
+    sum(&sorted_hashes(values))
+}
+
+/// The digest as written to a hasher: the raw digest, written as a `u64` to a [`DefaultHasher`]
+pub fn digest<I>(values: I) -> u64
+where
+    I: IntoIterator,
+    I::Item: Hash,
+{
+    finish_raw(raw_digest(values))
+}
+
+/// Writes a raw digest to a new [`DefaultHasher`]
+pub fn finish_raw(raw: u64) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    hasher.write_u64(raw);
+    hasher.finish()
+}
+
+/// Whether two collections are equal as multisets, by their sorted element hashes
+pub fn multiset_eq<A, B>(a: A, b: B) -> bool
+where
+    A: IntoIterator,
+    A::Item: Hash,
+    B: IntoIterator,
+    B::Item: Hash,
+{
+    sorted_hashes(a) == sorted_hashes(b)
+}
+
+/// Keys-only mode: the digest of the keys, ignoring the values
+pub fn keys_digest<'m, K: Hash + 'm, V: 'm>(
+    entries: impl IntoIterator<Item = (&'m K, &'m V)>,
+) -> u64 {
+    digest(entries.into_iter().map(|(key, _)| key))
+}
+
+/// Filtered mode: the digest of the elements which satisfy `keep`
+pub fn filtered_digest<I, F>(values: I, keep: F) -> u64
+where
+    I: IntoIterator,
+    I::Item: Hash,
+    F: Fn(&I::Item) -> bool,
+{
+    digest(values.into_iter().filter(|value| keep(value)))
+}
+
+/// Nested mode: each inner collection is an element, hashed as the digest of its own elements
+pub fn nested_digest<O, I>(outer: O) -> u64
+where
+    O: IntoIterator<Item = I>,
+    I: IntoIterator,
+    I::Item: Hash,
+{
+    let inner_hashes: Vec<u64> = outer
+        .into_iter()
+        .map(|inner| element_hash(&raw_digest(inner)))
+        .collect();
+    finish_raw(sum(&inner_hashes))
+}
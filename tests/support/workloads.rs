@@ -0,0 +1,108 @@
+/*
+ * Copyright © 2023 Anand Beh
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Deterministic workload generation, shared by the tests and the collision example
+
+#![allow(dead_code)]
+
+use std::collections::HashSet;
+
+///
+/// A splitmix64 generator, so that workloads are reproducible without a dependency
+///
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a value below `bound`, which must be nonzero
+    pub fn below(&mut self, bound: u64) -> u64 {
+        ((u128::from(self.next_u64()) * u128::from(bound)) >> 64) as u64
+    }
+}
+
+/// The kinds of workload, paired with a short name for reports
+pub const KINDS: [(Workload, &str); 3] = [
+    (Workload::Random, "random sets"),
+    (Workload::NearLinear, "near-linear sets"),
+    (Workload::DuplicateHeavy, "duplicate-heavy multisets"),
+];
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Workload {
+    /// Sets of uniformly random values
+    Random,
+    /// Runs of consecutive values, with one value displaced by a small offset. Neighbouring
+    /// inputs differ only slightly, and many pairs have equal sums of values.
+    NearLinear,
+    /// Multisets drawn from an alphabet of 8 values, so most values repeat
+    DuplicateHeavy,
+}
+
+impl Workload {
+    ///
+    /// Generates `count` distinct inputs of `size` values each. Every input is sorted, which is
+    /// its canonical form as a set or multiset. Fewer inputs are returned if the workload has
+    /// fewer than `count` distinct ones.
+    ///
+    pub fn generate(self, count: usize, size: usize, seed: u64) -> Vec<Vec<u64>> {
+        let mut rng = Rng::new(seed);
+        let mut seen = HashSet::with_capacity(count);
+        let mut inputs = Vec::with_capacity(count);
+        // Bound the attempts, since small workloads may run out of distinct inputs
+        let mut attempts = count.saturating_mul(4);
+        while inputs.len() < count && attempts > 0 {
+            attempts -= 1;
+            let mut input = self.generate_one(&mut rng, size);
+            input.sort_unstable();
+            if seen.insert(input.clone()) {
+                inputs.push(input);
+            }
+        }
+        inputs
+    }
+
+    fn generate_one(self, rng: &mut Rng, size: usize) -> Vec<u64> {
+        match self {
+            Workload::Random => {
+                let mut set = HashSet::with_capacity(size);
+                while set.len() < size {
+                    set.insert(rng.next_u64());
+                }
+                set.into_iter().collect()
+            }
+            Workload::NearLinear => {
+                let start = rng.below(1 << 20);
+                let mut run: Vec<u64> = (start..start + size as u64).collect();
+                if let Some(last) = run.last_mut() {
+                    *last += 1 + rng.below(4);
+                }
+                run
+            }
+            Workload::DuplicateHeavy => (0..size).map(|_| rng.below(8)).collect(),
+        }
+    }
+}